@@ -0,0 +1,194 @@
+pub const DEFAULT_PORT: u16 = 33331;
+pub const TOKEN_ENV: &str = "CLASH_VERGE_API_TOKEN";
+pub const PORT_ENV: &str = "CLASH_VERGE_API_PORT";
+
+pub const USAGE: &str = "\
+Usage: clash-verge-cli [--port <port>] [--token <token>] <command>
+
+Commands:
+  status                          运行状态、当前订阅与端口
+  profile list                    列出订阅
+  profile use <uid|name>          切换订阅
+  profile update [uid|name]       更新订阅，默认为当前订阅
+  mode rule|global|direct         切换代理模式
+  proxy on|off                    开关系统代理
+  tun on|off                      开关 TUN 模式
+  select <group> <node>           为策略组选择节点
+  logs [--follow] [--level <levels>] [--limit <n>]
+                                  读取内核日志，--follow 时每行输出一条
+
+Options:
+  --port <port>    单例服务端口，也可通过 CLASH_VERGE_API_PORT 指定
+  --token <token>  自动化 API 令牌，也可通过 CLASH_VERGE_API_TOKEN 指定
+";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Status,
+    ProfileList,
+    ProfileUse(String),
+    ProfileUpdate(Option<String>),
+    Mode(String),
+    Proxy(bool),
+    Tun(bool),
+    Select { group: String, node: String },
+    Logs(LogsArgs),
+    Help,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LogsArgs {
+    pub follow: bool,
+    pub level: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cli {
+    pub port: Option<u16>,
+    pub token: Option<String>,
+    pub command: Command,
+}
+
+/// 解析命令行参数，不含程序名
+pub fn parse<I>(args: I) -> Result<Cli, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut port = None;
+    let mut token = None;
+    let mut rest = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                let value = args.next().ok_or("--port requires a value")?;
+                port = Some(value.parse().map_err(|_| format!("invalid port `{value}`"))?);
+            }
+            "--token" => token = Some(args.next().ok_or("--token requires a value")?),
+            "-h" | "--help" => {
+                return Ok(Cli {
+                    port,
+                    token,
+                    command: Command::Help,
+                });
+            }
+            _ => rest.push(arg),
+        }
+    }
+
+    Ok(Cli {
+        port,
+        token,
+        command: parse_command(&rest)?,
+    })
+}
+
+fn parse_command(args: &[String]) -> Result<Command, String> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let command = match args.as_slice() {
+        [] | ["help"] => Command::Help,
+        ["status"] => Command::Status,
+        ["profile", "list"] => Command::ProfileList,
+        ["profile", "use", profile] => Command::ProfileUse((*profile).into()),
+        ["profile", "update"] => Command::ProfileUpdate(None),
+        ["profile", "update", profile] => Command::ProfileUpdate(Some((*profile).into())),
+        ["mode", mode @ ("rule" | "global" | "direct")] => Command::Mode((*mode).into()),
+        ["proxy", state] => Command::Proxy(parse_switch(state)?),
+        ["tun", state] => Command::Tun(parse_switch(state)?),
+        ["select", group, node] => Command::Select {
+            group: (*group).into(),
+            node: (*node).into(),
+        },
+        ["logs", options @ ..] => Command::Logs(parse_logs(options)?),
+        _ => return Err(format!("unknown command `{}`", args.join(" "))),
+    };
+    Ok(command)
+}
+
+fn parse_switch(state: &str) -> Result<bool, String> {
+    match state {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("expected `on` or `off`, got `{state}`")),
+    }
+}
+
+fn parse_logs(options: &[&str]) -> Result<LogsArgs, String> {
+    let mut logs = LogsArgs::default();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match *option {
+            "-f" | "--follow" => logs.follow = true,
+            "--level" => logs.level = Some((*options.next().ok_or("--level requires a value")?).into()),
+            "--limit" => {
+                let value = options.next().ok_or("--limit requires a value")?;
+                logs.limit = Some(value.parse().map_err(|_| format!("invalid limit `{value}`"))?);
+            }
+            _ => return Err(format!("unknown logs option `{option}`")),
+        }
+    }
+    Ok(logs)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{Cli, Command, LogsArgs, parse};
+
+    fn parse_args(args: &str) -> Result<Cli, String> {
+        parse(args.split_whitespace().map(String::from))
+    }
+
+    fn command(args: &str) -> Command {
+        parse_args(args).expect("valid arguments").command
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(command("status"), Command::Status);
+        assert_eq!(command(""), Command::Help);
+        assert_eq!(command("profile list"), Command::ProfileList);
+        assert_eq!(command("profile use abc"), Command::ProfileUse("abc".into()));
+        assert_eq!(command("profile update"), Command::ProfileUpdate(None));
+        assert_eq!(command("mode global"), Command::Mode("global".into()));
+        assert_eq!(command("proxy on"), Command::Proxy(true));
+        assert_eq!(command("tun off"), Command::Tun(false));
+        assert_eq!(
+            command("select Proxy HK-01"),
+            Command::Select {
+                group: "Proxy".into(),
+                node: "HK-01".into()
+            }
+        );
+        assert_eq!(
+            command("logs --follow --level warning,error --limit 20"),
+            Command::Logs(LogsArgs {
+                follow: true,
+                level: Some("warning,error".into()),
+                limit: Some(20),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_global_options() {
+        let cli = parse_args("--port 12345 status --token abc").expect("valid arguments");
+        assert_eq!(cli.port, Some(12345));
+        assert_eq!(cli.token.as_deref(), Some("abc"));
+        assert_eq!(cli.command, Command::Status);
+        assert_eq!(command("proxy --help"), Command::Help);
+    }
+
+    #[test]
+    fn reject_invalid_arguments() {
+        assert!(parse_args("mode script").is_err());
+        assert!(parse_args("proxy maybe").is_err());
+        assert!(parse_args("select Proxy").is_err());
+        assert!(parse_args("logs --limit many").is_err());
+        assert!(parse_args("--port").is_err());
+        assert!(parse_args("--port 70000 status").is_err());
+        assert!(parse_args("unknown").is_err());
+    }
+}
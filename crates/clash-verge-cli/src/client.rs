@@ -0,0 +1,100 @@
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde_json::Value;
+use std::time::Duration;
+
+/// 更新订阅可能较慢
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_UNAVAILABLE: i32 = 3;
+pub const EXIT_UNAUTHORIZED: i32 = 4;
+
+#[derive(Debug)]
+pub enum CliError {
+    /// 参数错误
+    Usage(String),
+    /// 应用未运行或未启用自动化 API
+    Unavailable(String),
+    Unauthorized,
+    /// 请求已送达但执行失败
+    Failed(String),
+}
+
+impl CliError {
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => EXIT_USAGE,
+            Self::Unavailable(_) => EXIT_UNAVAILABLE,
+            Self::Unauthorized => EXIT_UNAUTHORIZED,
+            Self::Failed(_) => EXIT_FAILED,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Usage(message) | Self::Unavailable(message) | Self::Failed(message) => message,
+            Self::Unauthorized => "invalid or missing token",
+        }
+    }
+}
+
+/// 通过单例端口上的 `/api/v1` 访问正在运行的应用
+pub struct ApiClient {
+    http: Client,
+    base: String,
+    token: String,
+}
+
+impl ApiClient {
+    pub fn new(port: u16, token: String) -> Result<Self, CliError> {
+        let http = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .no_proxy()
+            .build()
+            .map_err(|err| CliError::Failed(format!("failed to create http client: {err}")))?;
+        Ok(Self {
+            http,
+            base: format!("http://127.0.0.1:{port}/api/v1"),
+            token,
+        })
+    }
+
+    pub async fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Value, CliError> {
+        self.execute(self.request(Method::GET, path).query(query)).await
+    }
+
+    pub async fn send(&self, method: Method, path: &str, body: &Value) -> Result<Value, CliError> {
+        self.execute(self.request(method, path).json(body)).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}/{path}", self.base))
+            .bearer_auth(&self.token)
+    }
+
+    async fn execute(&self, request: RequestBuilder) -> Result<Value, CliError> {
+        let response = request
+            .send()
+            .await
+            .map_err(|err| CliError::Unavailable(format!("failed to reach Clash Verge at {}: {err}", self.base)))?;
+
+        let status = response.status();
+        let body = response.json::<Value>().await.unwrap_or(Value::Null);
+        match status {
+            status if status.is_success() => Ok(body),
+            StatusCode::UNAUTHORIZED => Err(CliError::Unauthorized),
+            StatusCode::NOT_FOUND => Err(CliError::Unavailable(
+                "automation API is disabled in Clash Verge settings".into(),
+            )),
+            status => Err(CliError::Failed(
+                body.get("error")
+                    .and_then(Value::as_str)
+                    .map_or_else(|| format!("request failed with {status}"), String::from),
+            )),
+        }
+    }
+}
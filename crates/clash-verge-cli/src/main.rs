@@ -0,0 +1,128 @@
+mod args;
+mod client;
+
+use args::{Command, DEFAULT_PORT, LogsArgs, PORT_ENV, TOKEN_ENV, USAGE};
+use client::{ApiClient, CliError};
+use reqwest::Method;
+use serde_json::{Value, json};
+use std::time::Duration;
+
+const LOG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_LOG_LIMIT: usize = 100;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let code = match run().await {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("{}", json!({ "error": err.message() }));
+            err.exit_code()
+        }
+    };
+    std::process::exit(code);
+}
+
+async fn run() -> Result<(), CliError> {
+    let cli = args::parse(std::env::args().skip(1)).map_err(CliError::Usage)?;
+    if cli.command == Command::Help {
+        print!("{USAGE}");
+        return Ok(());
+    }
+
+    let port = match cli.port {
+        Some(port) => port,
+        None => match std::env::var(PORT_ENV) {
+            Ok(port) => port
+                .parse()
+                .map_err(|_| CliError::Usage(format!("invalid {PORT_ENV} `{port}`")))?,
+            Err(_) => DEFAULT_PORT,
+        },
+    };
+    let token = cli
+        .token
+        .or_else(|| std::env::var(TOKEN_ENV).ok())
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| CliError::Usage(format!("missing token, pass --token or set {TOKEN_ENV}")))?;
+    let client = ApiClient::new(port, token)?;
+
+    let output = match cli.command {
+        Command::Status => client.get("status", &[]).await?,
+        Command::ProfileList => client.get("profiles", &[]).await?,
+        Command::ProfileUse(profile) => {
+            let uid = resolve_profile(&client, &profile).await?;
+            client.send(Method::PUT, "profile", &json!({ "uid": uid })).await?
+        }
+        Command::ProfileUpdate(profile) => {
+            let uid = match profile {
+                Some(profile) => Some(resolve_profile(&client, &profile).await?),
+                None => None,
+            };
+            client
+                .send(Method::POST, "profiles/update", &json!({ "uid": uid }))
+                .await?
+        }
+        Command::Mode(mode) => client.send(Method::PUT, "mode", &json!({ "mode": mode })).await?,
+        Command::Proxy(enable) => {
+            client
+                .send(Method::PUT, "system-proxy", &json!({ "enable": enable }))
+                .await?
+        }
+        Command::Tun(enable) => client.send(Method::PUT, "tun", &json!({ "enable": enable })).await?,
+        Command::Select { group, node } => {
+            client
+                .send(Method::PUT, "selection", &json!({ "group": group, "name": node }))
+                .await?
+        }
+        Command::Logs(logs) if logs.follow => return follow_logs(&client, &logs).await,
+        Command::Logs(logs) => client.get("logs", &logs_query(&logs, None)).await?,
+        Command::Help => return Ok(()),
+    };
+    println!("{output}");
+    Ok(())
+}
+
+/// 按 uid 或名称查找订阅
+async fn resolve_profile(client: &ApiClient, profile: &str) -> Result<String, CliError> {
+    let profiles = client.get("profiles", &[]).await?;
+    let uids = profiles
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|item| item["uid"] == profile || item["name"] == profile)
+        .filter_map(|item| item["uid"].as_str())
+        .collect::<Vec<_>>();
+
+    match uids.as_slice() {
+        [uid] => Ok((*uid).into()),
+        [] => Err(CliError::Failed(format!("profile `{profile}` not found"))),
+        _ => Err(CliError::Usage(format!(
+            "profile name `{profile}` is ambiguous, use its uid instead"
+        ))),
+    }
+}
+
+fn logs_query(logs: &LogsArgs, after: Option<u64>) -> Vec<(&'static str, String)> {
+    let mut query = Vec::new();
+    if let Some(level) = &logs.level {
+        query.push(("level", level.clone()));
+    }
+    // 跟踪时不限制条数，避免两次轮询之间的日志被截断
+    match after {
+        Some(after) => query.push(("after", after.to_string())),
+        None => query.push(("limit", logs.limit.unwrap_or(DEFAULT_LOG_LIMIT).to_string())),
+    }
+    query
+}
+
+/// 持续输出新日志，每行一条 JSON
+async fn follow_logs(client: &ApiClient, logs: &LogsArgs) -> Result<(), CliError> {
+    let mut after = None;
+    loop {
+        let entries = client.get("logs", &logs_query(logs, after)).await?;
+        for entry in entries.as_array().into_iter().flatten() {
+            println!("{entry}");
+            after = entry.get("seq").and_then(Value::as_u64).or(after);
+        }
+        tokio::time::sleep(LOG_POLL_INTERVAL).await;
+    }
+}
@@ -35,3 +35,13 @@ where
     #[cfg(windows)]
     windows::register(f);
 }
+
+/// 注册 SIGHUP 重载回调，需在 [`register`] 之后调用
+#[cfg(unix)]
+pub fn register_reload<F, Fut>(f: F)
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future + Send + 'static,
+{
+    unix::register_reload(f);
+}
@@ -6,6 +6,8 @@ use tokio::signal::unix::{SignalKind, signal};
 use crate::RUNTIME;
 
 static IS_CLEANING_UP: AtomicBool = AtomicBool::new(false);
+// 注册了重载回调后，SIGHUP 不再触发退出
+static RELOAD_REGISTERED: AtomicBool = AtomicBool::new(false);
 
 pub fn register<F, Fut>(f: F)
 where
@@ -46,6 +48,9 @@ where
                         signal_name = "SIGINT";
                     }
                     _ = sighup.recv() => {
+                        if RELOAD_REGISTERED.load(Ordering::SeqCst) {
+                            continue;
+                        }
                         signal_name = "SIGHUP";
                     }
                     else => {
@@ -77,3 +82,37 @@ where
         );
     }
 }
+
+pub fn register_reload<F, Fut>(f: F)
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future + Send + 'static,
+{
+    let Some(Some(rt)) = RUNTIME.get() else {
+        logging!(
+            error,
+            Type::SystemSignal,
+            "register reload signal failed, RUNTIME is not available"
+        );
+        return;
+    };
+
+    RELOAD_REGISTERED.store(true, Ordering::SeqCst);
+    rt.spawn(async move {
+        let mut sighup = match signal(SignalKind::hangup()) {
+            Ok(s) => s,
+            Err(e) => {
+                logging!(error, Type::SystemSignal, "Failed to register SIGHUP: {}", e);
+                return;
+            }
+        };
+
+        while sighup.recv().await.is_some() {
+            if IS_CLEANING_UP.load(Ordering::SeqCst) {
+                continue;
+            }
+            logging!(info, Type::SystemSignal, "Caught signal SIGHUP, reloading");
+            f().await;
+        }
+    });
+}
@@ -606,6 +606,30 @@ const resolveUninstall = () => {
   })
 }
 
+// clash-verge-cli 由工作区构建，作为 externalBin 随应用发布
+const resolveCli = async () => {
+  const ext = platform === 'win32' ? '.exe' : ''
+  const targetPath = path.join(
+    SIDECAR_DIR,
+    `clash-verge-cli-${SIDECAR_HOST}${ext}`,
+  )
+  execSync(
+    `cargo build --release -p clash-verge-cli --target ${SIDECAR_HOST}`,
+    { stdio: 'inherit' },
+  )
+  const targetDir = process.env.CARGO_TARGET_DIR || path.join(cwd, 'target')
+  const builtPath = path.join(
+    targetDir,
+    SIDECAR_HOST,
+    'release',
+    `clash-verge-cli${ext}`,
+  )
+  await fsp.mkdir(SIDECAR_DIR, { recursive: true })
+  await fsp.copyFile(builtPath, targetPath)
+  if (platform !== 'win32') execSync(`chmod 755 ${targetPath}`)
+  log_success(`clash-verge-cli finished`)
+}
+
 const resolveMmdb = () =>
   resolveResource({
     file: 'Country.mmdb',
@@ -658,6 +682,7 @@ const tasks = [
   { name: 'service', func: resolveService, retry: 5 },
   { name: 'install', func: resolveInstall, retry: 5 },
   { name: 'uninstall', func: resolveUninstall, retry: 5 },
+  { name: 'cli', func: resolveCli, retry: 1 },
   { name: 'mmdb', func: resolveMmdb, retry: 5 },
   { name: 'geosite', func: resolveGeosite, retry: 5 },
   { name: 'geoip', func: resolveGeoIP, retry: 5 },
@@ -0,0 +1,283 @@
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+use std::hint::black_box;
+use std::process;
+use tokio::runtime::Runtime;
+
+use app_lib::{
+    EnhanceCache, SeqMap, apply_builtin_transforms_cached, process_chain_for_bench, use_merge, use_script, use_seq,
+};
+
+const PROXY_COUNT: usize = 5000;
+const RULE_COUNT: usize = 50_000;
+
+const SCRIPT: &str = r#"
+function main(config, profileName) {
+  const names = config.proxies.map((proxy) => proxy.name);
+  config["proxy-groups"].push({
+    name: "AUTO",
+    type: "url-test",
+    proxies: names.filter((name) => name.endsWith("0")),
+  });
+  config.rules = config.rules.map((rule) => rule.replace(",DIRECT", ",AUTO"));
+  console.log(profileName, names.length);
+  return config;
+}
+"#;
+
+const MERGE: &str = r#"
+mixed-port: 7897
+dns:
+  enable: true
+  enhanced-mode: fake-ip
+  nameserver: [https://1.1.1.1/dns-query, https://8.8.8.8/dns-query]
+  fallback-filter:
+    geoip: true
+    ipcidr: [240.0.0.0/4]
+rule-providers:
+  reject:
+    type: http
+    behavior: domain
+    url: https://example.com/reject.txt
+    path: ./ruleset/reject.yaml
+    interval: 86400
+"#;
+
+fn make_profile() -> Mapping {
+    let proxies = (0..PROXY_COUNT)
+        .map(|i| {
+            let mut proxy = Mapping::new();
+            proxy.insert("name".into(), format!("node-{i}").into());
+            proxy.insert("type".into(), if i % 2 == 0 { "hysteria" } else { "ss" }.into());
+            proxy.insert("server".into(), format!("{i}.example.com").into());
+            proxy.insert("port".into(), 443.into());
+            proxy.insert("alpn".into(), "h3".into());
+            Value::Mapping(proxy)
+        })
+        .collect::<Vec<_>>();
+
+    let names = (0..PROXY_COUNT)
+        .map(|i| Value::from(format!("node-{i}")))
+        .collect::<Vec<_>>();
+    let mut group = Mapping::new();
+    group.insert("name".into(), "PROXY".into());
+    group.insert("type".into(), "select".into());
+    group.insert("proxies".into(), Value::Sequence(names));
+
+    let mut config = Mapping::new();
+    config.insert("mode".into(), "rule".into());
+    config.insert("proxies".into(), Value::Sequence(proxies));
+    config.insert("proxy-groups".into(), Value::Sequence(vec![Value::Mapping(group)]));
+    config.insert("rules".into(), Value::Sequence(vec!["MATCH,PROXY".into()]));
+    config
+}
+
+pub fn bench_builtin(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap_or_else(|e| {
+        eprintln!("Tokio runtime init failed: {e}");
+        process::exit(1);
+    });
+
+    let config = make_profile();
+    let core = Some(String::from("verge-mihomo"));
+
+    let mut group = c.benchmark_group("enhance_builtin");
+    group.sample_size(10);
+    group.warm_up_time(std::time::Duration::from_millis(300));
+    group.measurement_time(std::time::Duration::from_secs(5));
+
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            let res = rt.block_on(apply_builtin_transforms_cached(
+                black_box(config.clone()),
+                core.as_ref(),
+                true,
+                &[],
+                None,
+            ));
+            black_box(res);
+        });
+    });
+
+    let cache = EnhanceCache::with_dir(None);
+    rt.block_on(apply_builtin_transforms_cached(
+        config.clone(),
+        core.as_ref(),
+        true,
+        &[],
+        Some(&cache),
+    ));
+
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            let res = rt.block_on(apply_builtin_transforms_cached(
+                black_box(config.clone()),
+                core.as_ref(),
+                true,
+                &[],
+                Some(&cache),
+            ));
+            black_box(res);
+        });
+    });
+
+    group.finish();
+}
+
+fn make_rules_profile() -> Mapping {
+    let mut config = make_profile();
+    let rules = (0..RULE_COUNT)
+        .map(|i| Value::from(format!("DOMAIN-SUFFIX,site-{i}.example.com,DIRECT")))
+        .chain(std::iter::once(Value::from("MATCH,PROXY")))
+        .collect::<Vec<_>>();
+    config.insert("rules".into(), Value::Sequence(rules));
+    config
+}
+
+fn make_rules_seq() -> SeqMap {
+    SeqMap {
+        prepend: (0..1000)
+            .map(|i| Value::from(format!("DOMAIN,prepend-{i}.example.com,PROXY")))
+            .collect(),
+        append: vec![],
+        delete: (0..1000)
+            .map(|i| format!("DOMAIN-SUFFIX,site-{i}.example.com,DIRECT"))
+            .collect(),
+    }
+}
+
+pub fn bench_chain(c: &mut Criterion) {
+    let config = make_profile();
+    let rules_config = make_rules_profile();
+    let merge: Mapping = serde_yaml_ng::from_str(MERGE).unwrap_or_else(|e| {
+        eprintln!("Invalid merge fixture: {e}");
+        process::exit(1);
+    });
+    let name = String::from("bench");
+
+    let mut group = c.benchmark_group("enhance_chain");
+    group.sample_size(10);
+    group.warm_up_time(std::time::Duration::from_millis(300));
+    group.measurement_time(std::time::Duration::from_secs(5));
+
+    group.bench_function("merge", |b| {
+        b.iter(|| black_box(use_merge(&merge, black_box(config.clone()))));
+    });
+
+    group.bench_function("script", |b| {
+        b.iter(|| black_box(use_script(SCRIPT.into(), black_box(&config), &name)));
+    });
+
+    group.bench_function("rules_seq", |b| {
+        b.iter(|| black_box(use_seq(make_rules_seq(), black_box(rules_config.clone()), "rules")));
+    });
+
+    group.bench_function("rules_script", |b| {
+        b.iter(|| black_box(use_script(SCRIPT.into(), black_box(&rules_config), &name)));
+    });
+
+    group.finish();
+}
+
+pub fn bench_rules_builtin(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap_or_else(|e| {
+        eprintln!("Tokio runtime init failed: {e}");
+        process::exit(1);
+    });
+
+    let config = make_rules_profile();
+    let core = Some(String::from("verge-mihomo"));
+
+    let mut group = c.benchmark_group("enhance_rules_builtin");
+    group.sample_size(10);
+    group.measurement_time(std::time::Duration::from_secs(5));
+
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            let res = rt.block_on(apply_builtin_transforms_cached(
+                black_box(config.clone()),
+                core.as_ref(),
+                true,
+                &[],
+                None,
+            ));
+            black_box(res);
+        });
+    });
+
+    group.finish();
+}
+
+/// 订阅扩展，两份交替使用时订阅阶段始终未命中而全局阶段命中
+const PROFILE_MERGES: [&str; 2] = ["log-level: info", "log-level: warning"];
+
+/// 全局与订阅的合并、脚本阶段：无缓存、冷缓存、只命中全局阶段、全部命中
+pub fn bench_chain_cached(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap_or_else(|e| {
+        eprintln!("Tokio runtime init failed: {e}");
+        process::exit(1);
+    });
+
+    let config = make_rules_profile();
+    let parse = |yaml: &str| -> Mapping {
+        serde_yaml_ng::from_str(yaml).unwrap_or_else(|e| {
+            eprintln!("Invalid merge fixture: {e}");
+            process::exit(1);
+        })
+    };
+    let global_merge = parse(MERGE);
+    let profile_merges = PROFILE_MERGES.map(parse);
+    let run = |profile_merge: &Mapping, cache: Option<&EnhanceCache>| {
+        rt.block_on(process_chain_for_bench(
+            black_box(config.clone()),
+            global_merge.clone(),
+            SCRIPT,
+            profile_merge.clone(),
+            SCRIPT,
+            cache,
+        ))
+    };
+
+    let mut group = c.benchmark_group("enhance_chain_cached");
+    group.sample_size(10);
+    group.warm_up_time(std::time::Duration::from_millis(300));
+    group.measurement_time(std::time::Duration::from_secs(10));
+
+    group.bench_function("uncached", |b| {
+        b.iter(|| black_box(run(&profile_merges[0], None)));
+    });
+
+    group.bench_function("cold", |b| {
+        b.iter_batched(
+            || EnhanceCache::with_dir(None),
+            |cache| black_box(run(&profile_merges[0], Some(&cache))),
+            BatchSize::PerIteration,
+        );
+    });
+
+    let cache = EnhanceCache::with_dir(None);
+    let mut turn = 0;
+    group.bench_function("global_hit", |b| {
+        b.iter(|| {
+            turn ^= 1;
+            black_box(run(&profile_merges[turn], Some(&cache)))
+        });
+    });
+
+    run(&profile_merges[0], Some(&cache));
+    group.bench_function("warm", |b| {
+        b.iter(|| black_box(run(&profile_merges[0], Some(&cache))));
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_builtin,
+    bench_chain,
+    bench_chain_cached,
+    bench_rules_builtin
+);
+criterion_main!(benches);
@@ -1,6 +1,9 @@
 use super::CmdResult;
 use crate::core::{autostart, handle};
-use crate::utils::resolve::ui::{self, UiReadyStage};
+use crate::utils::resolve::{
+    scheme::{self, DeepLinkPreview},
+    ui::{self, UiReadyStage},
+};
 use crate::{cmd::StringifyErr as _, feat, utils::dirs};
 use clash_verge_logging::{Type, logging};
 use smartstring::alias::String;
@@ -128,3 +131,15 @@ pub fn update_ui_stage(stage: UiReadyStage) {
     logging!(info, Type::Cmd, "UI加载阶段更新: {:?}", &stage);
     ui::update_ui_ready_stage(stage);
 }
+
+/// 获取等待确认的深链接
+#[tauri::command]
+pub fn get_pending_deep_links() -> Vec<DeepLinkPreview> {
+    scheme::pending_deep_links()
+}
+
+/// 确认或拒绝深链接
+#[tauri::command]
+pub async fn respond_deep_link(id: String, accept: bool) -> CmdResult<()> {
+    scheme::respond_deep_link(&id, accept).await.stringify_err()
+}
@@ -3,14 +3,23 @@ use crate::feat;
 use crate::utils::dirs;
 use crate::{
     cmd::StringifyErr as _,
-    config::{ClashInfo, Config},
+    config::{ClashInfo, Config, SecretStrength},
     constants,
-    core::{CoreManager, handle, validate::CoreConfigValidator},
+    core::{
+        CoreManager,
+        core_log::{CoreLogEntry, CoreLogQuery},
+        handle,
+        managed_core::{self, CoreSource, ManagedCoreInfo},
+        manager::CoreRestartStats,
+        validate::CoreConfigValidator,
+    },
+    module::core_watchdog::{CoreWatchdog, HealthRecord},
 };
 use clash_verge_logging::{Type, logging, logging_error};
 use compact_str::CompactString;
 use serde_yaml_ng::Mapping;
 use smartstring::alias::String;
+use std::path::PathBuf;
 use tokio::fs;
 
 /// 复制Clash环境变量
@@ -20,6 +29,28 @@ pub async fn copy_clash_env() -> CmdResult {
     Ok(())
 }
 
+/// 生成代理环境变量的导出文本，`unset` 时生成清除命令
+#[tauri::command]
+pub async fn get_env_export(env_type: String, unset: bool) -> CmdResult<String> {
+    let target = feat::EnvTarget::parse(&env_type).ok_or("invalid env type")?;
+    Ok(feat::env_export_text(target, &feat::ProxyEnv::current().await, unset))
+}
+
+/// 将代理环境写入文件，未指定路径时写入目标的默认配置文件，返回写入的路径
+#[tauri::command]
+pub async fn write_env_export(env_type: String, path: Option<String>, unset: bool) -> CmdResult<String> {
+    let target = feat::EnvTarget::parse(&env_type).ok_or("invalid env type")?;
+    let path = match path {
+        Some(path) => PathBuf::from(path.as_str()),
+        None => feat::default_env_file(target).stringify_err()?,
+    };
+    feat::write_env_file(target, &feat::ProxyEnv::current().await, &path, unset)
+        .await
+        .stringify_err()?;
+    logging!(info, Type::Cmd, "Proxy environment written to {}", path.display());
+    Ok(path.to_string_lossy().into())
+}
+
 /// 获取Clash信息
 #[tauri::command]
 pub async fn get_clash_info() -> CmdResult<ClashInfo> {
@@ -32,6 +63,18 @@ pub async fn patch_clash_config(payload: Mapping) -> CmdResult {
     feat::patch_clash(&payload).await.stringify_err()
 }
 
+/// 生成新的控制器密钥并热更新到内核
+#[tauri::command]
+pub async fn rotate_clash_secret() -> CmdResult<String> {
+    feat::rotate_clash_secret().await.map(Into::into).stringify_err()
+}
+
+/// 评估控制器密钥强度
+#[tauri::command]
+pub fn check_secret_strength(secret: String) -> SecretStrength {
+    SecretStrength::assess(&secret)
+}
+
 /// 修改Clash模式
 #[tauri::command]
 pub async fn patch_clash_mode(payload: String) -> CmdResult {
@@ -47,22 +90,10 @@ pub async fn change_clash_core(clash_core: String) -> CmdResult<Option<String>>
     match CoreManager::global().change_core(&clash_core).await {
         Ok(_) => {
             logging_error!(Type::Core, Config::profiles().await.data_arc().save_file().await);
-
-            // 切换内核后重启内核
-            match CoreManager::global().restart_core().await {
-                Ok(_) => {
-                    logging!(info, Type::Core, "core changed and restarted to {clash_core}");
-                    handle::Handle::notice_message("config_core::change_success", clash_core);
-                    handle::Handle::refresh_clash();
-                    Ok(None)
-                }
-                Err(err) => {
-                    let error_msg: String = format!("Core changed but failed to restart: {err}").into();
-                    handle::Handle::notice_message("config_core::change_error", error_msg.clone());
-                    logging!(error, Type::Core, "{error_msg}");
-                    Ok(Some(error_msg))
-                }
-            }
+            logging!(info, Type::Core, "core changed and restarted to {clash_core}");
+            handle::Handle::notice_message("config_core::change_success", clash_core);
+            handle::Handle::refresh_clash();
+            Ok(None)
         }
         Err(err) => {
             let error_msg: String = err;
@@ -73,6 +104,31 @@ pub async fn change_clash_core(clash_core: String) -> CmdResult<Option<String>>
     }
 }
 
+/// 安装受管内核
+#[tauri::command]
+pub async fn install_managed_core(
+    source: CoreSource,
+    version: String,
+    sha256: Option<String>,
+) -> CmdResult<ManagedCoreInfo> {
+    managed_core::install_core(source, &version, sha256.as_deref())
+        .await
+        .stringify_err()
+}
+
+/// 获取已安装的受管内核
+#[tauri::command]
+pub async fn list_managed_cores() -> CmdResult<Vec<ManagedCoreInfo>> {
+    managed_core::list_cores().await.stringify_err()
+}
+
+/// 删除受管内核，正在使用的内核不可删除
+#[tauri::command]
+pub async fn remove_managed_core(version: String) -> CmdResult {
+    let current = Config::verge().await.latest_arc().get_valid_clash_core();
+    managed_core::remove_core(&version, &current).await.stringify_err()
+}
+
 /// 启动核心
 #[tauri::command]
 pub async fn start_core() -> CmdResult {
@@ -98,13 +154,25 @@ pub async fn stop_core() -> CmdResult {
 #[tauri::command]
 pub async fn restart_core() -> CmdResult {
     logging_error!(Type::Core, Config::profiles().await.data_arc().save_file().await);
-    let result = CoreManager::global().restart_core().await.stringify_err();
+    let result = CoreManager::global().graceful_restart_core().await.stringify_err();
     if result.is_ok() {
         handle::Handle::refresh_clash();
     }
     result
 }
 
+/// 获取核心自动重启统计
+#[tauri::command]
+pub async fn get_core_restart_stats() -> CmdResult<CoreRestartStats> {
+    Ok(CoreManager::global().get_restart_stats())
+}
+
+/// 获取核心健康检查记录
+#[tauri::command]
+pub async fn get_core_health_history() -> CmdResult<Vec<HealthRecord>> {
+    Ok(CoreWatchdog::global().history())
+}
+
 /// 测试URL延迟
 #[tauri::command]
 pub async fn test_delay(url: String) -> CmdResult<u32> {
@@ -249,3 +317,9 @@ pub async fn get_clash_logs() -> CmdResult<Vec<CompactString>> {
     let logs = CoreManager::global().get_clash_logs().await.unwrap_or_default();
     Ok(logs)
 }
+
+/// 按级别、时间、关键字及连接信息查询内核日志
+#[tauri::command]
+pub async fn query_clash_logs(query: CoreLogQuery) -> CmdResult<Vec<CoreLogEntry>> {
+    CoreManager::global().query_clash_logs(query).await.stringify_err()
+}
@@ -16,6 +16,7 @@ pub mod runtime;
 pub mod save_profile;
 pub mod service;
 pub mod system;
+pub mod tun;
 pub mod uwp;
 pub mod validate;
 pub mod verge;
@@ -34,6 +35,7 @@ pub use runtime::*;
 pub use save_profile::*;
 pub use service::*;
 pub use system::*;
+pub use tun::*;
 pub use uwp::*;
 pub use validate::*;
 pub use verge::*;
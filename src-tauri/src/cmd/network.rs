@@ -1,5 +1,13 @@
 use super::CmdResult;
 use crate::cmd::StringifyErr as _;
+use crate::{
+    core::{
+        pac::{self, PacReport},
+        proxy_guard::{ProxyDeviation, ProxyGuard},
+    },
+    feat::{self, LanOnboarding, LanSharingInfo, LanSharingPatch},
+    utils::bypass::{BypassFormat, BypassList, BypassPreset, BypassPresetInfo, BypassReport},
+};
 use clash_verge_logging::{Type, logging};
 use gethostname::gethostname;
 use network_interface::NetworkInterface;
@@ -57,6 +65,36 @@ pub async fn get_auto_proxy() -> CmdResult<Mapping> {
     Ok(map)
 }
 
+/// 获取由运行时规则生成 PAC 的统计
+#[tauri::command]
+pub async fn get_pac_report() -> CmdResult<PacReport> {
+    Ok(pac::pac_report().await)
+}
+
+/// 校验并规范化代理绕过列表
+#[tauri::command]
+pub fn check_bypass(bypass: String) -> BypassReport {
+    BypassList::parse(&bypass).report(BypassFormat::system())
+}
+
+/// 获取可用的绕过预设
+#[tauri::command]
+pub fn get_bypass_presets() -> Vec<BypassPresetInfo> {
+    BypassPreset::ALL.into_iter().map(BypassPresetInfo::from).collect()
+}
+
+/// 获取系统代理守卫检测到的篡改记录
+#[tauri::command]
+pub fn get_proxy_guard_history() -> Vec<ProxyDeviation> {
+    ProxyGuard::global().history()
+}
+
+/// 清空系统代理守卫的篡改记录
+#[tauri::command]
+pub fn clear_proxy_guard_history() {
+    ProxyGuard::global().clear_history();
+}
+
 /// 获取系统主机名
 #[tauri::command]
 pub fn get_system_hostname() -> String {
@@ -97,6 +135,34 @@ pub fn get_network_interfaces_info() -> CmdResult<Vec<NetworkInterface>> {
     Ok(result)
 }
 
+/// 获取局域网共享设置与已授权设备
+#[tauri::command]
+pub async fn get_lan_sharing() -> LanSharingInfo {
+    feat::lan_sharing_info().await
+}
+
+#[tauri::command]
+pub async fn patch_lan_sharing(patch: LanSharingPatch) -> CmdResult {
+    feat::patch_lan_sharing(patch).await.stringify_err()
+}
+
+/// 为设备添加认证用户，返回接入信息
+#[tauri::command]
+pub async fn add_lan_user(name: String) -> CmdResult<LanOnboarding> {
+    feat::add_lan_user(&name).await.stringify_err()
+}
+
+#[tauri::command]
+pub async fn remove_lan_user(username: String) -> CmdResult {
+    feat::remove_lan_user(&username).await.stringify_err()
+}
+
+/// 获取设备的接入信息，包含代理地址、认证信息与 PAC 地址
+#[tauri::command]
+pub async fn get_lan_onboarding(username: String) -> CmdResult<LanOnboarding> {
+    feat::lan_onboarding(&username).await.stringify_err()
+}
+
 #[tauri::command]
 pub fn is_port_in_use(port: u16) -> bool {
     TcpListener::bind(("127.0.0.1", port)).is_err()
@@ -146,6 +146,25 @@ pub async fn create_profile(item: PrfItem, file_data: Option<String>) -> CmdResu
     }
 }
 
+/// 导入 WebAssembly 增强模块
+/// 返回新建扩展项的 uid
+#[tauri::command]
+pub async fn import_wasm_item(file_path: String, name: Option<String>) -> CmdResult<String> {
+    logging!(info, Type::Cmd, "[导入Wasm] 开始导入: {}", file_path);
+
+    let wasm = tokio::fs::read(file_path.as_str()).await.stringify_err()?;
+    let item = &mut PrfItem::from_wasm(name, &wasm).await.stringify_err_log(|err| {
+        logging!(error, Type::Cmd, "[导入Wasm] 模块验证失败: {}", err);
+    })?;
+
+    profiles_append_item_safe(item).await.stringify_err()?;
+    profiles_save_file_safe().await.stringify_err()?;
+
+    let uid = item.uid.clone().unwrap_or_default();
+    handle::Handle::notify_profile_changed(&uid);
+    Ok(uid)
+}
+
 /// 更新配置文件
 #[tauri::command]
 pub async fn update_profile(index: String, option: Option<PrfOption>) -> CmdResult {
@@ -1,31 +1,36 @@
 use super::CmdResult;
-use crate::{cmd::StringifyErr as _, config::Config, core::CoreManager};
+use crate::{
+    cmd::StringifyErr as _,
+    config::Config,
+    core::{CoreManager, manager::RollbackRecord},
+};
 use anyhow::{Context as _, anyhow};
 use clash_verge_logging::{Type, logging_error};
 use serde_yaml_ng::Mapping;
 use smartstring::alias::String;
 use std::collections::{HashMap, HashSet};
 
-/// 获取运行时配置
+/// 获取运行时配置，机密变量与局域网用户密码会被隐去
 #[tauri::command]
 pub async fn get_runtime_config() -> CmdResult<Option<Mapping>> {
-    Ok(Config::runtime().await.latest_arc().config.clone())
+    let runtime = Config::runtime().await.latest_arc();
+    Ok(runtime
+        .config
+        .as_ref()
+        .map(|config| runtime.secret_values.redact(config)))
 }
 
-/// 获取运行时YAML配置
+/// 获取运行时YAML配置，机密变量与局域网用户密码会被隐去
 #[tauri::command]
 pub async fn get_runtime_yaml() -> CmdResult<String> {
-    let runtime = Config::runtime().await;
-    let runtime = runtime.latest_arc();
+    let runtime = Config::runtime().await.latest_arc();
 
     let config = runtime.config.as_ref();
     config
         .ok_or_else(|| anyhow!("failed to parse config to yaml file"))
-        .and_then(|config| {
-            serde_yaml_ng::to_string(config)
-                .context("failed to convert config to yaml")
-                .map(|s| s.into())
-        })
+        .map(|config| runtime.secret_values.redact(config))
+        .and_then(|config| serde_yaml_ng::to_string(&config).context("failed to convert config to yaml"))
+        .map(Into::into)
         .stringify_err()
 }
 
@@ -103,3 +108,15 @@ pub async fn update_proxy_chain_config_in_runtime(proxy_chain_config: Option<ser
 
     Ok(())
 }
+
+/// 回退到上一次成功应用的运行时配置
+#[tauri::command]
+pub async fn revert_runtime_config() -> CmdResult<()> {
+    CoreManager::global().revert_runtime_config().await.stringify_err()
+}
+
+/// 获取最近一次运行时配置回滚的信息
+#[tauri::command]
+pub async fn get_last_config_rollback() -> CmdResult<Option<RollbackRecord>> {
+    Ok(CoreManager::global().get_last_rollback())
+}
@@ -1,5 +1,8 @@
 use super::{CmdResult, StringifyErr as _};
-use crate::core::service::{self, SERVICE_MANAGER, ServiceStatus};
+use crate::core::{
+    service::{self, SERVICE_MANAGER, ServiceStatus},
+    service_version::{self, Negotiation},
+};
 use smartstring::SmartString;
 
 async fn execute_service_operation_sync(status: ServiceStatus, op_type: &str) -> CmdResult {
@@ -30,6 +33,18 @@ pub async fn repair_service() -> CmdResult {
     execute_service_operation_sync(ServiceStatus::ForceReinstallRequired, "Repair").await
 }
 
+/// 原地升级服务，期间使用 Sidecar 模式
+#[tauri::command]
+pub async fn upgrade_service() -> CmdResult {
+    service_version::upgrade_service().await.stringify_err()
+}
+
+/// 重新与服务协商版本
+#[tauri::command]
+pub async fn get_service_negotiation() -> CmdResult<Negotiation> {
+    Ok(service_version::negotiate().await)
+}
+
 #[tauri::command]
 pub async fn is_service_available() -> CmdResult<bool> {
     service::is_service_available().await.stringify_err()?;
@@ -0,0 +1,28 @@
+/// Platform-specific implementation for TUN pre-flight diagnostics
+#[cfg(target_os = "linux")]
+mod platform {
+    use crate::core::tun_preflight;
+
+    pub type Report = tun_preflight::TunPreflightReport;
+
+    pub async fn check_tun_preflight() -> Option<Report> {
+        Some(tun_preflight::run().await)
+    }
+}
+
+/// Stub implementation for non-Linux platforms
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    pub type Report = ();
+
+    #[allow(clippy::unused_async)]
+    pub async fn check_tun_preflight() -> Option<Report> {
+        None
+    }
+}
+
+/// 检查开启 TUN 模式的前置条件，仅 Linux 返回报告
+#[tauri::command]
+pub async fn check_tun_preflight() -> Option<platform::Report> {
+    platform::check_tun_preflight().await
+}
@@ -1,6 +1,13 @@
 use super::CmdResult;
-use crate::{cmd::StringifyErr as _, config::IVerge, feat};
+use crate::{
+    cmd::StringifyErr as _,
+    config::{IVerge, IVergeApiToken},
+    enhance::builtin::{BuiltinTransformInfo, builtin_transform_infos},
+    feat,
+    module::automation_api,
+};
 use clash_verge_draft::SharedDraft;
+use smartstring::alias::String;
 
 /// 获取Verge配置
 #[tauri::command]
@@ -13,3 +20,21 @@ pub async fn get_verge_config() -> CmdResult<SharedDraft<IVerge>> {
 pub async fn patch_verge_config(payload: IVerge) -> CmdResult {
     feat::patch_verge(&payload, false).await.stringify_err()
 }
+
+/// 获取内建配置转换列表
+#[tauri::command]
+pub fn get_builtin_transforms() -> CmdResult<Vec<BuiltinTransformInfo>> {
+    Ok(builtin_transform_infos())
+}
+
+/// 创建自动化 API 访问令牌
+#[tauri::command]
+pub async fn create_automation_token(name: String) -> CmdResult<IVergeApiToken> {
+    automation_api::create_token(&name).await.stringify_err()
+}
+
+/// 吊销自动化 API 访问令牌
+#[tauri::command]
+pub async fn revoke_automation_token(name: String) -> CmdResult {
+    automation_api::revoke_token(&name).await.stringify_err()
+}
@@ -2,7 +2,8 @@ use crate::config::Config;
 use crate::constants::{network, tun as tun_const};
 use crate::utils::dirs::{ipc_path, path_to_str};
 use crate::utils::{dirs, help};
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use clash_verge_logging::{Type, logging};
 use serde::{Deserialize, Serialize};
 use serde_yaml_ng::{Mapping, Value};
@@ -11,6 +12,77 @@ use std::{
     str::FromStr as _,
 };
 
+/// 旧版本模板写入的固定密钥
+pub const LEGACY_DEFAULT_SECRET: &str = "set-your-secret";
+/// 常见的默认或示例密钥，按默认密钥处理
+const KNOWN_DEFAULT_SECRETS: [&str; 9] = [
+    LEGACY_DEFAULT_SECRET,
+    "123456",
+    "12345678",
+    "admin",
+    "clash",
+    "mihomo",
+    "password",
+    "secret",
+    "your-secret",
+];
+const SECRET_BYTES: usize = 24;
+const MIN_SECRET_LEN: usize = 16;
+const MIN_SECRET_DISTINCT_CHARS: usize = 8;
+
+/// 控制器密钥的强度评估
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecretStrength {
+    Empty,
+    Default,
+    Weak,
+    Strong,
+}
+
+impl SecretStrength {
+    pub fn assess(secret: &str) -> Self {
+        let secret = secret.trim();
+        if secret.is_empty() {
+            return Self::Empty;
+        }
+        if KNOWN_DEFAULT_SECRETS
+            .iter()
+            .any(|known| secret.eq_ignore_ascii_case(known))
+        {
+            return Self::Default;
+        }
+        let mut distinct: Vec<char> = secret.chars().collect();
+        let len = distinct.len();
+        distinct.sort_unstable();
+        distinct.dedup();
+        if len < MIN_SECRET_LEN || distinct.len() < MIN_SECRET_DISTINCT_CHARS {
+            return Self::Weak;
+        }
+        Self::Strong
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::Default => "default",
+            Self::Weak => "weak",
+            Self::Strong => "strong",
+        }
+    }
+
+    pub const fn is_strong(self) -> bool {
+        matches!(self, Self::Strong)
+    }
+}
+
+/// 生成随机的控制器密钥
+pub fn generate_secret() -> Result<String> {
+    let mut bytes = [0u8; SECRET_BYTES];
+    getrandom::fill(&mut bytes).map_err(|err| anyhow!("failed to generate secret: {err}"))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct IClashTemp(pub Mapping);
 
@@ -37,7 +109,7 @@ impl IClashTemp {
                     && let Value::String(s) = val
                     && s.is_empty()
                 {
-                    *s = "set-your-secret".into();
+                    *s = Self::initial_secret();
                 }
 
                 Self(Self::guard(map))
@@ -103,12 +175,20 @@ impl IClashTemp {
             ]
             .into(),
         );
-        map.insert("secret".into(), "set-your-secret".into());
+        map.insert("secret".into(), Self::initial_secret().into());
         map.insert("external-controller-cors".into(), cors_map.into());
         map.insert("unified-delay".into(), true.into());
         Self(map)
     }
 
+    /// 首次运行时生成随机密钥，随机数不可用时才退回旧的固定密钥
+    fn initial_secret() -> String {
+        generate_secret().unwrap_or_else(|err| {
+            logging!(error, Type::Config, "{err}, falling back to the default secret");
+            LEGACY_DEFAULT_SECRET.into()
+        })
+    }
+
     fn guard(mut config: Mapping) -> Mapping {
         #[cfg(not(target_os = "windows"))]
         let redir_port = Self::guard_redir_port(&config);
@@ -380,6 +460,21 @@ fn test_clash_info() {
     assert_eq!(get_case(8888, "192.168.1.1:80800"), get_result(8888, "127.0.0.1:9097"));
 }
 
+#[test]
+fn test_secret_strength() {
+    assert_eq!(SecretStrength::assess("  "), SecretStrength::Empty);
+    assert_eq!(SecretStrength::assess(LEGACY_DEFAULT_SECRET), SecretStrength::Default);
+    assert_eq!(SecretStrength::assess("Mihomo"), SecretStrength::Default);
+    assert_eq!(SecretStrength::assess("hunter2"), SecretStrength::Weak);
+    assert_eq!(SecretStrength::assess("aaaaaaaaaaaaaaaaaaaa"), SecretStrength::Weak);
+    assert_eq!(SecretStrength::assess("correct-horse-battery"), SecretStrength::Strong);
+
+    let generated = generate_secret().unwrap_or_default();
+    assert_eq!(generated.len(), 32);
+    assert!(SecretStrength::assess(&generated).is_strong());
+    assert_ne!(generated, generate_secret().unwrap_or_default());
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct IClashExternalControllerCors {
@@ -3,11 +3,12 @@ mod clash;
 mod config;
 mod encrypt;
 mod prfitem;
+mod prfscope;
 pub mod profiles;
 pub mod runtime;
 mod verge;
 
-pub use self::{clash::*, config::*, encrypt::*, prfitem::*, profiles::*, verge::*};
+pub use self::{clash::*, config::*, encrypt::*, prfitem::*, prfscope::*, profiles::*, verge::*};
 
 pub const DEFAULT_PAC: &str = r#"function FindProxyForURL(url, host) {
   return "PROXY 127.0.0.1:%mixed-port%; SOCKS5 127.0.0.1:%mixed-port%; DIRECT;";
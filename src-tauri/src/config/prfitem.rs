@@ -1,5 +1,5 @@
 use crate::{
-    config::profiles,
+    config::{PrfScope, profiles},
     utils::{
         dirs, help,
         network::{NetworkManager, ProxyType},
@@ -21,7 +21,7 @@ pub struct PrfItem {
     pub uid: Option<String>,
 
     /// profile item type
-    /// enum value: remote | local | script | merge
+    /// enum value: remote | local | script | merge | rules | proxies | groups | wasm
     #[serde(rename = "type")]
     pub itype: Option<String>,
 
@@ -58,6 +58,10 @@ pub struct PrfItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub home: Option<String>,
 
+    /// user defined tags, used by the scope of global enhance items
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+
     /// the file data
     #[serde(skip)]
     pub file_data: Option<String>,
@@ -122,6 +126,15 @@ pub struct PrfOption {
     pub proxies: Option<String>,
 
     pub groups: Option<String>,
+
+    /// uid of the optional `wasm` enhance item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wasm: Option<String>,
+
+    /// for the global `Merge` and `Script` items
+    /// which profiles the item applies to, default is all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<PrfScope>,
 }
 
 impl PrfOption {
@@ -141,6 +154,8 @@ impl PrfOption {
                 result.rules = b_ref.rules.clone().or(result.rules);
                 result.proxies = b_ref.proxies.clone().or(result.proxies);
                 result.groups = b_ref.groups.clone().or(result.groups);
+                result.wasm = b_ref.wasm.clone().or(result.wasm);
+                result.scope = b_ref.scope.clone().or(result.scope);
                 result.timeout_seconds = b_ref.timeout_seconds.or(result.timeout_seconds);
                 Some(result)
             }
@@ -246,6 +261,7 @@ impl PrfItem {
                 ..PrfOption::default()
             }),
             home: None,
+            tags: None,
             updated: Some(chrono::Local::now().timestamp() as usize),
             file_data: Some(file_data.unwrap_or_else(|| tmpl::ITEM_LOCAL.into())),
         })
@@ -437,6 +453,7 @@ impl PrfItem {
                 ..PrfOption::default()
             }),
             home,
+            tags: None,
             updated: Some(chrono::Local::now().timestamp() as usize),
             file_data: Some(data.into()),
         })
@@ -526,6 +543,28 @@ impl PrfItem {
         })
     }
 
+    /// ## Wasm type (enhance)
+    /// create the enhanced item by copying a compiled WebAssembly module
+    pub async fn from_wasm(name: Option<String>, wasm: &[u8]) -> Result<Self> {
+        crate::enhance::wasm::validate_wasm(wasm)?;
+
+        let uid = help::get_uid("w").into();
+        let file = format!("{uid}.wasm").into(); // wasm ext
+        let path = dirs::app_profiles_dir()?.join(file.as_str());
+        fs::write(&path, wasm)
+            .await
+            .with_context(|| format!("failed to write to file \"{file}\""))?;
+
+        Ok(Self {
+            uid: Some(uid),
+            itype: Some("wasm".into()),
+            name,
+            file: Some(file),
+            updated: Some(chrono::Local::now().timestamp() as usize),
+            ..Default::default()
+        })
+    }
+
     /// get the file data
     pub async fn read_file(&self) -> Result<String> {
         let file = self
@@ -575,6 +614,11 @@ impl PrfItem {
     pub fn current_groups(&self) -> Option<&String> {
         self.option.as_ref().and_then(|o| o.groups.as_ref())
     }
+
+    /// 获取current指向的订阅的wasm
+    pub fn current_wasm(&self) -> Option<&String> {
+        self.option.as_ref().and_then(|o| o.wasm.as_ref())
+    }
 }
 
 // 向前兼容，默认为订阅启用自动更新
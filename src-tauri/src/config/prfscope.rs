@@ -0,0 +1,120 @@
+use super::PrfItem;
+use anyhow::{Context as _, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use smartstring::alias::String;
+use tauri::Url;
+
+/// 全局 Merge / Script 的作用范围
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum PrfScope {
+    /// 作用于所有订阅
+    #[default]
+    All,
+    /// 仅作用于列出的订阅 uid
+    Uids { uids: Vec<String> },
+    /// 仅作用于带有该标签的订阅
+    Tag { tag: String },
+    /// 仅作用于名称或订阅链接域名匹配正则的订阅
+    Pattern { pattern: String },
+}
+
+impl PrfScope {
+    /// 判断当前订阅是否在作用范围内
+    pub fn matches(&self, item: &PrfItem) -> Result<bool> {
+        match self {
+            Self::All => Ok(true),
+            Self::Uids { uids } => Ok(item.uid.as_ref().is_some_and(|uid| uids.contains(uid))),
+            Self::Tag { tag } => Ok(item.tags.as_ref().is_some_and(|tags| tags.contains(tag))),
+            Self::Pattern { pattern } => {
+                let re = Regex::new(pattern).with_context(|| format!("invalid scope pattern `{pattern}`"))?;
+                let name = item.name.as_deref().is_some_and(|name| re.is_match(name));
+                let host = item
+                    .url
+                    .as_deref()
+                    .and_then(|url| Url::parse(url).ok())
+                    .is_some_and(|url| url.host_str().is_some_and(|host| re.is_match(host)));
+                Ok(name || host)
+            }
+        }
+    }
+
+    /// 用于增强日志的简短描述
+    pub fn describe(&self) -> String {
+        match self {
+            Self::All => "all".into(),
+            Self::Uids { uids } => format!("uids [{}]", uids.join(", ")).into(),
+            Self::Tag { tag } => format!("tag `{tag}`").into(),
+            Self::Pattern { pattern } => format!("pattern `{pattern}`").into(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::PrfScope;
+    use crate::config::PrfItem;
+
+    fn remote_item() -> PrfItem {
+        PrfItem {
+            uid: Some("R1".into()),
+            name: Some("Provider A".into()),
+            url: Some("https://sub.example.com/api?token=1".into()),
+            tags: Some(vec!["work".into()]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn scope_matches_profile() {
+        let item = remote_item();
+
+        assert!(PrfScope::All.matches(&item).expect("all"));
+        assert!(
+            PrfScope::Uids {
+                uids: vec!["R0".into(), "R1".into()]
+            }
+            .matches(&item)
+            .expect("uids")
+        );
+        assert!(
+            !PrfScope::Uids {
+                uids: vec!["R2".into()]
+            }
+            .matches(&item)
+            .expect("uids")
+        );
+        assert!(PrfScope::Tag { tag: "work".into() }.matches(&item).expect("tag"));
+        assert!(!PrfScope::Tag { tag: "home".into() }.matches(&item).expect("tag"));
+        assert!(
+            PrfScope::Pattern {
+                pattern: r"example\.com$".into()
+            }
+            .matches(&item)
+            .expect("host")
+        );
+        assert!(
+            PrfScope::Pattern {
+                pattern: "^Provider".into()
+            }
+            .matches(&item)
+            .expect("name")
+        );
+        assert!(
+            !PrfScope::Pattern {
+                pattern: "token".into()
+            }
+            .matches(&item)
+            .expect("query")
+        );
+        assert!(PrfScope::Pattern { pattern: "(".into() }.matches(&item).is_err());
+    }
+
+    #[test]
+    fn scope_serde_shape() {
+        let scope: PrfScope = serde_yaml_ng::from_str("type: tag\ntag: work").expect("Failed to parse scope");
+        assert_eq!(scope, PrfScope::Tag { tag: "work".into() });
+    }
+}
@@ -204,6 +204,7 @@ impl IProfiles {
                 patch!(each, item, extra);
                 patch!(each, item, updated);
                 patch!(each, item, option);
+                patch!(each, item, tags);
 
                 self.items = Some(items);
                 return self.save_file().await;
@@ -273,6 +274,7 @@ impl IProfiles {
                     op.rules.clone(),
                     op.proxies.clone(),
                     op.groups.clone(),
+                    op.wasm.clone(),
                 ]
                 .into_iter()
                 .collect::<Vec<_>>()
@@ -492,6 +494,13 @@ impl IProfiles {
                     {
                         active_files.insert(file);
                     }
+
+                    if let Some(wasm_uid) = &option.wasm
+                        && let Ok(wasm_item) = self.get_item(wasm_uid)
+                        && let Some(file) = &wasm_item.file
+                    {
+                        active_files.insert(file);
+                    }
                 }
             }
         }
@@ -509,12 +518,14 @@ impl IProfiles {
         // r12345678.yaml (rules)
         // p12345678.yaml (proxies)
         // g12345678.yaml (groups)
+        // w12345678.wasm (wasm)
 
         let patterns = [
             r"^[RL][a-zA-Z0-9]+\.yaml$",  // Remote/Local profiles
             r"^m[a-zA-Z0-9]+\.yaml$",     // Merge files
             r"^s[a-zA-Z0-9]+\.js$",       // Script files
             r"^[rpg][a-zA-Z0-9]+\.yaml$", // Rules/Proxies/Groups files
+            r"^w[a-zA-Z0-9]+\.wasm$",     // Wasm files
         ];
 
         patterns.iter().any(|pattern| {
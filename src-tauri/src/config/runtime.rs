@@ -2,7 +2,7 @@ use serde_yaml_ng::{Mapping, Value};
 use smartstring::alias::String;
 use std::collections::{HashMap, HashSet};
 
-use crate::enhance::field::use_keys;
+use crate::enhance::{field::use_keys, vars::SecretValues};
 
 const PATCH_CONFIG_INNER: [&str; 5] = ["allow-lan", "ipv6", "log-level", "unified-delay", "tunnels"];
 
@@ -14,6 +14,8 @@ pub struct IRuntime {
     pub exists_keys: HashSet<String>,
     // TODO 或许可以用 FixMap 来存储以提升效率
     pub chain_logs: HashMap<String, Vec<(String, String)>>,
+    /// 机密变量与局域网用户密码在配置中的位置
+    pub secret_values: SecretValues,
 }
 
 impl IRuntime {
@@ -1,6 +1,7 @@
 use crate::config::Config;
 use crate::{
     config::{DEFAULT_PAC, deserialize_encrypted, serialize_encrypted},
+    core::{managed_core, pac::DEFAULT_PAC_RULE_LIMIT},
     utils::{dirs, help},
 };
 use anyhow::Result;
@@ -8,6 +9,7 @@ use clash_verge_logging::{Type, logging};
 use log::LevelFilter;
 use serde::{Deserialize, Serialize};
 use smartstring::alias::String;
+use std::collections::HashMap;
 
 /// ### `verge.yaml` schema
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
@@ -109,6 +111,9 @@ pub struct IVerge {
     /// set system proxy bypass
     pub system_proxy_bypass: Option<String>,
 
+    /// 追加到系统代理绕过列表的预设: lan / intranet / domestic-direct
+    pub system_proxy_bypass_presets: Option<Vec<String>>,
+
     /// proxy guard duration
     pub proxy_guard_duration: Option<u64>,
 
@@ -118,6 +123,12 @@ pub struct IVerge {
     /// pac script content
     pub pac_file_content: Option<String>,
 
+    /// 由运行时规则生成 PAC，代替自定义脚本
+    pub pac_generate_from_rules: Option<bool>,
+
+    /// 生成 PAC 时的规则数上限
+    pub pac_rule_limit: Option<usize>,
+
     /// proxy host address
     pub proxy_host: Option<String>,
 
@@ -164,6 +175,12 @@ pub struct IVerge {
     /// 是否使用内部的脚本支持，默认为真
     pub enable_builtin_enhanced: Option<bool>,
 
+    /// 单独禁用的内建转换 uid
+    pub disabled_builtin_enhanced: Option<Vec<String>>,
+
+    /// 是否缓存增强流程各阶段的结果，默认为真
+    pub enable_enhance_cache: Option<bool>,
+
     /// proxy 页面布局 列数
     pub proxy_layout_column: Option<u8>,
 
@@ -183,6 +200,27 @@ pub struct IVerge {
     /// Create backups automatically when critical configs change
     pub auto_backup_on_change: Option<bool>,
 
+    /// 是否启用核心健康检查
+    pub enable_core_watchdog: Option<bool>,
+
+    /// 核心健康检查间隔（秒）
+    pub core_watchdog_interval: Option<u64>,
+
+    /// 连续失败多少次后开始恢复
+    pub core_watchdog_threshold: Option<u32>,
+
+    /// 健康检查允许的最大响应时间（毫秒）
+    pub core_watchdog_max_latency: Option<u64>,
+
+    /// 自动升级服务：未设置时仅在版本不兼容时升级，`true` 时有新版本也升级，`false` 时只提示
+    pub auto_upgrade_service: Option<bool>,
+
+    /// 内核日志缓冲区保留的条数
+    pub core_log_buffer_size: Option<usize>,
+
+    /// 启动前端口被占用时的处理方式
+    pub port_conflict_strategy: Option<PortConflictStrategy>,
+
     /// verge 的各种 port 用于覆盖 clash 的各种 port
     #[cfg(not(target_os = "windows"))]
     pub verge_redir_port: Option<u16>,
@@ -233,6 +271,63 @@ pub struct IVerge {
     )]
     pub webdav_password: Option<String>,
 
+    /// 订阅与扩展中 `${VAR}` 引用的变量
+    pub variables: Option<HashMap<String, String>>,
+
+    /// 连接外部运行的内核，不启动 sidecar 或服务
+    pub enable_external_core: Option<bool>,
+
+    /// 外部内核控制器地址，如 `http://192.168.1.1:9090`
+    pub external_core_url: Option<String>,
+
+    /// 外部内核控制器的 unix socket 路径，优先于地址
+    pub external_core_socket: Option<String>,
+
+    /// 外部内核控制器密钥 (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub external_core_secret: Option<String>,
+
+    /// 启用本地自动化 REST API，仅监听回环地址
+    pub enable_automation_api: Option<bool>,
+
+    /// 自动化 API 的访问令牌 (加密存储)
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub automation_api_tokens: Option<Vec<IVergeApiToken>>,
+
+    /// 局域网共享的认证用户 (加密存储)，生成配置时写入 authentication
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub lan_sharing_users: Option<Vec<ILanUser>>,
+
+    /// 局域网共享使用的网卡名称，为空时自动选择
+    pub lan_sharing_interface: Option<String>,
+
+    /// 深链接的可信来源域名，含子域名，来自这些域名的 https 导入无需确认
+    pub deep_link_trusted_domains: Option<Vec<String>>,
+
+    /// 机密变量 (加密存储)，运行时配置展示时会被隐去
+    #[serde(
+        serialize_with = "serialize_encrypted",
+        deserialize_with = "deserialize_encrypted",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub secret_variables: Option<HashMap<String, String>>,
+
     #[serde(skip)]
     pub enable_tray_speed: Option<bool>,
 
@@ -259,6 +354,17 @@ pub struct IVerge {
     pub enable_external_controller: Option<bool>,
 }
 
+/// 启动前端口被占用时的处理方式
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PortConflictStrategy {
+    /// 自动换用空闲端口
+    #[default]
+    Auto,
+    /// 拒绝启动
+    Refuse,
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct IVergeTestItem {
     pub uid: Option<String>,
@@ -267,6 +373,24 @@ pub struct IVergeTestItem {
     pub url: Option<String>,
 }
 
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct IVergeApiToken {
+    pub name: String,
+    pub token: String,
+    /// 创建时间，unix 秒
+    pub created_at: i64,
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct ILanUser {
+    /// 设备备注
+    pub name: String,
+    pub username: String,
+    pub password: String,
+    /// 创建时间，unix 秒
+    pub created_at: i64,
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct IVergeTheme {
     pub primary_color: Option<String>,
@@ -287,6 +411,12 @@ impl IVerge {
     /// 有效的clash核心名称
     pub const VALID_CLASH_CORES: &'static [&'static str] = &["verge-mihomo", "verge-mihomo-alpha"];
 
+    /// 内置内核或已安装的受管内核
+    pub fn is_valid_clash_core(clash_core: &str) -> bool {
+        Self::VALID_CLASH_CORES.contains(&clash_core)
+            || managed_core::managed_core_path(clash_core).is_ok_and(|path| path.is_some())
+    }
+
     /// 验证并修正配置文件中的clash_core值
     pub async fn validate_and_fix_config() -> Result<()> {
         let config_path = dirs::verge_path()?;
@@ -299,7 +429,7 @@ impl IVerge {
 
         if let Some(ref core) = config.clash_core {
             let core_str = core.trim();
-            if core_str.is_empty() || !Self::VALID_CLASH_CORES.contains(&core_str) {
+            if core_str.is_empty() || !Self::is_valid_clash_core(core_str) {
                 logging!(
                     warn,
                     Type::Config,
@@ -410,6 +540,8 @@ impl IVerge {
             enable_system_proxy: Some(false),
             proxy_auto_config: Some(false),
             pac_file_content: Some(DEFAULT_PAC.into()),
+            pac_generate_from_rules: Some(false),
+            pac_rule_limit: Some(DEFAULT_PAC_RULE_LIMIT),
             proxy_host: Some("127.0.0.1".into()),
             #[cfg(not(target_os = "windows"))]
             verge_redir_port: Some(7895),
@@ -427,14 +559,25 @@ impl IVerge {
             enable_proxy_guard: Some(false),
             enable_bypass_check: Some(true),
             use_default_bypass: Some(true),
+            system_proxy_bypass_presets: Some(vec![]),
             proxy_guard_duration: Some(30),
             auto_close_connection: Some(true),
             auto_check_update: Some(true),
             enable_builtin_enhanced: Some(true),
+            enable_enhance_cache: Some(true),
             auto_log_clean: Some(2), // 1: 1天, 2: 7天, 3: 30天, 4: 90天
             enable_auto_backup_schedule: Some(false),
             auto_backup_interval_hours: Some(24),
             auto_backup_on_change: Some(true),
+            enable_core_watchdog: Some(false),
+            core_watchdog_interval: Some(30),
+            core_watchdog_threshold: Some(3),
+            core_watchdog_max_latency: Some(3000),
+            auto_upgrade_service: None,
+            core_log_buffer_size: Some(1000),
+            port_conflict_strategy: Some(PortConflictStrategy::Auto),
+            enable_automation_api: Some(false),
+            deep_link_trusted_domains: Some(vec![]),
             webdav_url: None,
             webdav_username: None,
             webdav_password: None,
@@ -516,9 +659,12 @@ impl IVerge {
         patch!(enable_bypass_check);
         patch!(use_default_bypass);
         patch!(system_proxy_bypass);
+        patch!(system_proxy_bypass_presets);
         patch!(proxy_guard_duration);
         patch!(proxy_auto_config);
         patch!(pac_file_content);
+        patch!(pac_generate_from_rules);
+        patch!(pac_rule_limit);
         patch!(proxy_host);
         patch!(theme_setting);
         patch!(web_ui_list);
@@ -533,16 +679,36 @@ impl IVerge {
         patch!(enable_auto_delay_detection);
         patch!(auto_delay_detection_interval_minutes);
         patch!(enable_builtin_enhanced);
+        patch!(disabled_builtin_enhanced);
+        patch!(enable_enhance_cache);
         patch!(proxy_layout_column);
         patch!(test_list);
         patch!(auto_log_clean);
         patch!(enable_auto_backup_schedule);
         patch!(auto_backup_interval_hours);
         patch!(auto_backup_on_change);
+        patch!(enable_core_watchdog);
+        patch!(core_watchdog_interval);
+        patch!(core_watchdog_threshold);
+        patch!(core_watchdog_max_latency);
 
         patch!(webdav_url);
         patch!(webdav_username);
         patch!(webdav_password);
+        patch!(variables);
+        patch!(secret_variables);
+        patch!(auto_upgrade_service);
+        patch!(core_log_buffer_size);
+        patch!(port_conflict_strategy);
+        patch!(enable_external_core);
+        patch!(external_core_url);
+        patch!(external_core_socket);
+        patch!(external_core_secret);
+        patch!(enable_automation_api);
+        patch!(automation_api_tokens);
+        patch!(lan_sharing_users);
+        patch!(lan_sharing_interface);
+        patch!(deep_link_trusted_domains);
         patch!(enable_tray_speed);
         // patch!(enable_tray_icon);
         patch!(tray_proxy_groups_display_mode);
@@ -25,6 +25,17 @@ pub mod timing {
     pub const CONFIG_UPDATE_DEBOUNCE: Duration = Duration::from_millis(300);
     pub const STARTUP_ERROR_DELAY: Duration = Duration::from_secs(2);
 
+    pub const CORE_RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+    pub const CORE_RESTART_MAX_DELAY: Duration = Duration::from_secs(30);
+    /// 时间窗口内崩溃超过次数上限后停止自动重启
+    pub const CORE_CRASH_WINDOW: Duration = Duration::from_secs(120);
+    pub const CORE_CRASH_LIMIT: usize = 5;
+
+    /// 应用配置后的健康检查
+    pub const CONFIG_HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+    pub const CONFIG_HEALTH_INTERVAL: Duration = Duration::from_millis(500);
+    pub const CONFIG_HEALTH_RETRIES: u32 = 5;
+
     #[cfg(target_os = "windows")]
     pub const SERVICE_WAIT_MAX: Duration = Duration::from_millis(3000);
     #[cfg(target_os = "windows")]
@@ -34,6 +45,8 @@ pub mod timing {
 pub mod files {
     pub const RUNTIME_CONFIG: &str = "clash-verge.yaml";
     pub const CHECK_CONFIG: &str = "clash-verge-check.yaml";
+    pub const LAST_GOOD_CONFIG: &str = "clash-verge-last-good.yaml";
+    pub const PREVIOUS_GOOD_CONFIG: &str = "clash-verge-previous-good.yaml";
     pub const DNS_CONFIG: &str = "dns_config.yaml";
     pub const WINDOW_STATE: &str = "window_state.json";
 }
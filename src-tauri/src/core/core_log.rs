@@ -0,0 +1,530 @@
+use anyhow::{Context as _, Result};
+use compact_str::CompactString;
+use log::Level;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use smartstring::alias::String;
+use std::collections::VecDeque;
+
+pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+pub const MIN_LOG_CAPACITY: usize = 100;
+pub const MAX_LOG_CAPACITY: usize = 100_000;
+
+/// 本次会话内的内核日志，核心重启后保留
+pub static CORE_LOGS: Lazy<CoreLogBuffer> = Lazy::new(|| CoreLogBuffer::new(DEFAULT_LOG_CAPACITY));
+
+/// mihomo 使用 logrus 文本格式：`time="..." level=info msg="..."`
+static LOGRUS_RE: Lazy<Option<Regex>> =
+    Lazy::new(|| Regex::new(r#"^time="([^"]*)"\s+level=(\w+)\s+msg="((?:[^"\\]|\\.)*)""#).ok());
+
+/// `127.0.0.1:5000(chrome) --> example.com:443 match DomainSuffix(example.com) using Proxy`
+static CONNECTION_RE: Lazy<Option<Regex>> = Lazy::new(|| {
+    Regex::new(
+        r"^(?P<src>[^\s(]+)(?:\((?P<process>[^)]*)\))? --> (?P<dst>\S+)(?: match (?P<rule>[\w-]+)(?:\((?P<payload>.*)\))?| doesn't match any rule)? using (?P<proxy>.+)$",
+    )
+    .ok()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoreLogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl CoreLogLevel {
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.to_ascii_lowercase().as_str() {
+            "trace" | "debug" => Some(Self::Debug),
+            "info" => Some(Self::Info),
+            "warn" | "warning" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            "fatal" | "panic" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+
+    /// 写入 sidecar 日志文件时使用的级别
+    pub const fn as_log_level(self) -> Level {
+        match self {
+            Self::Debug => Level::Debug,
+            Self::Info => Level::Info,
+            Self::Warning => Level::Warn,
+            Self::Error | Self::Fatal => Level::Error,
+        }
+    }
+}
+
+/// 连接日志中的元数据
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionMeta {
+    /// TCP / UDP
+    pub network: String,
+    pub source: String,
+    pub process: Option<String>,
+    pub destination: String,
+    pub rule: Option<String>,
+    pub rule_payload: Option<String>,
+    pub proxy: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreLogEntry {
+    /// 会话内递增的序号
+    pub seq: u64,
+    /// 第几次启动内核时产生的日志
+    pub run: u64,
+    /// 日志时间戳 (ms)，无法解析时为空
+    pub timestamp: Option<i64>,
+    pub level: CoreLogLevel,
+    /// 消息前缀中的模块，如 `TCP`、`DNS`
+    pub component: Option<String>,
+    pub message: String,
+    pub connection: Option<ConnectionMeta>,
+    #[serde(skip)]
+    pub raw: CompactString,
+}
+
+impl CoreLogEntry {
+    /// 解析一行内核输出，非 logrus 格式的行按输出流决定级别
+    pub fn parse(line: &str, from_stderr: bool) -> Self {
+        let line = line.trim_end();
+        let fallback_level = if from_stderr {
+            CoreLogLevel::Error
+        } else {
+            CoreLogLevel::Info
+        };
+
+        let captures = LOGRUS_RE.as_ref().and_then(|re| re.captures(line));
+        let (timestamp, level, message) = match captures {
+            Some(caps) => (
+                chrono::DateTime::parse_from_rfc3339(&caps[1])
+                    .ok()
+                    .map(|time| time.timestamp_millis()),
+                CoreLogLevel::parse(&caps[2]).unwrap_or(fallback_level),
+                unescape(&caps[3]),
+            ),
+            None => (None, fallback_level, line.into()),
+        };
+
+        let (component, body) = split_component(&message);
+        let connection = component
+            .as_deref()
+            .filter(|component| matches!(*component, "TCP" | "UDP"))
+            .and_then(|network| parse_connection(network, body));
+
+        Self {
+            seq: 0,
+            run: 0,
+            timestamp,
+            level,
+            component,
+            message: body.into(),
+            connection,
+            raw: line.into(),
+        }
+    }
+}
+
+fn unescape(message: &str) -> String {
+    let mut result = String::new();
+    let mut chars = message.chars();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.clone().next()) {
+            ('\\', Some(next @ ('"' | '\\'))) => {
+                result.push(next);
+                chars.next();
+            }
+            ('\\', Some('n')) => {
+                result.push('\n');
+                chars.next();
+            }
+            _ => result.push(ch),
+        }
+    }
+    result
+}
+
+fn split_component(message: &str) -> (Option<String>, &str) {
+    message
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .filter(|(component, _)| !component.is_empty() && !component.contains(' '))
+        .map_or((None, message), |(component, body)| {
+            (Some(component.into()), body.trim_start())
+        })
+}
+
+fn parse_connection(network: &str, body: &str) -> Option<ConnectionMeta> {
+    let caps = CONNECTION_RE.as_ref()?.captures(body)?;
+    let field = |name: &str| caps.name(name).map(|value| String::from(value.as_str()));
+    Some(ConnectionMeta {
+        network: network.into(),
+        source: field("src")?,
+        process: field("process").filter(|process| !process.is_empty()),
+        destination: field("dst")?,
+        rule: field("rule"),
+        rule_payload: field("payload"),
+        proxy: field("proxy")?,
+    })
+}
+
+/// 日志查询条件，文本条件均不区分大小写
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CoreLogQuery {
+    /// 为空时不按级别过滤
+    pub levels: Vec<CoreLogLevel>,
+    /// 起始时间 (ms)
+    pub since: Option<i64>,
+    /// 结束时间 (ms)
+    pub until: Option<i64>,
+    pub keyword: Option<String>,
+    /// 将 keyword 视为正则表达式
+    pub regex: bool,
+    pub component: Option<String>,
+    pub network: Option<String>,
+    pub source: Option<String>,
+    pub destination: Option<String>,
+    pub process: Option<String>,
+    pub rule: Option<String>,
+    pub proxy: Option<String>,
+    /// 只返回最近的若干条
+    pub limit: Option<usize>,
+}
+
+pub struct CoreLogFilter {
+    query: CoreLogQuery,
+    keyword: Option<Regex>,
+}
+
+impl CoreLogFilter {
+    pub fn new(query: CoreLogQuery) -> Result<Self> {
+        let keyword = match query.keyword.as_deref().filter(|keyword| !keyword.is_empty()) {
+            Some(keyword) => {
+                let pattern = if query.regex {
+                    keyword.into()
+                } else {
+                    regex::escape(keyword)
+                };
+                Some(
+                    RegexBuilder::new(&pattern)
+                        .case_insensitive(true)
+                        .build()
+                        .with_context(|| format!("invalid log search pattern `{keyword}`"))?,
+                )
+            }
+            None => None,
+        };
+        Ok(Self { query, keyword })
+    }
+
+    pub fn matches(&self, entry: &CoreLogEntry) -> bool {
+        let query = &self.query;
+        if !query.levels.is_empty() && !query.levels.contains(&entry.level) {
+            return false;
+        }
+        if query
+            .since
+            .is_some_and(|since| entry.timestamp.is_none_or(|time| time < since))
+            || query
+                .until
+                .is_some_and(|until| entry.timestamp.is_none_or(|time| time > until))
+        {
+            return false;
+        }
+        if let Some(keyword) = &self.keyword
+            && !keyword.is_match(&entry.raw)
+        {
+            return false;
+        }
+        if !contains(query.component.as_deref(), entry.component.as_deref()) {
+            return false;
+        }
+
+        let has_connection_filter = [
+            &query.network,
+            &query.source,
+            &query.destination,
+            &query.process,
+            &query.rule,
+            &query.proxy,
+        ]
+        .iter()
+        .any(|filter| filter.is_some());
+        if !has_connection_filter {
+            return true;
+        }
+        let Some(conn) = &entry.connection else {
+            return false;
+        };
+        contains(query.network.as_deref(), Some(&conn.network))
+            && contains(query.source.as_deref(), Some(&conn.source))
+            && contains(query.destination.as_deref(), Some(&conn.destination))
+            && contains(query.process.as_deref(), conn.process.as_deref())
+            && contains(query.rule.as_deref(), conn.rule.as_deref())
+            && contains(query.proxy.as_deref(), Some(&conn.proxy))
+    }
+
+    pub fn apply(&self, entries: impl IntoIterator<Item = CoreLogEntry>) -> Vec<CoreLogEntry> {
+        let mut matched = entries
+            .into_iter()
+            .filter(|entry| self.matches(entry))
+            .collect::<Vec<_>>();
+        if let Some(limit) = self.query.limit {
+            matched.drain(..matched.len().saturating_sub(limit));
+        }
+        matched
+    }
+}
+
+fn contains(filter: Option<&str>, value: Option<&str>) -> bool {
+    match (filter, value) {
+        (None, _) => true,
+        (Some(filter), Some(value)) => value.to_lowercase().contains(&filter.to_lowercase()),
+        (Some(_), None) => false,
+    }
+}
+
+/// 解析服务模式返回的原始日志
+pub fn parse_lines(lines: &[CompactString]) -> Vec<CoreLogEntry> {
+    lines
+        .iter()
+        .zip(1..)
+        .map(|(line, seq)| CoreLogEntry {
+            seq,
+            ..CoreLogEntry::parse(line, false)
+        })
+        .collect()
+}
+
+struct BufferState {
+    entries: VecDeque<CoreLogEntry>,
+    capacity: usize,
+    next_seq: u64,
+    run: u64,
+}
+
+/// 定长环形缓冲区，超出容量时丢弃最早的日志
+pub struct CoreLogBuffer {
+    state: RwLock<BufferState>,
+}
+
+impl CoreLogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: RwLock::new(BufferState {
+                entries: VecDeque::new(),
+                capacity: capacity.clamp(MIN_LOG_CAPACITY, MAX_LOG_CAPACITY),
+                next_seq: 1,
+                run: 0,
+            }),
+        }
+    }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state.write();
+        state.capacity = capacity.clamp(MIN_LOG_CAPACITY, MAX_LOG_CAPACITY);
+        let overflow = state.entries.len().saturating_sub(state.capacity);
+        state.entries.drain(..overflow);
+    }
+
+    /// 内核启动时调用，返回本次运行的编号
+    pub fn begin_run(&self) -> u64 {
+        let mut state = self.state.write();
+        state.run += 1;
+        state.run
+    }
+
+    pub fn push(&self, line: &str, from_stderr: bool) -> CoreLogEntry {
+        let mut state = self.state.write();
+        let entry = CoreLogEntry {
+            seq: state.next_seq,
+            run: state.run,
+            ..CoreLogEntry::parse(line, from_stderr)
+        };
+        state.next_seq += 1;
+        if state.entries.len() >= state.capacity {
+            state.entries.pop_front();
+        }
+        state.entries.push_back(entry.clone());
+        entry
+    }
+
+    /// 原始日志行
+    pub fn lines(&self) -> Vec<CompactString> {
+        self.state
+            .read()
+            .entries
+            .iter()
+            .map(|entry| entry.raw.clone())
+            .collect()
+    }
+
+    /// 某次运行产生的原始日志行
+    pub fn run_lines(&self, run: u64) -> Vec<CompactString> {
+        self.state
+            .read()
+            .entries
+            .iter()
+            .filter(|entry| entry.run == run)
+            .map(|entry| entry.raw.clone())
+            .collect()
+    }
+
+    pub fn query(&self, filter: &CoreLogFilter) -> Vec<CoreLogEntry> {
+        filter.apply(self.state.read().entries.iter().cloned())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{CoreLogBuffer, CoreLogEntry, CoreLogFilter, CoreLogLevel, CoreLogQuery, MIN_LOG_CAPACITY};
+
+    const TCP_LINE: &str = r#"time="2025-01-02T03:04:05.123456789+08:00" level=info msg="[TCP] 127.0.0.1:50000(chrome) --> www.example.com:443 match DomainSuffix(example.com) using Proxy[HK 01]""#;
+
+    #[test]
+    fn parse_logrus_line() {
+        let entry = CoreLogEntry::parse(TCP_LINE, false);
+        assert_eq!(entry.level, CoreLogLevel::Info);
+        assert_eq!(entry.timestamp, Some(1_735_758_245_123));
+        assert_eq!(entry.component.as_deref(), Some("TCP"));
+
+        let conn = entry.connection.expect("connection");
+        assert_eq!(conn.source, "127.0.0.1:50000");
+        assert_eq!(conn.process.as_deref(), Some("chrome"));
+        assert_eq!(conn.destination, "www.example.com:443");
+        assert_eq!(conn.rule.as_deref(), Some("DomainSuffix"));
+        assert_eq!(conn.rule_payload.as_deref(), Some("example.com"));
+        assert_eq!(conn.proxy, "Proxy[HK 01]");
+
+        let entry = CoreLogEntry::parse(
+            r#"time="2025-01-02T03:04:05Z" level=warning msg="[UDP] 10.0.0.2:5353 --> 8.8.8.8:53 doesn't match any rule using DIRECT""#,
+            false,
+        );
+        assert_eq!(entry.level, CoreLogLevel::Warning);
+        let conn = entry.connection.expect("connection");
+        assert_eq!(conn.network, "UDP");
+        assert_eq!(conn.process, None);
+        assert_eq!(conn.rule, None);
+        assert_eq!(conn.proxy, "DIRECT");
+    }
+
+    #[test]
+    fn parse_other_lines() {
+        let entry = CoreLogEntry::parse(
+            r#"time="2025-01-02T03:04:05Z" level=error msg="[Config] parse \"rules\" failed: bad rule""#,
+            false,
+        );
+        assert_eq!(entry.level, CoreLogLevel::Error);
+        assert_eq!(entry.component.as_deref(), Some("Config"));
+        assert_eq!(entry.message, r#"parse "rules" failed: bad rule"#);
+        assert!(entry.connection.is_none());
+
+        let entry = CoreLogEntry::parse("panic: runtime error\n", true);
+        assert_eq!(entry.level, CoreLogLevel::Error);
+        assert_eq!(entry.timestamp, None);
+        assert_eq!(entry.message, "panic: runtime error");
+        assert_eq!(
+            CoreLogEntry::parse("Start initial configuration", false).level,
+            CoreLogLevel::Info
+        );
+    }
+
+    #[test]
+    fn buffer_keeps_logs_across_runs() {
+        let buffer = CoreLogBuffer::new(0);
+        let first = buffer.begin_run();
+        for index in 0..MIN_LOG_CAPACITY {
+            buffer.push(&format!("line {index}"), false);
+        }
+        let second = buffer.begin_run();
+        buffer.push("restarted", false);
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), MIN_LOG_CAPACITY);
+        assert_eq!(lines.first().map(|line| line.as_str()), Some("line 1"));
+        assert_eq!(buffer.run_lines(second), vec!["restarted"]);
+        assert_eq!(buffer.run_lines(first).len(), MIN_LOG_CAPACITY - 1);
+
+        buffer.set_capacity(usize::MAX);
+        buffer.push("more", false);
+        assert_eq!(buffer.lines().len(), MIN_LOG_CAPACITY + 1);
+    }
+
+    #[test]
+    fn query_filters() {
+        let buffer = CoreLogBuffer::new(MIN_LOG_CAPACITY);
+        buffer.push(TCP_LINE, false);
+        buffer.push(
+            r#"time="2025-01-02T03:05:00Z" level=error msg="[DNS] resolve example.org failed""#,
+            false,
+        );
+        buffer.push("panic: boom", true);
+
+        let query = |query: CoreLogQuery| {
+            let filter = CoreLogFilter::new(query).expect("filter");
+            buffer.query(&filter).iter().map(|entry| entry.seq).collect::<Vec<_>>()
+        };
+
+        assert_eq!(query(CoreLogQuery::default()), vec![1, 2, 3]);
+        assert_eq!(
+            query(CoreLogQuery {
+                levels: vec![CoreLogLevel::Error],
+                ..Default::default()
+            }),
+            vec![2, 3]
+        );
+        assert_eq!(
+            query(CoreLogQuery {
+                since: Some(1_735_786_000_000),
+                ..Default::default()
+            }),
+            vec![2]
+        );
+        assert_eq!(
+            query(CoreLogQuery {
+                keyword: Some("EXAMPLE.".into()),
+                ..Default::default()
+            }),
+            vec![1, 2]
+        );
+        assert_eq!(
+            query(CoreLogQuery {
+                keyword: Some(r"example\.(org|net)".into()),
+                regex: true,
+                ..Default::default()
+            }),
+            vec![2]
+        );
+        assert_eq!(
+            query(CoreLogQuery {
+                process: Some("Chrome".into()),
+                proxy: Some("hk".into()),
+                ..Default::default()
+            }),
+            vec![1]
+        );
+        assert_eq!(
+            query(CoreLogQuery {
+                limit: Some(1),
+                ..Default::default()
+            }),
+            vec![3]
+        );
+        assert!(
+            CoreLogFilter::new(CoreLogQuery {
+                keyword: Some("(".into()),
+                regex: true,
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+}
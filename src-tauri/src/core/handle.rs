@@ -1,8 +1,15 @@
-use crate::{APP_HANDLE, singleton};
+use crate::{
+    APP_HANDLE,
+    config::IClashTemp,
+    core::manager::ExternalController,
+    singleton,
+    utils::{headless, resolve::scheme::DeepLinkPreview},
+};
+use clash_verge_logging::{Type, logging};
 use smartstring::alias::String;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::AppHandle;
-use tauri_plugin_mihomo::{Mihomo, MihomoExt as _};
+use tauri_plugin_mihomo::{Mihomo, MihomoExt as _, models::Protocol};
 use tokio::sync::RwLockReadGuard;
 
 use super::notification::{FrontendEvent, NotificationSystem};
@@ -36,6 +43,38 @@ impl Handle {
         Self::app_handle().mihomo().read().await
     }
 
+    /// 控制器连接切换到外部内核
+    pub async fn use_external_controller(controller: &ExternalController) {
+        {
+            let mut mihomo = Self::app_handle().mihomo().write().await;
+            match controller {
+                ExternalController::Http { host, port, secret } => {
+                    mihomo.update_protocol(Protocol::Http);
+                    mihomo.update_external_host(Some(host.to_string()));
+                    mihomo.update_external_port(Some(u32::from(*port)));
+                    mihomo.update_secret(secret.as_ref().map(ToString::to_string));
+                }
+                ExternalController::Socket { path, secret } => {
+                    mihomo.update_protocol(Protocol::LocalSocket);
+                    mihomo.update_socket_path(Some(path.to_string()));
+                    mihomo.update_secret(secret.as_ref().map(ToString::to_string));
+                }
+            }
+        }
+        let _ = Self::mihomo().await.clear_all_ws_connections().await;
+    }
+
+    /// 控制器连接恢复为本地内核的 IPC
+    pub async fn use_local_controller() {
+        {
+            let mut mihomo = Self::app_handle().mihomo().write().await;
+            mihomo.update_protocol(Protocol::LocalSocket);
+            mihomo.update_socket_path(Some(IClashTemp::guard_external_controller_ipc().to_string()));
+            mihomo.update_secret(None);
+        }
+        let _ = Self::mihomo().await.clear_all_ws_connections().await;
+    }
+
     pub fn refresh_clash() {
         Self::send_event(FrontendEvent::RefreshClash);
     }
@@ -62,10 +101,21 @@ impl Handle {
         Self::send_event(FrontendEvent::ProfileUpdateCompleted { uid });
     }
 
+    /// 请求前端确认深链接
+    pub fn notify_deep_link_confirm(preview: &DeepLinkPreview) {
+        Self::send_event(FrontendEvent::DeepLinkConfirm { preview });
+    }
+
     pub fn notice_message<S: AsRef<str>, M: Into<String>>(status: S, msg: M) {
         let status_str = status.as_ref();
         let msg_str = msg.into();
 
+        // 无界面模式没有前端，提示写入日志
+        if headless::is_headless() {
+            logging!(info, Type::Frontend, "[{status_str}] {msg_str}");
+            return;
+        }
+
         Self::send_event(FrontendEvent::NoticeMessage {
             status: status_str,
             message: msg_str,
@@ -0,0 +1,444 @@
+use crate::{
+    core::handle::Handle,
+    utils::{
+        dirs,
+        network::{NetworkManager, ProxyType},
+    },
+};
+use anyhow::{Context as _, Result, anyhow, bail};
+use clash_verge_logging::{Type, logging};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use smartstring::alias::String;
+use std::{
+    fmt::Write as _,
+    io::Read as _,
+    path::{Path, PathBuf},
+};
+use tauri_plugin_shell::{ShellExt as _, process::Command};
+
+/// 受管内核在 `clash_core` 中的前缀，如 `managed:v1.19.2`
+pub const MANAGED_CORE_PREFIX: &str = "managed:";
+
+const CORE_BINARY: &str = if cfg!(windows) { "mihomo.exe" } else { "mihomo" };
+const CORE_META: &str = "core.json";
+const DOWNLOAD_TIMEOUT_SECS: u64 = 300;
+/// 解压后的内核大小上限，防止压缩炸弹
+const MAX_CORE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// 已安装的受管内核
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManagedCoreInfo {
+    /// 写入 `clash_core` 的标识
+    pub id: String,
+    pub version: String,
+    pub sha256: String,
+    /// 下载地址或本地文件路径
+    pub source: String,
+    /// 安装时间戳 (ms)
+    pub installed_at: i64,
+    /// `-v` 输出的版本信息
+    pub reported_version: Option<String>,
+}
+
+/// 内核安装来源
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum CoreSource {
+    Url { url: String },
+    File { path: String },
+}
+
+impl CoreSource {
+    fn describe(&self) -> &str {
+        match self {
+            Self::Url { url } => url,
+            Self::File { path } => path,
+        }
+    }
+}
+
+pub fn is_managed_core(clash_core: &str) -> bool {
+    clash_core.starts_with(MANAGED_CORE_PREFIX)
+}
+
+/// 受管内核对应的内置内核类型，用于判断扩展与内建转换是否适用
+pub fn core_flavour(clash_core: &str) -> &str {
+    match clash_core.strip_prefix(MANAGED_CORE_PREFIX) {
+        Some(version) if version.to_ascii_lowercase().contains("alpha") => "verge-mihomo-alpha",
+        Some(_) => "verge-mihomo",
+        None => clash_core,
+    }
+}
+
+/// 受管内核的可执行文件路径，非受管内核返回 None
+pub fn managed_core_path(clash_core: &str) -> Result<Option<PathBuf>> {
+    let Some(version) = clash_core.strip_prefix(MANAGED_CORE_PREFIX) else {
+        return Ok(None);
+    };
+    let store = CoreStore::new()?;
+    let path = store.binary_path(version)?;
+    if !path.is_file() {
+        bail!("managed core `{version}` is not installed");
+    }
+    Ok(Some(path))
+}
+
+/// 内置 sidecar 直接使用，受管内核使用安装目录下的文件
+pub fn core_command(clash_core: &str) -> Result<Command> {
+    let shell = Handle::app_handle().shell();
+    match managed_core_path(clash_core)? {
+        Some(path) => Ok(shell.command(path)),
+        None => Ok(shell.sidecar(clash_core)?),
+    }
+}
+
+/// 受管内核的安装目录，每个版本一个子目录
+pub struct CoreStore {
+    root: PathBuf,
+}
+
+impl CoreStore {
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_dir(dirs::managed_cores_dir()?))
+    }
+
+    pub const fn with_dir(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn version_dir(&self, version: &str) -> Result<PathBuf> {
+        validate_version(version)?;
+        Ok(self.root.join(version))
+    }
+
+    pub fn binary_path(&self, version: &str) -> Result<PathBuf> {
+        Ok(self.version_dir(version)?.join(CORE_BINARY))
+    }
+
+    pub async fn list(&self) -> Result<Vec<ManagedCoreInfo>> {
+        if !tokio::fs::try_exists(&self.root).await.unwrap_or(false) {
+            return Ok(Vec::new());
+        }
+
+        let mut cores = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.root).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let meta_path = entry.path().join(CORE_META);
+            let Ok(content) = tokio::fs::read_to_string(&meta_path).await else {
+                continue;
+            };
+            match serde_json::from_str::<ManagedCoreInfo>(&content) {
+                Ok(info) => cores.push(info),
+                Err(err) => logging!(
+                    warn,
+                    Type::Core,
+                    "Invalid managed core meta {}: {err}",
+                    meta_path.display()
+                ),
+            }
+        }
+        cores.sort_by(|a, b| b.installed_at.cmp(&a.installed_at));
+        Ok(cores)
+    }
+
+    /// 校验并解压内核，写入版本目录
+    pub async fn install(
+        &self,
+        bytes: &[u8],
+        version: &str,
+        expected_sha256: Option<&str>,
+        source: &str,
+    ) -> Result<ManagedCoreInfo> {
+        let dir = self.version_dir(version)?;
+        if tokio::fs::try_exists(&dir).await.unwrap_or(false) {
+            bail!("core version `{version}` is already installed");
+        }
+
+        let sha256 = sha256_hex(bytes);
+        if let Some(expected) = expected_sha256
+            && !expected.trim().eq_ignore_ascii_case(&sha256)
+        {
+            bail!("checksum mismatch, expected {expected}, got {sha256}");
+        }
+        let binary = extract_binary(bytes)?;
+
+        tokio::fs::create_dir_all(&dir).await?;
+        let binary_path = dir.join(CORE_BINARY);
+        tokio::fs::write(&binary_path, binary).await?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            tokio::fs::set_permissions(&binary_path, std::fs::Permissions::from_mode(0o755)).await?;
+        }
+
+        let info = ManagedCoreInfo {
+            id: format!("{MANAGED_CORE_PREFIX}{version}").into(),
+            version: version.into(),
+            sha256: sha256.into(),
+            source: source.into(),
+            installed_at: chrono::Local::now().timestamp_millis(),
+            reported_version: None,
+        };
+        self.write_meta(&info).await?;
+        Ok(info)
+    }
+
+    pub async fn write_meta(&self, info: &ManagedCoreInfo) -> Result<()> {
+        let path = self.version_dir(&info.version)?.join(CORE_META);
+        tokio::fs::write(path, serde_json::to_vec_pretty(info)?).await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, version: &str) -> Result<()> {
+        let dir = self.version_dir(version)?;
+        if tokio::fs::try_exists(&dir).await.unwrap_or(false) {
+            tokio::fs::remove_dir_all(&dir).await?;
+        }
+        Ok(())
+    }
+}
+
+/// 版本号用作目录名，只允许常见字符
+fn validate_version(version: &str) -> Result<()> {
+    let valid = !version.is_empty()
+        && !version.starts_with('.')
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if !valid {
+        bail!("invalid core version `{version}`");
+    }
+    Ok(())
+}
+
+fn sha256_hex(bytes: &[u8]) -> std::string::String {
+    Sha256::digest(bytes)
+        .iter()
+        .fold(std::string::String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// 根据文件头识别 zip / gzip，其它内容视为可执行文件本身
+fn extract_binary(bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.starts_with(b"PK\x03\x04") {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+        let index = (0..archive.len())
+            .filter_map(|i| {
+                archive
+                    .by_index(i)
+                    .ok()
+                    .map(|file| (i, file.is_file(), file.name().to_owned()))
+            })
+            .filter(|(_, is_file, _)| *is_file)
+            .map(|(i, _, name)| (i, name))
+            .min_by_key(|(_, name)| !name.contains("mihomo"))
+            .map(|(i, _)| i)
+            .ok_or_else(|| anyhow!("no executable found in zip archive"))?;
+        return read_limited(archive.by_index(index)?).context("failed to decompress zip archive");
+    }
+
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return read_limited(flate2::read::GzDecoder::new(bytes)).context("failed to decompress gzip archive");
+    }
+
+    if bytes.is_empty() {
+        bail!("core file is empty");
+    }
+    Ok(bytes.to_vec())
+}
+
+fn read_limited(reader: impl std::io::Read) -> Result<Vec<u8>> {
+    let mut binary = Vec::new();
+    reader.take(MAX_CORE_SIZE + 1).read_to_end(&mut binary)?;
+    if binary.len() as u64 > MAX_CORE_SIZE {
+        bail!("decompressed core exceeds {} MiB", MAX_CORE_SIZE / 1024 / 1024);
+    }
+    Ok(binary)
+}
+
+async fn download(url: &str) -> Result<Vec<u8>> {
+    let client = NetworkManager::new()
+        .create_request(ProxyType::None, Some(DOWNLOAD_TIMEOUT_SECS), None, false)
+        .await?;
+    let response = client.get(url).send().await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+async fn read_source(source: &CoreSource) -> Result<Vec<u8>> {
+    match source {
+        CoreSource::Url { url } => download(url).await,
+        CoreSource::File { path } => tokio::fs::read(path.as_str())
+            .await
+            .with_context(|| format!("failed to read core file `{path}`")),
+    }
+}
+
+/// 运行 `-v` 确认文件可以执行
+async fn probe_version(binary: &Path) -> Result<String> {
+    let output = Handle::app_handle()
+        .shell()
+        .command(binary)
+        .args(["-v"])
+        .output()
+        .await?;
+    if !output.status.success() {
+        bail!("core exited with {:?}", output.status.code());
+    }
+    let version = std::string::String::from_utf8_lossy(&output.stdout);
+    Ok(version.lines().next().unwrap_or_default().trim().into())
+}
+
+/// 安装受管内核，远程下载时必须提供 SHA-256
+pub async fn install_core(source: CoreSource, version: &str, sha256: Option<&str>) -> Result<ManagedCoreInfo> {
+    if matches!(source, CoreSource::Url { .. }) && sha256.is_none_or(|s| s.trim().is_empty()) {
+        bail!("sha256 checksum is required when installing from url");
+    }
+
+    logging!(
+        info,
+        Type::Core,
+        "Installing managed core {version} from {}",
+        source.describe()
+    );
+    let bytes = read_source(&source).await?;
+    let store = CoreStore::new()?;
+    let mut info = store.install(&bytes, version, sha256, source.describe()).await?;
+
+    match probe_version(&store.binary_path(version)?).await {
+        Ok(reported) => {
+            info.reported_version = Some(reported);
+            store.write_meta(&info).await?;
+            logging!(info, Type::Core, "Managed core {} installed", info.id);
+            Ok(info)
+        }
+        Err(err) => {
+            store.remove(version).await?;
+            Err(err.context("installed file is not a runnable core"))
+        }
+    }
+}
+
+pub async fn list_cores() -> Result<Vec<ManagedCoreInfo>> {
+    CoreStore::new()?.list().await
+}
+
+pub async fn remove_core(version: &str, current: &str) -> Result<()> {
+    if current.strip_prefix(MANAGED_CORE_PREFIX) == Some(version) {
+        bail!("cannot remove the core in use");
+    }
+    CoreStore::new()?.remove(version).await
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{CoreStore, core_flavour, download, extract_binary, sha256_hex, validate_version};
+    use std::io::{Read as _, Write as _};
+
+    const BINARY: &[u8] = b"\x7fELF fake mihomo binary";
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(bytes).expect("gzip");
+        encoder.finish().expect("gzip")
+    }
+
+    fn zip(bytes: &[u8]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("README.md", options).expect("zip");
+        writer.write_all(b"readme").expect("zip");
+        writer.start_file("mihomo-windows-amd64.exe", options).expect("zip");
+        writer.write_all(bytes).expect("zip");
+        writer.finish().expect("zip").into_inner()
+    }
+
+    /// 本地文件服务，代替发布站点
+    fn serve_once(body: Vec<u8>) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(header.as_bytes());
+                let _ = stream.write_all(&body);
+            }
+        });
+        format!("http://{addr}/mihomo-linux-amd64.gz")
+    }
+
+    fn temp_store(name: &str) -> CoreStore {
+        let dir = std::env::temp_dir().join(format!("clash-verge-cores-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        CoreStore::with_dir(dir)
+    }
+
+    #[test]
+    fn managed_core_flavour() {
+        assert_eq!(core_flavour("managed:v1.19.2"), "verge-mihomo");
+        assert_eq!(core_flavour("managed:alpha-7b2c1e0"), "verge-mihomo-alpha");
+        assert_eq!(core_flavour("verge-mihomo-alpha"), "verge-mihomo-alpha");
+    }
+
+    #[test]
+    fn extract_archives() {
+        assert_eq!(extract_binary(&gzip(BINARY)).expect("gzip"), BINARY);
+        assert_eq!(extract_binary(&zip(BINARY)).expect("zip"), BINARY);
+        assert_eq!(extract_binary(BINARY).expect("raw"), BINARY);
+        assert!(extract_binary(&[]).is_err());
+    }
+
+    #[test]
+    fn version_is_safe_dir_name() {
+        assert!(validate_version("v1.19.2-alpha_1").is_ok());
+        assert!(validate_version("../etc").is_err());
+        assert!(validate_version("a/b").is_err());
+        assert!(validate_version("").is_err());
+    }
+
+    #[tokio::test]
+    async fn install_from_local_server() {
+        let archive = gzip(BINARY);
+        let checksum = sha256_hex(&archive);
+        let url = serve_once(archive);
+
+        let bytes = download(&url).await.expect("download");
+        let store = temp_store("install");
+        let info = store
+            .install(&bytes, "v1.0.0", Some(checksum.to_uppercase().as_str()), &url)
+            .await
+            .expect("install");
+
+        assert_eq!(info.id, "managed:v1.0.0");
+        assert_eq!(
+            std::fs::read(store.binary_path("v1.0.0").expect("path")).expect("read"),
+            BINARY
+        );
+        assert_eq!(store.list().await.expect("list"), vec![info]);
+        assert!(store.install(&bytes, "v1.0.0", None, &url).await.is_err());
+
+        // 多个版本并存
+        store.install(BINARY, "v1.0.1", None, "local").await.expect("install");
+        assert_eq!(store.list().await.expect("list").len(), 2);
+
+        store.remove("v1.0.0").await.expect("remove");
+        assert_eq!(store.list().await.expect("list").len(), 1);
+        store.remove("v1.0.1").await.expect("remove");
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatch_is_rejected() {
+        let store = temp_store("checksum");
+        let res = store.install(BINARY, "v1.0.0", Some("deadbeef"), "local").await;
+        assert!(res.is_err());
+        assert!(!store.binary_path("v1.0.0").expect("path").exists());
+    }
+}
@@ -1,4 +1,4 @@
-use super::CoreManager;
+use super::{CoreManager, RunningMode};
 use crate::{
     config::{Config, ConfigType, runtime::IRuntime},
     constants::timing,
@@ -8,8 +8,11 @@ use crate::{
 use anyhow::{Result, anyhow};
 use clash_verge_logging::{Type, logging};
 use smartstring::alias::String;
-use std::{collections::HashSet, path::PathBuf, time::Instant};
-use tauri_plugin_mihomo::Error as MihomoError;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
 impl CoreManager {
     pub async fn use_default_config(&self, error_key: &str, error_msg: &str) -> Result<()> {
@@ -23,6 +26,7 @@ impl CoreManager {
                 config: Some(clash_config.to_owned()),
                 exists_keys: HashSet::new(),
                 chain_logs: Default::default(),
+                secret_values: Default::default(),
             }
         });
 
@@ -82,35 +86,51 @@ impl CoreManager {
 
     async fn apply_config(&self, path: PathBuf) -> Result<()> {
         let path = dirs::path_to_str(&path)?;
-        match self.reload_config(path).await {
-            Ok(_) => {
-                Config::runtime().await.apply();
-                logging!(info, Type::Core, "Configuration applied");
-                Ok(())
-            }
+        let applied = match self.reload_config(path).await {
+            Ok(_) => Ok(()),
             Err(err) => {
                 logging!(
                     warn,
                     Type::Core,
                     "Failed to apply configuration by mihomo api, restart core to apply it, error msg: {err}"
                 );
-                match self.restart_core().await {
-                    Ok(_) => {
-                        Config::runtime().await.apply();
-                        logging!(info, Type::Core, "Configuration applied after restart");
-                        Ok(())
-                    }
-                    Err(err) => {
-                        logging!(error, Type::Core, "Failed to restart core: {}", err);
-                        Config::runtime().await.discard();
-                        Err(anyhow!("Failed to apply config: {}", err))
-                    }
-                }
+                self.restart_core().await.inspect_err(|err| {
+                    logging!(error, Type::Core, "Failed to restart core: {}", err);
+                })
+            }
+        };
+
+        let verified = match applied {
+            Ok(()) => self.verify_applied().await,
+            Err(err) => Err(err),
+        };
+
+        match verified {
+            Ok(()) => {
+                Config::runtime().await.apply();
+                logging!(info, Type::Core, "Configuration applied");
+                self.save_last_good().await;
+                Ok(())
+            }
+            Err(err) => {
+                Config::runtime().await.discard();
+                // 回滚失败时同样返回原始错误，回滚结果已单独通知
+                let _ = self.rollback(&format!("{err:#}")).await;
+                Err(anyhow!("Failed to apply config: {}", err))
             }
         }
     }
 
-    async fn reload_config(&self, path: &str) -> Result<(), MihomoError> {
-        handle::Handle::mihomo().await.reload_config(true, path).await
+    pub(super) async fn reload_config(&self, path: &str) -> Result<()> {
+        if *self.get_running_mode() == RunningMode::External
+            && let Some(controller) = self.external_controller().await?
+        {
+            return self.push_external_config(&controller, Path::new(path)).await;
+        }
+        handle::Handle::mihomo()
+            .await
+            .reload_config(true, path)
+            .await
+            .map_err(|err| anyhow!("{err}"))
     }
 }
@@ -0,0 +1,401 @@
+use super::{CoreManager, RunningMode};
+use crate::{
+    config::{Config, ConfigType, IVerge},
+    constants::timing,
+    core::handle::Handle,
+    module::core_watchdog::probe_controller,
+    utils::network::{NetworkManager, ProxyType},
+};
+use anyhow::{Context as _, Result, anyhow, bail};
+use clash_verge_logging::{Type, logging};
+use parking_lot::Mutex;
+use serde_yaml_ng::Mapping;
+use smartstring::alias::String;
+use std::{net::IpAddr, path::Path};
+use tauri::Url;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+const PUSH_TIMEOUT_SECS: u64 = 30;
+
+/// 外部内核自身的监听设置，推送配置时沿用远程的当前值
+const REMOTE_LISTENER_KEYS: [&str; 7] = [
+    "port",
+    "socks-port",
+    "mixed-port",
+    "redir-port",
+    "tproxy-port",
+    "allow-lan",
+    "bind-address",
+];
+
+/// 本地内核的控制器设置，不能覆盖到外部内核
+const CONTROLLER_KEYS: [&str; 9] = [
+    "external-controller",
+    "external-controller-tls",
+    "external-controller-unix",
+    "external-controller-pipe",
+    "external-controller-cors",
+    "external-ui",
+    "external-ui-url",
+    "external-ui-name",
+    "secret",
+];
+
+/// 连接外部内核时系统代理指向的地址与端口
+static EXTERNAL_PROXY: Mutex<Option<(String, u16)>> = Mutex::new(None);
+
+/// 外部运行的 mihomo 控制器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExternalController {
+    Http {
+        host: String,
+        port: u16,
+        secret: Option<String>,
+    },
+    Socket {
+        path: String,
+        secret: Option<String>,
+    },
+}
+
+impl ExternalController {
+    /// 未启用外部内核时返回 None，填写了 socket 路径时优先使用
+    pub fn from_verge(verge: &IVerge) -> Result<Option<Self>> {
+        if !verge.enable_external_core.unwrap_or(false) {
+            return Ok(None);
+        }
+
+        let secret = verge.external_core_secret.clone().filter(|secret| !secret.is_empty());
+        if let Some(path) = non_empty(verge.external_core_socket.as_deref()) {
+            return Ok(Some(Self::Socket {
+                path: path.into(),
+                secret,
+            }));
+        }
+
+        let url = non_empty(verge.external_core_url.as_deref())
+            .ok_or_else(|| anyhow!("external core requires a controller url or socket path"))?;
+        let url = if url.contains("://") {
+            Url::parse(url)
+        } else {
+            Url::parse(&format!("http://{url}"))
+        }
+        .with_context(|| format!("invalid controller url `{url}`"))?;
+
+        if url.scheme() != "http" {
+            bail!("unsupported controller scheme `{}`", url.scheme());
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("controller url `{url}` has no host"))?;
+        let port = url.port_or_known_default().unwrap_or(80);
+
+        Ok(Some(Self::Http {
+            host: host.into(),
+            port,
+            secret,
+        }))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Http { host, port, .. } => format!("http://{host}:{port}").into(),
+            Self::Socket { path, .. } => format!("unix://{path}").into(),
+        }
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+/// 生成推送给外部内核的配置：去掉本地控制器设置，保留远程的监听端口与当前连接方式
+fn remote_payload(mut config: Mapping, controller: &ExternalController, remote: &serde_json::Value) -> Mapping {
+    for key in CONTROLLER_KEYS.iter().chain(&REMOTE_LISTENER_KEYS) {
+        config.remove(*key);
+    }
+    for key in REMOTE_LISTENER_KEYS {
+        if let Some(value) = remote.get(key).filter(|value| !value.is_null())
+            && let Ok(value) = serde_yaml_ng::to_value(value)
+        {
+            config.insert(key.into(), value);
+        }
+    }
+
+    let secret = match controller {
+        ExternalController::Http { host, port, secret } => {
+            // 保留连接时使用的主机，主机名由内核解析，只有用户配置通配地址时才监听所有网卡
+            let bind = match host.parse::<IpAddr>() {
+                Ok(ip) => std::net::SocketAddr::new(ip, *port).to_string(),
+                Err(_) => format!("{host}:{port}"),
+            };
+            config.insert("external-controller".into(), bind.into());
+            secret
+        }
+        ExternalController::Socket { path, secret } => {
+            let key = if cfg!(windows) {
+                "external-controller-pipe"
+            } else {
+                "external-controller-unix"
+            };
+            config.insert(key.into(), path.as_str().into());
+            secret
+        }
+    };
+    if let Some(secret) = secret {
+        config.insert("secret".into(), secret.as_str().into());
+    }
+    config
+}
+
+/// 外部内核的代理入口，优先使用 mixed-port
+fn remote_proxy(controller: &ExternalController, remote: &serde_json::Value) -> Option<(String, u16)> {
+    let port = ["mixed-port", "port"]
+        .iter()
+        .filter_map(|key| remote.get(key).and_then(serde_json::Value::as_u64))
+        .find(|port| *port > 0)
+        .and_then(|port| u16::try_from(port).ok())?;
+    let host = match controller {
+        ExternalController::Http { host, .. } => host.clone(),
+        ExternalController::Socket { .. } => "127.0.0.1".into(),
+    };
+    Some((host, port))
+}
+
+async fn remote_base_config() -> Result<serde_json::Value> {
+    let base = Handle::mihomo()
+        .await
+        .get_base_config()
+        .await
+        .map_err(|err| anyhow!("{err}"))?;
+    Ok(serde_json::to_value(base)?)
+}
+
+/// 通过 unix socket 或命名管道发送 `PUT /configs`，外部内核不一定能读取本地文件
+async fn put_over_socket(path: &str, secret: Option<&String>, body: &[u8]) -> Result<()> {
+    let mut request = format!(
+        "PUT /configs?force=true HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+    if let Some(secret) = secret {
+        request.push_str(&format!("Authorization: Bearer {secret}\r\n"));
+    }
+    request.push_str("\r\n");
+
+    #[cfg(unix)]
+    let mut stream = tokio::net::UnixStream::connect(path).await?;
+    #[cfg(windows)]
+    let mut stream = tokio::net::windows::named_pipe::ClientOptions::new().open(path)?;
+
+    let exchange = async {
+        stream.write_all(request.as_bytes()).await?;
+        stream.write_all(body).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        anyhow::Ok(response)
+    };
+    let response = tokio::time::timeout(std::time::Duration::from_secs(PUSH_TIMEOUT_SECS), exchange)
+        .await
+        .map_err(|_| anyhow!("timed out while pushing config to external core"))??;
+
+    let response = std::string::String::from_utf8_lossy(&response);
+    let status = response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .unwrap_or_default();
+    if !(200..300).contains(&status) {
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body)
+            .unwrap_or_default();
+        bail!("external core rejected config ({status}): {body}");
+    }
+    Ok(())
+}
+
+impl CoreManager {
+    pub(super) async fn external_controller(&self) -> Result<Option<ExternalController>> {
+        ExternalController::from_verge(&Config::verge().await.latest_arc())
+    }
+
+    /// 连接到外部内核，不启动任何进程
+    pub(super) async fn start_core_by_external(&self) -> Result<()> {
+        let controller = self
+            .external_controller()
+            .await?
+            .ok_or_else(|| anyhow!("external core is not enabled"))?;
+        logging!(
+            info,
+            Type::Core,
+            "Attaching to external core at {}",
+            controller.describe()
+        );
+
+        Handle::use_external_controller(&controller).await;
+        let version = probe_controller(timing::CONFIG_HEALTH_TIMEOUT)
+            .await
+            .with_context(|| format!("failed to reach external core at {}", controller.describe()))?;
+        logging!(info, Type::Core, "External core version: {version}");
+
+        self.set_running_mode(RunningMode::External);
+        // 只连接不推送配置，避免覆盖外部内核已有的配置
+        match remote_base_config().await {
+            Ok(remote) => *EXTERNAL_PROXY.lock() = remote_proxy(&controller, &remote),
+            Err(err) => logging!(warn, Type::Core, "Failed to read external core ports: {err}"),
+        }
+        Ok(())
+    }
+
+    /// 外部内核的代理地址，未连接外部内核时返回 None
+    pub fn external_proxy(&self) -> Option<(String, u16)> {
+        if *self.get_running_mode() != RunningMode::External {
+            return None;
+        }
+        EXTERNAL_PROXY.lock().clone()
+    }
+
+    /// 断开外部内核，外部进程保持运行
+    pub(super) async fn stop_core_by_external(&self) {
+        logging!(info, Type::Core, "Detaching from external core");
+        Handle::use_local_controller().await;
+        *EXTERNAL_PROXY.lock() = None;
+        self.set_running_mode(RunningMode::NotRunning);
+    }
+
+    /// 通过控制器的重载接口下发配置，外部内核无法读取本地文件，因此直接发送内容
+    pub(super) async fn push_external_config(&self, controller: &ExternalController, path: &Path) -> Result<()> {
+        let config: Mapping = serde_yaml_ng::from_str(&tokio::fs::read_to_string(path).await?)?;
+        let remote = remote_base_config().await?;
+        let payload = serde_yaml_ng::to_string(&remote_payload(config, controller, &remote))?;
+        let body = serde_json::json!({ "path": "", "payload": payload });
+
+        let (host, port, secret) = match controller {
+            ExternalController::Http { host, port, secret } => (host, port, secret),
+            ExternalController::Socket { path, secret } => {
+                return put_over_socket(path, secret.as_ref(), &serde_json::to_vec(&body)?).await;
+            }
+        };
+
+        let client = NetworkManager::new()
+            .create_request(ProxyType::None, Some(PUSH_TIMEOUT_SECS), None, false)
+            .await?;
+        let mut request = client
+            .put(format!("http://{host}:{port}/configs?force=true"))
+            .json(&body);
+        if let Some(secret) = secret {
+            request = request.bearer_auth(secret);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("external core rejected config ({status}): {body}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{ExternalController, remote_payload, remote_proxy};
+    use crate::config::IVerge;
+    use serde_yaml_ng::Mapping;
+
+    fn verge(url: Option<&str>, socket: Option<&str>) -> IVerge {
+        IVerge {
+            enable_external_core: Some(true),
+            external_core_url: url.map(Into::into),
+            external_core_socket: socket.map(Into::into),
+            external_core_secret: Some("s3cret".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_controller() {
+        assert_eq!(
+            ExternalController::from_verge(&verge(Some("192.168.1.1:9090"), None)).expect("parse"),
+            Some(ExternalController::Http {
+                host: "192.168.1.1".into(),
+                port: 9090,
+                secret: Some("s3cret".into()),
+            })
+        );
+        assert_eq!(
+            ExternalController::from_verge(&verge(Some("http://router.lan"), Some("  ")))
+                .expect("parse")
+                .map(|controller| controller.describe()),
+            Some("http://router.lan:80".into())
+        );
+        assert_eq!(
+            ExternalController::from_verge(&verge(Some("http://router.lan"), Some("/run/mihomo.sock")))
+                .expect("parse")
+                .map(|controller| controller.describe()),
+            Some("unix:///run/mihomo.sock".into())
+        );
+    }
+
+    #[test]
+    fn reject_invalid_controller() {
+        assert!(ExternalController::from_verge(&verge(None, None)).is_err());
+        assert!(ExternalController::from_verge(&verge(Some("https://router.lan"), None)).is_err());
+
+        let disabled = IVerge {
+            enable_external_core: Some(false),
+            ..verge(None, None)
+        };
+        assert_eq!(ExternalController::from_verge(&disabled).expect("parse"), None);
+    }
+
+    #[test]
+    fn payload_keeps_remote_listeners() {
+        let config: Mapping = serde_yaml_ng::from_str(
+            r"
+mixed-port: 7897
+allow-lan: false
+external-controller: 127.0.0.1:9097
+external-controller-unix: /tmp/verge/verge-mihomo.sock
+secret: local
+proxies: []
+",
+        )
+        .expect("yaml");
+        let remote = serde_json::json!({ "mixed-port": 7890, "port": 0, "allow-lan": true, "bind-address": "*" });
+        let controller = ExternalController::Http {
+            host: "192.168.1.1".into(),
+            port: 9090,
+            secret: Some("s3cret".into()),
+        };
+
+        let expected: Mapping = serde_yaml_ng::from_str(
+            r"
+proxies: []
+port: 0
+mixed-port: 7890
+allow-lan: true
+bind-address: '*'
+external-controller: 192.168.1.1:9090
+secret: s3cret
+",
+        )
+        .expect("yaml");
+        assert_eq!(remote_payload(config, &controller, &remote), expected);
+        assert_eq!(remote_proxy(&controller, &remote), Some(("192.168.1.1".into(), 7890)));
+
+        let controller = ExternalController::Http {
+            host: "router.lan".into(),
+            port: 9090,
+            secret: None,
+        };
+        let payload = remote_payload(Mapping::new(), &controller, &serde_json::json!({}));
+        assert_eq!(
+            payload.get("external-controller").and_then(|v| v.as_str()),
+            Some("router.lan:9090")
+        );
+        assert!(!payload.contains_key("secret"));
+        assert_eq!(remote_proxy(&controller, &serde_json::json!({ "mixed-port": 0 })), None);
+    }
+}
@@ -2,9 +2,10 @@ use super::{CoreManager, RunningMode};
 use crate::cmd::StringifyErr as _;
 use crate::config::{Config, IVerge};
 use crate::core::handle::Handle;
-use crate::core::manager::CLASH_LOGGER;
+use crate::core::managed_core;
 use crate::core::service::{SERVICE_MANAGER, ServiceStatus};
-use anyhow::Result;
+use crate::core::validate::CoreConfigValidator;
+use anyhow::{Result, bail};
 use clash_verge_logging::{Type, logging};
 use scopeguard::defer;
 use smartstring::alias::String;
@@ -19,12 +20,14 @@ impl CoreManager {
 
         match *self.get_running_mode() {
             RunningMode::Service => self.start_core_by_service().await,
+            RunningMode::External => self.start_core_by_external().await,
             RunningMode::NotRunning | RunningMode::Sidecar => self.start_core_by_sidecar().await,
         }
     }
 
     pub async fn stop_core(&self) -> Result<()> {
-        CLASH_LOGGER.clear_logs().await;
+        // 崩溃后的退避期内核心处于 NotRunning，同样需要取消待执行的重启
+        self.supervisor.invalidate();
         defer! {
             self.after_core_process();
         }
@@ -35,6 +38,10 @@ impl CoreManager {
                 self.stop_core_by_sidecar();
                 Ok(())
             }
+            RunningMode::External => {
+                self.stop_core_by_external().await;
+                Ok(())
+            }
             RunningMode::NotRunning => Ok(()),
         }
     }
@@ -45,40 +52,133 @@ impl CoreManager {
         self.start_core().await
     }
 
+    /// 在 Service 与 Sidecar 之间切换运行模式，返回切换后的模式
+    pub async fn switch_running_mode(&self) -> Result<RunningMode> {
+        let target = match *self.get_running_mode() {
+            RunningMode::Service => RunningMode::Sidecar,
+            RunningMode::Sidecar => {
+                if !matches!(SERVICE_MANAGER.lock().await.current(), ServiceStatus::Ready) {
+                    bail!("service is not available");
+                }
+                if Self::uses_managed_core().await {
+                    bail!("managed cores can only run as sidecar");
+                }
+                RunningMode::Service
+            }
+            RunningMode::External => bail!("running mode cannot be switched while attached to an external core"),
+            RunningMode::NotRunning => bail!("core is not running"),
+        };
+
+        logging!(info, Type::Core, "Switching running mode to {target}");
+        self.stop_core().await?;
+        defer! {
+            self.after_core_process();
+        }
+
+        match target {
+            RunningMode::Service => self.start_core_by_service().await?,
+            _ => self.start_core_by_sidecar().await?,
+        }
+        Ok(target)
+    }
+
+    /// 切换内核并重启，新内核未通过 `-t` 校验或无法启动时恢复原内核
     pub async fn change_core(&self, clash_core: &String) -> Result<(), String> {
-        if !IVerge::VALID_CLASH_CORES.contains(&clash_core.as_str()) {
+        if !IVerge::is_valid_clash_core(clash_core) {
             return Err(format!("Invalid clash core: {}", clash_core).into());
         }
 
+        let previous = Config::verge().await.latest_arc().get_valid_clash_core();
+        Self::save_clash_core(clash_core).await.stringify_err()?;
+
+        let Err(err) = self.apply_core().await else {
+            return Ok(());
+        };
+
+        logging!(
+            warn,
+            Type::Core,
+            "Failed to switch core to {clash_core}, rolling back to {previous}: {err:#}"
+        );
+        Self::save_clash_core(&previous).await.stringify_err()?;
+        if let Err(rollback_err) = self.apply_core().await {
+            return Err(format!("{err:#}; failed to roll back to {previous}: {rollback_err:#}").into());
+        }
+        Err(format!("{err:#}; rolled back to {previous}").into())
+    }
+
+    async fn save_clash_core(clash_core: &String) -> Result<()> {
         Config::verge().await.edit_draft(|d| {
             d.clash_core = Some(clash_core.to_owned());
         });
         Config::verge().await.apply();
 
         let verge_data = Config::verge().await.latest_arc();
-        verge_data.save_file().await.map_err(|e| e.to_string())?;
+        verge_data.save_file().await
+    }
 
-        self.update_config().await.stringify_err()?;
-        Ok(())
+    /// 用当前选择的内核重新生成、校验配置并重启
+    async fn apply_core(&self) -> Result<()> {
+        Config::generate().await?;
+        match CoreConfigValidator::global().validate_config().await? {
+            (true, _) => {}
+            (false, msg) => {
+                Config::runtime().await.discard();
+                bail!("core validation failed: {msg}");
+            }
+        }
+        if let Err(err) = self.restart_core().await {
+            Config::runtime().await.discard();
+            return Err(err);
+        }
+        Config::runtime().await.apply();
+        self.verify_applied().await
     }
 
     async fn prepare_startup(&self) -> Result<()> {
+        if self.external_controller().await?.is_some() {
+            self.set_running_mode(RunningMode::External);
+            return Ok(());
+        }
+
         #[cfg(target_os = "windows")]
         self.wait_for_service_if_needed().await;
 
         let value = SERVICE_MANAGER.lock().await.current();
         let mode = match value {
+            ServiceStatus::Ready if Self::uses_managed_core().await => {
+                logging!(
+                    warn,
+                    Type::Core,
+                    "Managed core runs as sidecar, service mode is skipped"
+                );
+                RunningMode::Sidecar
+            }
             ServiceStatus::Ready => RunningMode::Service,
             _ => RunningMode::Sidecar,
         };
 
         self.set_running_mode(mode);
-        Ok(())
+        self.preflight_ports().await
     }
 
-    fn after_core_process(&self) {
+    /// 受管内核位于用户可写目录，不交给以 root 运行的服务启动
+    async fn uses_managed_core() -> bool {
+        let clash_core = Config::verge().await.latest_arc().get_valid_clash_core();
+        managed_core::is_managed_core(&clash_core)
+    }
+
+    pub(super) fn after_core_process(&self) {
         let app_handle = Handle::app_handle();
-        tauri_plugin_clash_verge_sysinfo::set_app_core_mode(app_handle, self.get_running_mode().to_string());
+        let mode = self.get_running_mode().to_string();
+        let stats = self.get_restart_stats();
+        let mode = match stats.last_exit_reason {
+            Some(reason) if stats.crash_count > 0 => {
+                format!("{mode} (restarts: {}, last exit: {reason})", stats.restart_count)
+            }
+            _ => mode,
+        };
+        tauri_plugin_clash_verge_sysinfo::set_app_core_mode(app_handle, mode);
     }
 
     #[cfg(target_os = "windows")]
@@ -1,22 +1,36 @@
 mod config;
+mod external;
 mod lifecycle;
+mod ports;
+mod rollback;
+mod selections;
 mod state;
+mod supervisor;
+
+pub use self::{external::ExternalController, rollback::RollbackRecord, supervisor::CoreRestartStats};
 
 use anyhow::Result;
 use arc_swap::{ArcSwap, ArcSwapOption};
-use clash_verge_logger::AsyncLogger;
-use once_cell::sync::Lazy;
-use std::{fmt, sync::Arc, time::Instant};
+use parking_lot::Mutex;
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    time::Instant,
+};
 use tauri_plugin_shell::process::CommandChild;
 
-use crate::singleton;
-
-pub(crate) static CLASH_LOGGER: Lazy<Arc<AsyncLogger>> = Lazy::new(|| Arc::new(AsyncLogger::new()));
+use self::supervisor::Supervisor;
+use crate::{process::AsyncHandler, singleton};
 
 #[derive(Debug, serde::Serialize, PartialEq, Eq)]
 pub enum RunningMode {
     Service,
     Sidecar,
+    /// 连接外部运行的内核
+    External,
     NotRunning,
 }
 
@@ -25,6 +39,7 @@ impl fmt::Display for RunningMode {
         match self {
             Self::Service => write!(f, "Service"),
             Self::Sidecar => write!(f, "Sidecar"),
+            Self::External => write!(f, "External"),
             Self::NotRunning => write!(f, "NotRunning"),
         }
     }
@@ -34,6 +49,12 @@ impl fmt::Display for RunningMode {
 pub struct CoreManager {
     state: ArcSwap<State>,
     last_update: ArcSwapOption<Instant>,
+    supervisor: Supervisor,
+    last_rollback: Mutex<Option<RollbackRecord>>,
+    /// 最近一次启动的 sidecar 进程 pid，停止后仍保留，用于识别尚未释放的端口
+    sidecar_pid: AtomicU32,
+    /// 是否已提醒过没有可回滚的配置
+    missing_snapshot_noticed: AtomicBool,
 }
 
 #[derive(Debug)]
@@ -56,6 +77,10 @@ impl Default for CoreManager {
         Self {
             state: ArcSwap::new(Arc::new(State::default())),
             last_update: ArcSwapOption::new(None),
+            supervisor: Supervisor::default(),
+            last_rollback: Mutex::new(None),
+            sidecar_pid: AtomicU32::new(0),
+            missing_snapshot_noticed: AtomicBool::new(false),
         }
     }
 }
@@ -87,16 +112,33 @@ impl CoreManager {
     }
 
     pub fn set_running_child_sidecar(&self, child: CommandChild) {
+        self.sidecar_pid.store(child.pid(), Ordering::Relaxed);
         let state = self.state.load();
         state.child_sidecar.store(Some(Arc::new(child)));
     }
 
+    pub fn sidecar_pid(&self) -> Option<u32> {
+        Some(self.sidecar_pid.load(Ordering::Relaxed)).filter(|pid| *pid != 0)
+    }
+
     pub fn set_last_update(&self, time: Instant) {
         self.last_update.store(Some(Arc::new(time)));
     }
 
     pub async fn init(&self) -> Result<()> {
+        self.spawn_supervisor();
         self.start_core().await?;
+        // 外部内核的配置不由本应用管理，不作为回滚目标
+        if *self.get_running_mode() == RunningMode::External {
+            return Ok(());
+        }
+        // 启动成功的配置作为初始的回滚目标，在后台确认以免拖慢启动
+        AsyncHandler::spawn(|| async {
+            let manager = Self::global();
+            if manager.verify_applied().await.is_ok() {
+                manager.save_last_good().await;
+            }
+        });
         Ok(())
     }
 }
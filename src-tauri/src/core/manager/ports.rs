@@ -0,0 +1,429 @@
+use super::CoreManager;
+use crate::{
+    config::{Config, IClashTemp, IVerge, PortConflictStrategy},
+    core::{handle::Handle, sysopt::Sysopt},
+    process::AsyncHandler,
+};
+use anyhow::{Result, bail};
+use clash_verge_logging::{Type, logging, logging_error};
+use serde_yaml_ng::Mapping;
+use smartstring::alias::String;
+use std::{
+    collections::HashSet,
+    fmt,
+    net::{Ipv4Addr, SocketAddr, TcpListener},
+    str::FromStr as _,
+    time::Duration,
+};
+
+/// 向上查找可用端口的范围
+const PORT_SEARCH_RANGE: u16 = 100;
+/// 刚停止的内核可能尚未释放端口，冲突时稍等重试
+const RECHECK_TIMES: usize = 3;
+const RECHECK_INTERVAL: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum PortKind {
+    Mixed,
+    Socks,
+    Http,
+    #[cfg(not(target_os = "windows"))]
+    Redir,
+    #[cfg(target_os = "linux")]
+    Tproxy,
+    Controller,
+}
+
+impl PortKind {
+    const fn clash_key(self) -> &'static str {
+        match self {
+            Self::Mixed => "mixed-port",
+            Self::Socks => "socks-port",
+            Self::Http => "port",
+            #[cfg(not(target_os = "windows"))]
+            Self::Redir => "redir-port",
+            #[cfg(target_os = "linux")]
+            Self::Tproxy => "tproxy-port",
+            Self::Controller => "external-controller",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct PortConflict {
+    pub kind: PortKind,
+    pub port: u16,
+    /// 与其它已检查的端口重复，而非被其它进程占用
+    pub duplicate: bool,
+    pub replacement: Option<u16>,
+}
+
+/// 按顺序检查端口，前面的端口优先保留，冲突的端口向上查找替代端口，替代端口不会占用其它设置的端口
+pub(super) fn plan_ports(ports: &[(PortKind, u16)], is_free: impl Fn(u16) -> bool) -> Vec<PortConflict> {
+    let configured = ports.iter().map(|&(_, port)| port).collect::<HashSet<_>>();
+    let mut taken = HashSet::new();
+    let mut conflicts = Vec::new();
+
+    for &(kind, port) in ports {
+        let duplicate = taken.contains(&port);
+        if !duplicate && is_free(port) {
+            taken.insert(port);
+            continue;
+        }
+
+        let replacement = (1..=PORT_SEARCH_RANGE)
+            .filter_map(|offset| port.checked_add(offset))
+            .find(|candidate| !configured.contains(candidate) && !taken.contains(candidate) && is_free(*candidate));
+        if let Some(replacement) = replacement {
+            taken.insert(replacement);
+        }
+        conflicts.push(PortConflict {
+            kind,
+            port,
+            duplicate,
+            replacement,
+        });
+    }
+    conflicts
+}
+
+/// 本地回环与所有地址都能监听才视为可用
+pub(super) fn is_port_free(port: u16) -> bool {
+    [Ipv4Addr::LOCALHOST, Ipv4Addr::UNSPECIFIED]
+        .into_iter()
+        .all(|ip| TcpListener::bind((ip, port)).is_ok())
+}
+
+/// 端口空闲，或仅被本应用的内核占用（重启时旧内核可能尚未退出）
+fn is_port_available(port: u16, sidecar_pid: Option<u32>) -> bool {
+    is_port_free(port) || find_port_owner(port).is_some_and(|owner| owner.is_own_core(sidecar_pid))
+}
+
+/// 端口检查会启动外部命令，放到阻塞线程中执行
+async fn check_ports(ports: &[(PortKind, u16)], sidecar_pid: Option<u32>) -> Result<Vec<PortConflict>> {
+    let ports = ports.to_vec();
+    let conflicts =
+        AsyncHandler::spawn_blocking(move || plan_ports(&ports, |port| is_port_available(port, sidecar_pid))).await?;
+    Ok(conflicts)
+}
+
+/// 启动前需要检查的端口，未启用的端口不检查
+fn enabled_ports(verge: &IVerge, clash: &Mapping) -> Vec<(PortKind, u16)> {
+    let mut ports = vec![(PortKind::Mixed, IClashTemp::guard_mixed_port(clash))];
+    if verge.verge_socks_enabled.unwrap_or(false) {
+        ports.push((PortKind::Socks, IClashTemp::guard_socks_port(clash)));
+    }
+    if verge.verge_http_enabled.unwrap_or(false) {
+        ports.push((PortKind::Http, IClashTemp::guard_port(clash)));
+    }
+    #[cfg(not(target_os = "windows"))]
+    if verge.verge_redir_enabled.unwrap_or(false) {
+        ports.push((PortKind::Redir, IClashTemp::guard_redir_port(clash)));
+    }
+    #[cfg(target_os = "linux")]
+    if verge.verge_tproxy_enabled.unwrap_or(false) {
+        ports.push((PortKind::Tproxy, IClashTemp::guard_tproxy_port(clash)));
+    }
+    if verge.enable_external_controller.unwrap_or(false)
+        && let Ok(addr) = SocketAddr::from_str(&IClashTemp::guard_server_ctrl(clash))
+    {
+        ports.push((PortKind::Controller, addr.port()));
+    }
+    ports
+}
+
+fn describe_conflict(conflict: &PortConflict) -> String {
+    let key = conflict.kind.clash_key();
+    if conflict.duplicate {
+        return format!("{key} {} is already used by another port setting", conflict.port).into();
+    }
+    match find_port_owner(conflict.port) {
+        Some(owner) => format!("{key} {} is in use by {owner}", conflict.port).into(),
+        None => format!("{key} {} is in use by another process", conflict.port).into(),
+    }
+}
+
+impl CoreManager {
+    /// 检查内核需要监听的端口，根据设置自动换用空闲端口或拒绝启动
+    pub(super) async fn preflight_ports(&self) -> Result<()> {
+        let verge = Config::verge().await.latest_arc();
+        let clash = Config::clash().await.latest_arc();
+        let ports = enabled_ports(&verge, &clash.0);
+        let sidecar_pid = self.sidecar_pid();
+
+        let mut conflicts = check_ports(&ports, sidecar_pid).await?;
+        for _ in 0..RECHECK_TIMES {
+            if conflicts.iter().all(|conflict| conflict.duplicate) {
+                break;
+            }
+            tokio::time::sleep(RECHECK_INTERVAL).await;
+            conflicts = check_ports(&ports, sidecar_pid).await?;
+        }
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+
+        let described = conflicts.clone();
+        let details = AsyncHandler::spawn_blocking(move || {
+            described.iter().map(describe_conflict).collect::<Vec<_>>().join("; ")
+        })
+        .await?;
+        let strategy = verge.port_conflict_strategy.unwrap_or_default();
+        if strategy == PortConflictStrategy::Refuse || conflicts.iter().any(|conflict| conflict.replacement.is_none()) {
+            Handle::notice_message("port_conflict::refused", details.as_str());
+            bail!("port conflict: {details}");
+        }
+
+        self.reassign_ports(&conflicts, &clash.0).await?;
+        let changes = conflicts
+            .iter()
+            .filter_map(|conflict| {
+                conflict
+                    .replacement
+                    .map(|port| format!("{} {} -> {port}", conflict.kind.clash_key(), conflict.port))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        logging!(warn, Type::Core, "Port conflict resolved ({details}): {changes}");
+        Handle::notice_message("port_conflict::reassigned", format!("{details}\n{changes}"));
+        Ok(())
+    }
+
+    /// 同步更新 IClashTemp 与 IVerge 中的端口，并刷新系统代理
+    async fn reassign_ports(&self, conflicts: &[PortConflict], clash: &Mapping) -> Result<()> {
+        let mut patch = Mapping::new();
+        for conflict in conflicts {
+            let Some(port) = conflict.replacement else {
+                continue;
+            };
+            let value = match conflict.kind {
+                PortKind::Controller => {
+                    let ctrl = IClashTemp::guard_server_ctrl(clash);
+                    let host = ctrl.rsplit_once(':').map_or("127.0.0.1", |(host, _)| host);
+                    format!("{host}:{port}").into()
+                }
+                _ => port.into(),
+            };
+            patch.insert(conflict.kind.clash_key().into(), value);
+        }
+
+        Config::clash().await.edit_draft(|d| d.patch_config(&patch));
+        Config::clash().await.apply();
+        Config::clash().await.data_arc().save_config().await?;
+
+        Config::verge().await.edit_draft(|d| {
+            for conflict in conflicts {
+                let port = conflict.replacement;
+                match conflict.kind {
+                    PortKind::Mixed => d.verge_mixed_port = port,
+                    PortKind::Socks => d.verge_socks_port = port,
+                    PortKind::Http => d.verge_port = port,
+                    #[cfg(not(target_os = "windows"))]
+                    PortKind::Redir => d.verge_redir_port = port,
+                    #[cfg(target_os = "linux")]
+                    PortKind::Tproxy => d.verge_tproxy_port = port,
+                    PortKind::Controller => {}
+                }
+            }
+        });
+        Config::verge().await.apply();
+        Config::verge().await.data_arc().save_file().await?;
+
+        // 运行时配置中的端口需要重新生成
+        Config::generate().await?;
+
+        AsyncHandler::spawn(|| async {
+            logging_error!(Type::Core, Sysopt::global().update_sysproxy().await);
+            Handle::refresh_clash();
+            Handle::refresh_verge();
+        });
+        Ok(())
+    }
+}
+
+/// 监听端口的进程
+#[derive(Debug, PartialEq, Eq)]
+struct PortOwner {
+    pid: u32,
+    name: Option<String>,
+}
+
+impl PortOwner {
+    /// 最近启动的 sidecar，或服务模式下由服务启动的内核
+    fn is_own_core(&self, sidecar_pid: Option<u32>) -> bool {
+        sidecar_pid == Some(self.pid) || self.name.as_deref().is_some_and(is_core_process_name)
+    }
+}
+
+impl fmt::Display for PortOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name} (pid {})", self.pid),
+            None => write!(f, "pid {}", self.pid),
+        }
+    }
+}
+
+/// 内置内核的进程名，Linux 下可能被截断为 15 个字符，Windows 下带 `.exe`
+fn is_core_process_name(name: &str) -> bool {
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    name.len() >= "verge-mihomo".len() && IVerge::VALID_CLASH_CORES.iter().any(|core| core.starts_with(name))
+}
+
+/// 查找监听该端口的进程
+fn find_port_owner(port: u16) -> Option<PortOwner> {
+    #[cfg(unix)]
+    {
+        run("lsof", &["-nP", &format!("-iTCP:{port}"), "-sTCP:LISTEN", "-Fpc"])
+            .and_then(|output| parse_lsof(&output))
+            .or_else(|| run("ss", &["-ltnpH", &format!("sport = :{port}")]).and_then(|output| parse_ss(&output)))
+    }
+    #[cfg(windows)]
+    {
+        let pid = run("netstat", &["-ano", "-p", "TCP"]).and_then(|output| parse_netstat(&output, port))?;
+        let name = run("tasklist", &["/FI", &format!("PID eq {pid}"), "/FO", "CSV", "/NH"])
+            .and_then(|output| parse_tasklist(&output));
+        Some(PortOwner { pid, name })
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Option<std::string::String> {
+    let mut command = std::process::Command::new(program);
+    command.args(args);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt as _;
+        command.creation_flags(0x08000000);
+    }
+    let output = command.output().ok()?;
+    Some(std::string::String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `lsof -Fpc` 输出，`p` 行为 pid，`c` 行为进程名
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_lsof(output: &str) -> Option<PortOwner> {
+    let pid = output.lines().find_map(|line| line.strip_prefix('p'))?.parse().ok()?;
+    let name = output.lines().find_map(|line| line.strip_prefix('c')).map(Into::into);
+    Some(PortOwner { pid, name })
+}
+
+/// `ss -ltnp` 输出中的 `users:(("name",pid=123,fd=3))`
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_ss(output: &str) -> Option<PortOwner> {
+    let users = output.split("users:((").nth(1)?;
+    let name = users.split('"').nth(1).map(Into::into);
+    let pid = users.split("pid=").nth(1)?.split([',', ')']).next()?.parse().ok()?;
+    Some(PortOwner { pid, name })
+}
+
+/// `netstat -ano` 中监听该端口的 pid
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_netstat(output: &str, port: u16) -> Option<u32> {
+    let suffix = format!(":{port}");
+    output.lines().find_map(|line| {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        match fields.as_slice() {
+            [_, local, _, "LISTENING", pid] if local.ends_with(&suffix) => pid.parse().ok(),
+            _ => None,
+        }
+    })
+}
+
+/// `tasklist /FO CSV /NH` 的第一列为进程名
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_tasklist(output: &str) -> Option<String> {
+    let name = output.lines().next()?.split(',').next()?.trim_matches('"');
+    (!name.is_empty() && !name.starts_with("INFO:")).then(|| name.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        PortConflict, PortKind, PortOwner, is_core_process_name, parse_lsof, parse_netstat, parse_ss, parse_tasklist,
+        plan_ports,
+    };
+
+    #[test]
+    fn plan_reassigns_busy_and_duplicate_ports() {
+        let busy = [7897, 7898];
+        let ports = [
+            (PortKind::Mixed, 7897),
+            (PortKind::Socks, 7899),
+            (PortKind::Http, 7899),
+            (PortKind::Controller, 9097),
+        ];
+
+        let conflicts = plan_ports(&ports, |port| !busy.contains(&port));
+        assert_eq!(
+            conflicts,
+            vec![
+                PortConflict {
+                    kind: PortKind::Mixed,
+                    port: 7897,
+                    duplicate: false,
+                    replacement: Some(7900),
+                },
+                PortConflict {
+                    kind: PortKind::Http,
+                    port: 7899,
+                    duplicate: true,
+                    replacement: Some(7901),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_without_free_port() {
+        let conflicts = plan_ports(&[(PortKind::Mixed, u16::MAX)], |_| false);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].replacement, None);
+        assert!(plan_ports(&[(PortKind::Mixed, 7897)], |_| true).is_empty());
+    }
+
+    #[test]
+    fn parse_owner_output() {
+        let owner = parse_lsof("p1234\ncclash\nf5\n").map(|owner| owner.to_string());
+        assert_eq!(owner.as_deref(), Some("clash (pid 1234)"));
+        assert_eq!(parse_lsof(""), None);
+
+        let ss = r#"LISTEN 0 4096 127.0.0.1:7897 0.0.0.0:* users:(("mihomo",pid=4321,fd=7))"#;
+        assert_eq!(
+            parse_ss(ss),
+            Some(PortOwner {
+                pid: 4321,
+                name: Some("mihomo".into()),
+            })
+        );
+
+        let netstat = "  TCP    0.0.0.0:7897     0.0.0.0:0     LISTENING     5678\n  TCP    127.0.0.1:78970  0.0.0.0:0  LISTENING  1\n";
+        assert_eq!(parse_netstat(netstat, 7897), Some(5678));
+        assert_eq!(parse_netstat(netstat, 7898), None);
+
+        assert_eq!(
+            parse_tasklist(r#""clash.exe","5678","Console","1","12,345 K""#).as_deref(),
+            Some("clash.exe")
+        );
+        assert_eq!(parse_tasklist("INFO: No tasks are running"), None);
+    }
+
+    #[test]
+    fn own_core_is_not_a_conflict() {
+        let sidecar = PortOwner { pid: 42, name: None };
+        assert!(sidecar.is_own_core(Some(42)));
+        assert!(!sidecar.is_own_core(Some(7)));
+        assert!(!sidecar.is_own_core(None));
+
+        assert!(is_core_process_name("verge-mihomo"));
+        assert!(is_core_process_name("verge-mihomo.exe"));
+        assert!(is_core_process_name("verge-mihomo-al"));
+        assert!(!is_core_process_name("mihomo"));
+        assert!(!is_core_process_name("verge"));
+
+        let service_core = PortOwner {
+            pid: 1000,
+            name: Some("verge-mihomo-alpha".into()),
+        };
+        assert!(service_core.is_own_core(None));
+    }
+}
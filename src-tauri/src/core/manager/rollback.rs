@@ -0,0 +1,308 @@
+use super::{CoreManager, RunningMode};
+use crate::{
+    config::{Config, ConfigType, runtime::IRuntime},
+    constants::{files, timing},
+    core::handle::Handle,
+    enhance::vars::SecretValues,
+    module::core_watchdog::probe_controller,
+    utils::dirs,
+};
+use anyhow::{Context as _, Result, anyhow, bail};
+use clash_verge_logging::{Type, logging, logging_error};
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::Mapping;
+use smartstring::alias::String;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+};
+
+/// 已应用且通过健康检查的运行时配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuntimeSnapshot {
+    /// 保存时间戳 (ms)
+    pub saved_at: i64,
+    pub config: Mapping,
+    #[serde(default)]
+    pub exists_keys: HashSet<String>,
+    /// 配置中机密值的位置，恢复后用于继续隐去这些值
+    #[serde(default)]
+    pub secret_values: SecretValues,
+}
+
+/// 最近一次回滚的信息
+#[derive(Debug, Clone, Serialize)]
+pub struct RollbackRecord {
+    /// 回滚时间戳 (ms)
+    pub timestamp: i64,
+    pub reason: String,
+    /// 是否由用户手动触发
+    pub manual: bool,
+    /// 是否成功恢复
+    pub restored: bool,
+    /// 恢复的快照保存时间 (ms)
+    pub snapshot_at: Option<i64>,
+}
+
+/// 快照文件，`last` 为最近一次成功的配置，`previous` 为它之前的一次
+pub(super) struct SnapshotStore {
+    last: PathBuf,
+    previous: PathBuf,
+}
+
+impl SnapshotStore {
+    fn new() -> Result<Self> {
+        Ok(Self::with_dir(&dirs::app_home_dir()?))
+    }
+
+    pub fn with_dir(dir: &Path) -> Self {
+        Self {
+            last: dir.join(files::LAST_GOOD_CONFIG),
+            previous: dir.join(files::PREVIOUS_GOOD_CONFIG),
+        }
+    }
+
+    pub async fn last(&self) -> Result<Option<RuntimeSnapshot>> {
+        read_snapshot(&self.last).await
+    }
+
+    pub async fn previous(&self) -> Result<Option<RuntimeSnapshot>> {
+        read_snapshot(&self.previous).await
+    }
+
+    /// 保存新的成功配置，原先的成功配置移动到 `previous`，内容未变化时不轮换
+    pub async fn save(&self, snapshot: &RuntimeSnapshot) -> Result<bool> {
+        if let Some(last) = self.last().await?
+            && last.config == snapshot.config
+        {
+            return Ok(false);
+        }
+
+        if tokio::fs::try_exists(&self.last).await.unwrap_or(false) {
+            tokio::fs::rename(&self.last, &self.previous).await?;
+        }
+        write_snapshot(&self.last, snapshot).await?;
+        Ok(true)
+    }
+
+    /// 交换 `last` 与 `previous`，手动回退后再次回退可以撤销
+    pub async fn swap(&self) -> Result<()> {
+        let (Some(last), Some(previous)) = (self.last().await?, self.previous().await?) else {
+            bail!("no previous runtime config");
+        };
+        write_snapshot(&self.last, &previous).await?;
+        write_snapshot(&self.previous, &last).await
+    }
+}
+
+async fn read_snapshot(path: &Path) -> Result<Option<RuntimeSnapshot>> {
+    if !tokio::fs::try_exists(path).await.unwrap_or(false) {
+        return Ok(None);
+    }
+    let content = tokio::fs::read_to_string(path).await?;
+    let snapshot = serde_yaml_ng::from_str(&content)
+        .with_context(|| format!("invalid runtime snapshot \"{}\"", path.display()))?;
+    Ok(Some(snapshot))
+}
+
+async fn write_snapshot(path: &Path, snapshot: &RuntimeSnapshot) -> Result<()> {
+    let content = serde_yaml_ng::to_string(snapshot)?;
+    tokio::fs::write(path, format!("# Clash Verge Runtime Snapshot\n\n{content}")).await?;
+    Ok(())
+}
+
+impl CoreManager {
+    pub fn get_last_rollback(&self) -> Option<RollbackRecord> {
+        self.last_rollback.lock().clone()
+    }
+
+    /// 应用后确认控制器可以正常响应，重启核心后需要等待片刻
+    pub(super) async fn verify_applied(&self) -> Result<()> {
+        let mut last_err = anyhow!("core health check was not performed");
+        for attempt in 0..timing::CONFIG_HEALTH_RETRIES {
+            if attempt > 0 {
+                tokio::time::sleep(timing::CONFIG_HEALTH_INTERVAL).await;
+            }
+            match probe_controller(timing::CONFIG_HEALTH_TIMEOUT).await {
+                Ok(_) => return Ok(()),
+                Err(err) => last_err = err,
+            }
+        }
+        Err(last_err.context("core is unhealthy after applying config"))
+    }
+
+    /// 记录当前已生效的运行时配置
+    pub(super) async fn save_last_good(&self) {
+        let runtime = Config::runtime().await.latest_arc();
+        let Some(config) = runtime.config.clone() else {
+            return;
+        };
+        let snapshot = RuntimeSnapshot {
+            saved_at: chrono::Local::now().timestamp_millis(),
+            config,
+            exists_keys: runtime.exists_keys.clone(),
+            secret_values: runtime.secret_values.clone(),
+        };
+
+        match SnapshotStore::new() {
+            Ok(store) => logging_error!(Type::Core, store.save(&snapshot).await),
+            Err(err) => logging!(warn, Type::Core, "Failed to save runtime snapshot: {err}"),
+        }
+    }
+
+    /// 应用失败后恢复到最近一次成功的配置
+    pub(super) async fn rollback(&self, reason: &str) -> Result<()> {
+        let snapshot = SnapshotStore::new()?.last().await?;
+        self.restore(snapshot, reason, false).await
+    }
+
+    /// 手动回退到上一次成功的运行时配置
+    pub async fn revert_runtime_config(&self) -> Result<()> {
+        let store = SnapshotStore::new()?;
+        let snapshot = store.previous().await?;
+        if snapshot.is_none() {
+            bail!("no previous runtime config");
+        }
+        self.restore(snapshot, "reverted by user", true).await?;
+        store.swap().await
+    }
+
+    async fn restore(&self, snapshot: Option<RuntimeSnapshot>, reason: &str, manual: bool) -> Result<()> {
+        let snapshot_at = snapshot.as_ref().map(|snapshot| snapshot.saved_at);
+        let result = match snapshot {
+            Some(snapshot) => self.apply_snapshot(snapshot).await,
+            None => Err(anyhow!("no last known good runtime config")),
+        };
+
+        let record = RollbackRecord {
+            timestamp: chrono::Local::now().timestamp_millis(),
+            reason: reason.into(),
+            manual,
+            restored: result.is_ok(),
+            snapshot_at,
+        };
+        *self.last_rollback.lock() = Some(record);
+
+        match &result {
+            Ok(()) => {
+                logging!(warn, Type::Core, "Runtime config rolled back: {reason}");
+                Handle::notice_message(
+                    "config_rollback::restored",
+                    format!("Rolled back to the last working runtime config: {reason}"),
+                );
+            }
+            Err(err) => {
+                logging!(error, Type::Core, "Failed to roll back runtime config: {err}");
+                // 尚无可用快照时每次应用失败都会走到这里，只提醒一次
+                if snapshot_at.is_some() || !self.missing_snapshot_noticed.swap(true, Ordering::Relaxed) {
+                    Handle::notice_message("config_rollback::failed", format!("{reason}\n{err}"));
+                }
+            }
+        }
+        result
+    }
+
+    async fn apply_snapshot(&self, snapshot: RuntimeSnapshot) -> Result<()> {
+        Config::runtime().await.edit_draft(|d| restore_runtime(d, snapshot));
+        Config::runtime().await.apply();
+
+        let run_path = Config::generate_file(ConfigType::Run).await?;
+        if let Err(err) = self.reload_config(dirs::path_to_str(&run_path)?).await {
+            logging!(warn, Type::Core, "Failed to reload snapshot, restarting core: {err}");
+            self.restart_core().await?;
+        }
+        self.verify_applied().await
+    }
+}
+
+/// 用快照替换运行时配置，机密值位置随配置一起恢复
+fn restore_runtime(runtime: &mut IRuntime, snapshot: RuntimeSnapshot) {
+    runtime.config = Some(snapshot.config);
+    runtime.exists_keys = snapshot.exists_keys;
+    runtime.secret_values = snapshot.secret_values;
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{RuntimeSnapshot, SnapshotStore, restore_runtime};
+    use crate::{
+        config::runtime::IRuntime,
+        enhance::vars::{PathSegment, SecretValues},
+    };
+    use serde_yaml_ng::Mapping;
+
+    fn snapshot(mode: &str, saved_at: i64) -> RuntimeSnapshot {
+        let mut config = Mapping::new();
+        config.insert("mode".into(), mode.into());
+        RuntimeSnapshot {
+            saved_at,
+            config,
+            exists_keys: Default::default(),
+            secret_values: Default::default(),
+        }
+    }
+
+    /// `secret` 为机密变量展开后的值
+    fn with_secret(mut snapshot: RuntimeSnapshot, secret: &str) -> RuntimeSnapshot {
+        snapshot.config.insert("secret".into(), secret.into());
+        snapshot
+            .secret_values
+            .push(vec![PathSegment::Key("secret".into())], secret.into());
+        snapshot
+    }
+
+    #[tokio::test]
+    async fn redaction_survives_rollback() {
+        let dir = std::env::temp_dir().join(format!("clash-verge-snapshot-secret-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.expect("create temp dir");
+        let store = SnapshotStore::with_dir(&dir);
+
+        let good = with_secret(snapshot("rule", 1), "good-token");
+        assert!(store.save(&good).await.expect("save"));
+        let restored = store.last().await.expect("read").expect("snapshot");
+        assert_eq!(restored, good);
+
+        // 失败的配置中机密值位置不同
+        let mut runtime = IRuntime::new();
+        let mut failed = snapshot("global", 2).config;
+        failed.insert("password".into(), "bad-token".into());
+        runtime.config = Some(failed);
+        let mut secret_values = SecretValues::default();
+        secret_values.push(vec![PathSegment::Key("password".into())], "bad-token".into());
+        runtime.secret_values = secret_values;
+
+        restore_runtime(&mut runtime, restored);
+        let redacted = runtime.secret_values.redact(runtime.config.as_ref().expect("config"));
+        assert_eq!(redacted.get("secret"), Some(&"******".into()));
+        assert_eq!(redacted.get("mode"), Some(&"rule".into()));
+
+        tokio::fs::remove_dir_all(&dir).await.expect("remove temp dir");
+    }
+
+    #[tokio::test]
+    async fn snapshots_rotate_and_swap() {
+        let dir = std::env::temp_dir().join(format!("clash-verge-snapshot-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.expect("create temp dir");
+        let store = SnapshotStore::with_dir(&dir);
+
+        assert!(store.last().await.expect("read").is_none());
+        assert!(store.swap().await.is_err());
+
+        assert!(store.save(&snapshot("rule", 1)).await.expect("save"));
+        // 内容相同时不轮换
+        assert!(!store.save(&snapshot("rule", 2)).await.expect("save"));
+        assert!(store.previous().await.expect("read").is_none());
+
+        assert!(store.save(&snapshot("global", 3)).await.expect("save"));
+        assert_eq!(store.last().await.expect("read"), Some(snapshot("global", 3)));
+        assert_eq!(store.previous().await.expect("read"), Some(snapshot("rule", 1)));
+
+        store.swap().await.expect("swap");
+        assert_eq!(store.last().await.expect("read"), Some(snapshot("rule", 1)));
+        assert_eq!(store.previous().await.expect("read"), Some(snapshot("global", 3)));
+
+        tokio::fs::remove_dir_all(&dir).await.expect("remove temp dir");
+    }
+}
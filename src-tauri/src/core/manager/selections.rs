@@ -0,0 +1,390 @@
+use super::CoreManager;
+use crate::{
+    config::{Config, PrfItem, PrfSelected},
+    constants::timing,
+    core::handle::Handle,
+};
+use anyhow::{Result, anyhow};
+use clash_verge_logging::{Type, logging, logging_error};
+use serde_yaml_ng::Mapping;
+use smartstring::alias::String;
+use std::collections::HashSet;
+
+/// 名称相似度低于该值时不视为同一节点
+const FUZZY_MATCH_THRESHOLD: f64 = 0.7;
+/// 最佳匹配需要比次佳匹配高出该值，否则视为无法确定
+const FUZZY_MATCH_MARGIN: f64 = 0.2;
+
+/// 重启前各策略组的选择与代理模式
+#[derive(Debug)]
+pub struct SelectionSnapshot {
+    mode: Option<String>,
+    selections: Vec<(String, String)>,
+}
+
+impl SelectionSnapshot {
+    /// 本次修改设置了新的代理模式时，不再恢复旧模式
+    pub fn without_mode(self) -> Self {
+        Self { mode: None, ..self }
+    }
+}
+
+/// 单个策略组的恢复结果
+#[derive(Debug, PartialEq, Eq)]
+struct RestoreStep<'a> {
+    group: &'a str,
+    previous: &'a str,
+    /// 重启后对应的节点，找不到时为 None
+    target: Option<&'a str>,
+    /// 当前选择与目标不同，需要通过控制器切换
+    select: bool,
+}
+
+impl CoreManager {
+    /// 重启核心并恢复手动选择的节点与代理模式
+    pub async fn graceful_restart_core(&self) -> Result<()> {
+        let snapshot = self.snapshot_selections().await;
+        self.restart_and_restore(snapshot).await
+    }
+
+    /// 重启核心并恢复 `snapshot`，需要重新生成配置时应在 `Config::generate` 之前获取快照
+    pub async fn restart_and_restore(&self, snapshot: Option<SelectionSnapshot>) -> Result<()> {
+        self.restart_core().await?;
+
+        if let Some(snapshot) = snapshot {
+            logging_error!(Type::Core, self.restore_selections(snapshot).await);
+        }
+        Ok(())
+    }
+
+    /// 通过控制器读取当前选择，控制器无响应时返回 None；可选的策略组取自当前的运行时配置
+    pub async fn snapshot_selections(&self) -> Option<SelectionSnapshot> {
+        let result = tokio::time::timeout(timing::CONFIG_HEALTH_TIMEOUT, async {
+            let mihomo = Handle::mihomo().await;
+            let proxies = mihomo.get_proxies().await.map_err(|err| anyhow!("{err}"))?;
+            let base = mihomo.get_base_config().await.map_err(|err| anyhow!("{err}"))?;
+            anyhow::Ok((proxies, serde_json::to_value(base)?))
+        })
+        .await;
+
+        let (proxies, base) = match result {
+            Ok(Ok(data)) => data,
+            Ok(Err(err)) => {
+                logging!(warn, Type::Core, "Failed to snapshot proxy selections: {err}");
+                return None;
+            }
+            Err(_) => {
+                logging!(warn, Type::Core, "Timed out while snapshotting proxy selections");
+                return None;
+            }
+        };
+
+        let selectable = selector_groups().await;
+        let selections = proxies
+            .proxies
+            .iter()
+            .filter(|(name, _)| selectable.contains(name.as_str()))
+            .filter_map(|(name, group)| Some((name.as_str().into(), group.now.as_deref()?.into())))
+            .collect();
+        Some(SelectionSnapshot {
+            mode: base.get("mode").and_then(|mode| mode.as_str()).map(Into::into),
+            selections,
+        })
+    }
+
+    async fn restore_selections(&self, snapshot: SelectionSnapshot) -> Result<()> {
+        self.verify_applied().await?;
+        let mihomo = Handle::mihomo().await;
+
+        let current_mode = Config::clash()
+            .await
+            .latest_arc()
+            .0
+            .get("mode")
+            .and_then(|mode| mode.as_str())
+            .map(String::from);
+        if let Some(mode) = &snapshot.mode
+            && current_mode.as_ref() != Some(mode)
+        {
+            mihomo
+                .patch_base_config(&serde_json::json!({ "mode": mode }))
+                .await
+                .map_err(|err| anyhow!("{err}"))?;
+            let mut patch = Mapping::new();
+            patch.insert("mode".into(), mode.as_str().into());
+            Config::clash().await.edit_draft(|d| d.patch_config(&patch));
+            Config::clash().await.apply();
+            Config::clash().await.data_arc().save_config().await?;
+        }
+
+        let proxies = mihomo.get_proxies().await.map_err(|err| anyhow!("{err}"))?;
+        let steps = plan_restore(&snapshot.selections, |group| {
+            let data = proxies.proxies.get(group)?;
+            Some((data.now.as_deref(), data.all.as_deref().unwrap_or_default()))
+        });
+        let mut selected = Vec::new();
+        for RestoreStep {
+            group,
+            previous,
+            target,
+            select,
+        } in steps
+        {
+            let Some(target) = target else {
+                logging!(warn, Type::Core, "Node {previous} of group {group} no longer exists");
+                continue;
+            };
+
+            if select {
+                if let Err(err) = mihomo.select_node_for_group(group, target).await {
+                    logging!(warn, Type::Core, "Failed to restore {group} -> {target}: {err}");
+                    continue;
+                }
+                if target != previous {
+                    logging!(info, Type::Core, "Restored {group}: {previous} -> {target} (renamed)");
+                }
+            }
+            selected.push(PrfSelected {
+                name: Some(group.into()),
+                now: Some(target.into()),
+            });
+        }
+        drop(mihomo);
+
+        save_selected(selected).await?;
+        Handle::refresh_clash();
+        Ok(())
+    }
+}
+
+/// 运行时配置中 `select` 类型的策略组，以及 GLOBAL
+async fn selector_groups() -> HashSet<String> {
+    let runtime = Config::runtime().await.latest_arc();
+    let mut groups = runtime
+        .config
+        .as_ref()
+        .and_then(|config| config.get("proxy-groups"))
+        .and_then(|groups| groups.as_sequence())
+        .into_iter()
+        .flatten()
+        .filter(|group| group.get("type").and_then(|t| t.as_str()) == Some("select"))
+        .filter_map(|group| group.get("name").and_then(|name| name.as_str()))
+        .map(String::from)
+        .collect::<HashSet<_>>();
+    groups.insert("GLOBAL".into());
+    groups
+}
+
+/// 将恢复后的选择写回当前订阅，保留其它策略组的记录
+async fn save_selected(selected: Vec<PrfSelected>) -> Result<()> {
+    let profiles = Config::profiles().await.latest_arc();
+    let Some(uid) = profiles.get_current().cloned() else {
+        return Ok(());
+    };
+    let mut merged = profiles.get_item(&uid)?.selected.clone().unwrap_or_default();
+    drop(profiles);
+
+    for item in selected {
+        match merged.iter_mut().find(|each| each.name == item.name) {
+            Some(each) => each.now = item.now,
+            None => merged.push(item),
+        }
+    }
+
+    let patch = PrfItem {
+        selected: Some(merged),
+        ..Default::default()
+    };
+    Config::profiles()
+        .await
+        .with_data_modify(|mut profiles| async move {
+            profiles.patch_item(&uid, &patch).await?;
+            Ok((profiles, ()))
+        })
+        .await
+}
+
+/// 按快照逐个策略组查找重启后对应的节点，`lookup` 返回策略组当前的选择与全部节点，策略组已不存在时跳过
+fn plan_restore<'a, S: AsRef<str> + 'a>(
+    selections: &'a [(String, String)],
+    lookup: impl Fn(&str) -> Option<(Option<&'a str>, &'a [S])>,
+) -> Vec<RestoreStep<'a>> {
+    selections
+        .iter()
+        .filter_map(|(group, previous)| {
+            let (now, candidates) = lookup(group)?;
+            let target = match_node(previous, candidates);
+            Some(RestoreStep {
+                group,
+                previous,
+                target,
+                select: target.is_some() && now != target,
+            })
+        })
+        .collect()
+}
+
+/// 在候选节点中查找之前选择的节点，名称变化时按相似度匹配，
+/// 名称中的编号必须一致，且最佳匹配需明显优于其它候选
+pub(super) fn match_node<'a, S: AsRef<str>>(previous: &str, candidates: &'a [S]) -> Option<&'a str> {
+    let candidates = candidates.iter().map(AsRef::as_ref);
+    if let Some(exact) = candidates.clone().find(|candidate| *candidate == previous) {
+        return Some(exact);
+    }
+
+    let target = normalize(previous);
+    if target.is_empty() {
+        return None;
+    }
+    if let Some(same) = candidates.clone().find(|candidate| normalize(candidate) == target) {
+        return Some(same);
+    }
+
+    let expected = numbers(previous);
+    let mut scores = candidates
+        .filter(|candidate| numbers(candidate) == expected)
+        .map(|candidate| (candidate, similarity(&target, &normalize(candidate))))
+        .collect::<Vec<_>>();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    match scores.as_slice() {
+        [(best, score), rest @ ..]
+            if *score >= FUZZY_MATCH_THRESHOLD
+                && rest
+                    .first()
+                    .is_none_or(|(_, runner_up)| score - runner_up >= FUZZY_MATCH_MARGIN) =>
+        {
+            Some(best)
+        }
+        _ => None,
+    }
+}
+
+/// 名称中的数字序列，如 `香港 01 x2` 为 `["01", "2"]`
+fn numbers(name: &str) -> Vec<&str> {
+    name.split(|ch: char| !ch.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// 去掉 emoji、符号与空白并转为小写
+fn normalize(name: &str) -> Vec<char> {
+    name.chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 基于编辑距离的相似度，取值 0 ~ 1
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            current[j + 1] = (previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RestoreStep, SelectionSnapshot, match_node, plan_restore};
+    use smartstring::alias::String;
+    use std::collections::HashMap;
+
+    #[test]
+    fn snapshot_without_mode_keeps_selections() {
+        let snapshot = SelectionSnapshot {
+            mode: Some("global".into()),
+            selections: vec![("PROXY".into(), "node-1".into())],
+        }
+        .without_mode();
+
+        assert_eq!(snapshot.mode, None);
+        assert_eq!(snapshot.selections, vec![("PROXY".into(), "node-1".into())]);
+    }
+
+    #[test]
+    fn match_renamed_nodes() {
+        let candidates = ["🇭🇰 香港 01 | IPLC", "🇯🇵 日本 01", "🇺🇸 美国 02", "DIRECT"];
+
+        assert_eq!(match_node("DIRECT", &candidates), Some("DIRECT"));
+        // 仅 emoji 或空白变化
+        assert_eq!(match_node("🇯🇵日本 01", &candidates), Some("🇯🇵 日本 01"));
+        // 订阅更新后名称有少量变化
+        assert_eq!(match_node("香港 01 IPLC 专线", &candidates), Some("🇭🇰 香港 01 | IPLC"));
+        assert_eq!(match_node("美国 02", &candidates), Some("🇺🇸 美国 02"));
+        // 差异过大不匹配
+        assert_eq!(match_node("Singapore Premium", &candidates), None);
+        assert_eq!(match_node("🇸🇬", &candidates), None);
+        assert_eq!(match_node::<&str>("DIRECT", &[]), None);
+        // 编号不同的节点不能互相替代
+        assert_eq!(match_node("香港 01", &["香港 02", "香港 03"]), None);
+        assert_eq!(match_node("🇭🇰 香港 01", &["香港 02", "香港 01 新"]), Some("香港 01 新"));
+        // 多个候选同样接近时不匹配
+        assert_eq!(match_node("香港 IPLC C", &["香港 IPLC A", "香港 IPLC B"]), None);
+    }
+
+    #[test]
+    fn restore_selections_plan() {
+        let selections: Vec<(String, String)> = [
+            ("Proxy", "🇯🇵 日本 01"),
+            ("Auto", "香港 01"),
+            ("Stream", "🇺🇸 美国 02"),
+            ("Removed", "DIRECT"),
+            ("Manual", "REJECT"),
+        ]
+        .into_iter()
+        .map(|(group, node)| (group.into(), node.into()))
+        .collect();
+        let groups: HashMap<&str, (Option<&str>, Vec<&str>)> = HashMap::from([
+            ("Proxy", (Some("DIRECT"), vec!["DIRECT", "🇯🇵日本 01"])),
+            ("Auto", (Some("香港 02"), vec!["香港 02", "香港 03"])),
+            ("Stream", (Some("美国 02"), vec!["DIRECT", "美国 02"])),
+            ("Manual", (Some("DIRECT"), vec!["DIRECT", "REJECT"])),
+        ]);
+
+        let steps = plan_restore(&selections, |group| {
+            groups.get(group).map(|(now, all)| (*now, all.as_slice()))
+        });
+        assert_eq!(
+            steps,
+            vec![
+                RestoreStep {
+                    group: "Proxy",
+                    previous: "🇯🇵 日本 01",
+                    target: Some("🇯🇵日本 01"),
+                    select: true,
+                },
+                RestoreStep {
+                    group: "Auto",
+                    previous: "香港 01",
+                    target: None,
+                    select: false,
+                },
+                RestoreStep {
+                    group: "Stream",
+                    previous: "🇺🇸 美国 02",
+                    target: Some("美国 02"),
+                    select: false,
+                },
+                RestoreStep {
+                    group: "Manual",
+                    previous: "REJECT",
+                    target: Some("REJECT"),
+                    select: true,
+                },
+            ]
+        );
+    }
+}
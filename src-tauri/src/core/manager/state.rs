@@ -2,7 +2,11 @@ use super::{CoreManager, RunningMode};
 use crate::{
     AsyncHandler,
     config::{Config, IClashTemp},
-    core::{handle, logger::Logger, manager::CLASH_LOGGER, service},
+    core::{
+        core_log::{CORE_LOGS, CoreLogEntry, CoreLogFilter, CoreLogQuery, DEFAULT_LOG_CAPACITY, parse_lines},
+        logger::Logger,
+        managed_core, service,
+    },
     logging,
     utils::dirs,
 };
@@ -11,14 +15,25 @@ use clash_verge_logging::Type;
 use compact_str::CompactString;
 use log::Level;
 use scopeguard::defer;
-use tauri_plugin_shell::ShellExt as _;
 
 impl CoreManager {
     pub async fn get_clash_logs(&self) -> Result<Vec<CompactString>> {
         match *self.get_running_mode() {
             RunningMode::Service => service::get_clash_logs_by_service().await,
-            RunningMode::Sidecar => Ok(CLASH_LOGGER.get_logs().await),
-            RunningMode::NotRunning => Ok(Vec::new()),
+            // 保留本次会话中 sidecar 的日志，外部内核的日志只能通过控制器获取
+            RunningMode::Sidecar | RunningMode::External | RunningMode::NotRunning => Ok(CORE_LOGS.lines()),
+        }
+    }
+
+    /// 按条件查询解析后的内核日志
+    pub async fn query_clash_logs(&self, query: CoreLogQuery) -> Result<Vec<CoreLogEntry>> {
+        let filter = CoreLogFilter::new(query)?;
+        match *self.get_running_mode() {
+            RunningMode::Service => {
+                let lines = service::get_clash_logs_by_service().await?;
+                Ok(filter.apply(parse_lines(&lines)))
+            }
+            RunningMode::Sidecar | RunningMode::External | RunningMode::NotRunning => Ok(CORE_LOGS.query(&filter)),
         }
     }
 
@@ -26,15 +41,14 @@ impl CoreManager {
         logging!(info, Type::Core, "Starting core in sidecar mode");
 
         let config_file = Config::generate_file(crate::config::ConfigType::Run).await?;
-        let app_handle = handle::Handle::app_handle();
-        let clash_core = Config::verge().await.latest_arc().get_valid_clash_core();
+        let verge = Config::verge().await.latest_arc();
+        let clash_core = verge.get_valid_clash_core();
+        CORE_LOGS.set_capacity(verge.core_log_buffer_size.unwrap_or(DEFAULT_LOG_CAPACITY));
         let config_dir = dirs::app_home_dir()?;
 
         #[cfg(unix)]
         let previous_mask = unsafe { tauri_plugin_clash_verge_sysinfo::libc::umask(0o007) };
-        let (mut rx, child) = app_handle
-            .shell()
-            .sidecar(clash_core.as_str())?
+        let (mut rx, child) = managed_core::core_command(&clash_core)?
             .args([
                 "-d",
                 dirs::path_to_str(&config_dir)?,
@@ -58,15 +72,19 @@ impl CoreManager {
 
         self.set_running_child_sidecar(child);
         self.set_running_mode(RunningMode::Sidecar);
+        let generation = self.supervisor.begin();
+        let run = CORE_LOGS.begin_run();
 
-        AsyncHandler::spawn(|| async move {
+        AsyncHandler::spawn(move || async move {
             while let Some(event) = rx.recv().await {
                 match event {
-                    tauri_plugin_shell::process::CommandEvent::Stdout(line)
-                    | tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
-                        let message = CompactString::from(&*String::from_utf8_lossy(&line));
-                        Logger::global().writer_sidecar_log(Level::Error, &message);
-                        CLASH_LOGGER.append_log(message).await;
+                    tauri_plugin_shell::process::CommandEvent::Stdout(line) => {
+                        let entry = CORE_LOGS.push(&String::from_utf8_lossy(&line), false);
+                        Logger::global().writer_sidecar_log(entry.level.as_log_level(), &entry.raw);
+                    }
+                    tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
+                        let entry = CORE_LOGS.push(&String::from_utf8_lossy(&line), true);
+                        Logger::global().writer_sidecar_log(entry.level.as_log_level(), &entry.raw);
                     }
                     tauri_plugin_shell::process::CommandEvent::Terminated(term) => {
                         let message = if let Some(code) = term.code {
@@ -77,7 +95,8 @@ impl CoreManager {
                             CompactString::from("Process terminated")
                         };
                         Logger::global().writer_sidecar_log(Level::Info, &message);
-                        CLASH_LOGGER.clear_logs().await;
+                        let logs = CORE_LOGS.run_lines(run);
+                        CoreManager::global().handle_sidecar_exit(generation, &message, &logs);
                         break;
                     }
                     _ => {}
@@ -90,6 +109,7 @@ impl CoreManager {
 
     pub(super) fn stop_core_by_sidecar(&self) {
         logging!(info, Type::Core, "Stopping sidecar");
+        self.supervisor.invalidate();
         defer! {
             self.set_running_mode(RunningMode::NotRunning);
         }
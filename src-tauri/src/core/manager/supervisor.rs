@@ -0,0 +1,263 @@
+use super::{CoreManager, RunningMode};
+use crate::{AsyncHandler, constants::timing, core::handle::Handle, logging};
+use clash_verge_logging::Type;
+use compact_str::CompactString;
+use parking_lot::Mutex;
+use serde::Serialize;
+use smartstring::alias::String;
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// 崩溃时附带的 stderr 行数
+const STDERR_TAIL_LINES: usize = 10;
+
+/// sidecar 重启统计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CoreRestartStats {
+    /// 自动重启次数
+    pub restart_count: u32,
+    /// 异常退出次数
+    pub crash_count: u32,
+    /// 最近一次异常退出的原因
+    pub last_exit_reason: Option<String>,
+    /// 最近一次异常退出的时间戳 (ms)
+    pub last_crash_at: Option<i64>,
+    /// 是否因频繁崩溃而停止自动重启
+    pub crash_looping: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum CrashAction {
+    Restart(Duration),
+    GiveUp,
+}
+
+/// 监督 sidecar 进程，区分主动停止与异常退出
+///
+/// 每次启动 sidecar 都会分配新的 generation，主动停止时使其失效，
+/// 只有当前 generation 的退出事件才会被视为崩溃。
+#[derive(Debug)]
+pub(super) struct Supervisor {
+    generation: AtomicU64,
+    crashes: Mutex<VecDeque<Instant>>,
+    stats: Mutex<CoreRestartStats>,
+    restart_tx: UnboundedSender<(u64, Duration)>,
+    restart_rx: Mutex<Option<UnboundedReceiver<(u64, Duration)>>>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        let (restart_tx, restart_rx) = unbounded_channel();
+        Self {
+            generation: AtomicU64::new(0),
+            crashes: Mutex::new(VecDeque::new()),
+            stats: Mutex::new(CoreRestartStats::default()),
+            restart_tx,
+            restart_rx: Mutex::new(Some(restart_rx)),
+        }
+    }
+}
+
+impl Supervisor {
+    /// 新的 sidecar 进程已启动
+    pub fn begin(&self) -> u64 {
+        self.stats.lock().crash_looping = false;
+        self.generation.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    /// 主动停止，之后的退出事件不再触发重启
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::Acquire) == generation
+    }
+
+    pub fn stats(&self) -> CoreRestartStats {
+        self.stats.lock().clone()
+    }
+
+    fn record_restart(&self) {
+        self.stats.lock().restart_count += 1;
+    }
+
+    /// 记录一次崩溃，并决定是否继续重启
+    pub fn record_crash(&self, reason: &str, now: Instant) -> CrashAction {
+        let mut crashes = self.crashes.lock();
+        while crashes
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > timing::CORE_CRASH_WINDOW)
+        {
+            crashes.pop_front();
+        }
+        crashes.push_back(now);
+        let attempts = crashes.len();
+
+        let mut stats = self.stats.lock();
+        stats.crash_count += 1;
+        stats.last_exit_reason = Some(reason.into());
+        stats.last_crash_at = Some(chrono::Local::now().timestamp_millis());
+
+        if attempts > timing::CORE_CRASH_LIMIT {
+            // 放弃后清空记录，用户手动重启时重新计算
+            crashes.clear();
+            stats.crash_looping = true;
+            return CrashAction::GiveUp;
+        }
+        CrashAction::Restart(backoff_delay(attempts))
+    }
+}
+
+fn backoff_delay(attempt: usize) -> Duration {
+    let exp = u32::try_from(attempt.saturating_sub(1)).unwrap_or(u32::MAX).min(16);
+    timing::CORE_RESTART_BASE_DELAY
+        .saturating_mul(1 << exp)
+        .min(timing::CORE_RESTART_MAX_DELAY)
+}
+
+impl CoreManager {
+    pub fn get_restart_stats(&self) -> CoreRestartStats {
+        self.supervisor.stats()
+    }
+
+    /// 启动重启循环，重启请求通过通道传递，避免在退出事件中直接启动核心
+    pub(super) fn spawn_supervisor(&self) {
+        let Some(mut rx) = self.supervisor.restart_rx.lock().take() else {
+            return;
+        };
+
+        AsyncHandler::spawn(move || async move {
+            while let Some((generation, delay)) = rx.recv().await {
+                tokio::time::sleep(delay).await;
+
+                let manager = CoreManager::global();
+                // 等待期间用户已手动启动或停止
+                if !manager.supervisor.is_current(generation) || Handle::global().is_exiting() {
+                    continue;
+                }
+
+                manager.supervisor.record_restart();
+                logging!(info, Type::Core, "Restarting crashed sidecar");
+                if let Err(err) = manager.start_core().await {
+                    logging!(error, Type::Core, "Failed to restart crashed sidecar: {err}");
+                    Handle::notice_message("core_supervisor::restart_failed", format!("{err}"));
+                }
+            }
+        });
+    }
+
+    /// sidecar 退出事件，主动停止时忽略
+    pub(super) fn handle_sidecar_exit(&self, generation: u64, reason: &str, logs: &[CompactString]) {
+        if !self.supervisor.is_current(generation) || Handle::global().is_exiting() {
+            return;
+        }
+
+        drop(self.take_child_sidecar());
+        self.set_running_mode(RunningMode::NotRunning);
+
+        let tail = logs
+            .iter()
+            .skip(logs.len().saturating_sub(STDERR_TAIL_LINES))
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n");
+        logging!(warn, Type::Core, "Sidecar exited unexpectedly: {reason}\n{tail}");
+
+        match self.supervisor.record_crash(reason, Instant::now()) {
+            CrashAction::Restart(delay) => {
+                Handle::notice_message(
+                    "core_supervisor::restarting",
+                    format!("{reason}, restarting in {}s\n{tail}", delay.as_secs()),
+                );
+                if self.supervisor.restart_tx.send((generation, delay)).is_err() {
+                    logging!(error, Type::Core, "Sidecar supervisor is not running");
+                }
+            }
+            CrashAction::GiveUp => {
+                Handle::notice_message(
+                    "core_supervisor::crash_loop",
+                    format!("{reason}, stopped restarting after repeated crashes\n{tail}"),
+                );
+            }
+        }
+        self.after_core_process();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{CrashAction, Supervisor, backoff_delay};
+    use crate::constants::timing;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn generation_tracks_intentional_stop() {
+        let supervisor = Supervisor::default();
+        let generation = supervisor.begin();
+        assert!(supervisor.is_current(generation));
+
+        supervisor.invalidate();
+        assert!(!supervisor.is_current(generation));
+        assert!(supervisor.is_current(supervisor.begin()));
+    }
+
+    #[test]
+    fn stop_during_backoff_cancels_restart() {
+        let supervisor = Supervisor::default();
+        let generation = supervisor.begin();
+
+        let action = supervisor.record_crash("code 1", Instant::now());
+        assert!(matches!(action, CrashAction::Restart(_)));
+        supervisor
+            .restart_tx
+            .send((generation, Duration::ZERO))
+            .expect("queue restart");
+        // 退避期间用户停止核心
+        supervisor.invalidate();
+
+        let mut rx = supervisor.restart_rx.lock().take().expect("receiver");
+        let (queued, _) = rx.try_recv().expect("restart is queued");
+        assert!(!supervisor.is_current(queued));
+    }
+
+    #[test]
+    fn backoff_grows_until_limit() {
+        assert_eq!(backoff_delay(1), timing::CORE_RESTART_BASE_DELAY);
+        assert_eq!(backoff_delay(2), timing::CORE_RESTART_BASE_DELAY * 2);
+        assert_eq!(backoff_delay(100), timing::CORE_RESTART_MAX_DELAY);
+    }
+
+    #[test]
+    fn crash_loop_gives_up() {
+        let supervisor = Supervisor::default();
+        let start = Instant::now();
+
+        for _ in 0..timing::CORE_CRASH_LIMIT {
+            let action = supervisor.record_crash("code 1", start);
+            assert!(matches!(action, CrashAction::Restart(_)));
+        }
+        let action = supervisor.record_crash("code 1", start + Duration::from_secs(1));
+        assert_eq!(action, CrashAction::GiveUp);
+
+        let stats = supervisor.stats();
+        assert!(stats.crash_looping);
+        assert_eq!(
+            usize::try_from(stats.crash_count).ok(),
+            Some(timing::CORE_CRASH_LIMIT + 1)
+        );
+        assert_eq!(stats.last_exit_reason.as_deref(), Some("code 1"));
+
+        // 超出时间窗口的崩溃不计入
+        let later = start + timing::CORE_CRASH_WINDOW * 3;
+        assert_eq!(
+            supervisor.record_crash("code 1", later),
+            CrashAction::Restart(timing::CORE_RESTART_BASE_DELAY)
+        );
+    }
+}
@@ -1,14 +1,21 @@
 pub mod autostart;
 pub mod backup;
+pub mod core_log;
 pub mod handle;
 pub mod hotkey;
 pub mod logger;
+pub mod managed_core;
 pub mod manager;
 mod notification;
+pub mod pac;
+pub mod proxy_guard;
+mod proxy_watch;
 pub mod service;
+pub mod service_version;
 pub mod sysopt;
 pub mod timer;
 pub mod tray;
+pub mod tun_preflight;
 pub mod validate;
 pub mod win_uwp;
 
@@ -1,4 +1,4 @@
-use crate::utils::window_manager::WindowManager;
+use crate::utils::{resolve::scheme::DeepLinkPreview, window_manager::WindowManager};
 use clash_verge_logging::{Type, logging};
 use serde_json::json;
 use smartstring::alias::String;
@@ -14,6 +14,7 @@ pub enum FrontendEvent<'a> {
     TimerUpdated { profile_index: &'a String },
     ProfileUpdateStarted { uid: &'a String },
     ProfileUpdateCompleted { uid: &'a String },
+    DeepLinkConfirm { preview: &'a DeepLinkPreview },
 }
 
 #[derive(Debug)]
@@ -41,6 +42,7 @@ impl NotificationSystem {
             FrontendEvent::TimerUpdated { profile_index } => ("verge://timer-updated", Ok(json!(profile_index))),
             FrontendEvent::ProfileUpdateStarted { uid } => ("profile-update-started", Ok(json!({ "uid": uid }))),
             FrontendEvent::ProfileUpdateCompleted { uid } => ("profile-update-completed", Ok(json!({ "uid": uid }))),
+            FrontendEvent::DeepLinkConfirm { preview } => ("verge://deep-link-confirm", serde_json::to_value(preview)),
         }
     }
 
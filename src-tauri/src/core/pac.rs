@@ -0,0 +1,512 @@
+use crate::{
+    config::{Config, IRuntime},
+    core::sysopt,
+    utils::bypass::{BypassHost, BypassList},
+};
+use clash_verge_draft::SharedDraft;
+use clash_verge_logging::{Type, logging};
+use parking_lot::RwLock;
+use serde::Serialize;
+use smartstring::alias::String;
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+};
+
+pub const DEFAULT_PAC_RULE_LIMIT: usize = 5000;
+
+static PAC_CACHE: RwLock<Option<PacCache>> = RwLock::new(None);
+
+/// 由运行时配置生成的 PAC 及生成时的参数，运行时配置变化后重新生成
+struct PacCache {
+    runtime: SharedDraft<IRuntime>,
+    proxy: String,
+    mode: String,
+    bypass: String,
+    limit: usize,
+    script: String,
+    report: PacReport,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Direct,
+    Proxy,
+}
+
+impl Target {
+    /// REJECT 等策略在 PAC 中无法表达
+    fn parse(policy: &str) -> Option<Self> {
+        match policy {
+            "DIRECT" => Some(Self::Direct),
+            "REJECT" | "REJECT-DROP" | "PASS" => None,
+            _ => Some(Self::Proxy),
+        }
+    }
+
+    const fn code(self) -> u8 {
+        match self {
+            Self::Direct => 0,
+            Self::Proxy => 1,
+        }
+    }
+}
+
+/// 规则编译结果统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PacReport {
+    pub mode: String,
+    /// 运行时配置中的规则总数
+    pub total: usize,
+    /// 写入 PAC 的规则数，不含 MATCH
+    pub compiled: usize,
+    /// 超出上限被丢弃的规则数
+    pub truncated: usize,
+    pub limit: usize,
+    /// 无法在 PAC 中表达的规则，按类型计数
+    pub unsupported: BTreeMap<String, usize>,
+    /// MATCH 之后永远不会命中的规则数
+    pub unreachable: usize,
+}
+
+/// 按规则顺序记录命中后的目标，序号小者优先
+#[derive(Default)]
+struct CompiledRules {
+    domains: BTreeMap<String, (usize, Target)>,
+    suffixes: BTreeMap<String, (usize, Target)>,
+    keywords: Vec<(String, usize, Target)>,
+    cidrs: Vec<(u32, u32, usize, Target)>,
+    fallback: Option<(usize, Target)>,
+}
+
+/// 获取生成的 PAC，运行时配置、代理地址或模式变化后重新生成
+pub async fn generated_pac() -> String {
+    generate().await.0
+}
+
+pub async fn pac_report() -> PacReport {
+    generate().await.1
+}
+
+async fn generate() -> (String, PacReport) {
+    let verge = Config::verge().await.data_arc();
+    let clash = Config::clash().await.data_arc();
+    let limit = verge.pac_rule_limit.unwrap_or(DEFAULT_PAC_RULE_LIMIT);
+    let (host, port) = sysopt::proxy_endpoint().await;
+    let proxy: String = format!("PROXY {host}:{port}; SOCKS5 {host}:{port}; DIRECT").into();
+    let mode: String = clash
+        .0
+        .get("mode")
+        .and_then(|mode| mode.as_str())
+        .unwrap_or("rule")
+        .to_lowercase()
+        .into();
+    let bypass_list = sysopt::bypass_list().await;
+    let bypass: String = bypass_list.to_string().into();
+    let runtime = Config::runtime().await.data_arc();
+
+    let cached = PAC_CACHE
+        .read()
+        .as_ref()
+        .filter(|cache| {
+            Arc::ptr_eq(&cache.runtime, &runtime)
+                && cache.proxy == proxy
+                && cache.mode == mode
+                && cache.bypass == bypass
+                && cache.limit == limit
+        })
+        .map(|cache| (cache.script.clone(), cache.report.clone()));
+    if let Some(cached) = cached {
+        return cached;
+    }
+
+    let rules = runtime
+        .config
+        .as_ref()
+        .and_then(|config| config.get("rules"))
+        .and_then(|rules| rules.as_sequence())
+        .into_iter()
+        .flatten()
+        .filter_map(|rule| rule.as_str())
+        .collect::<Vec<_>>();
+    let (script, report) = compile_pac(&rules, &mode, &proxy, &bypass_list, limit);
+    if report.truncated > 0 {
+        logging!(
+            warn,
+            Type::Network,
+            "PAC rule limit {} reached, {} rules were dropped",
+            report.limit,
+            report.truncated
+        );
+    }
+    logging!(
+        info,
+        Type::Network,
+        "Generated PAC from {} of {} rules, unsupported: {:?}",
+        report.compiled,
+        report.total,
+        report.unsupported
+    );
+
+    *PAC_CACHE.write() = Some(PacCache {
+        runtime,
+        proxy,
+        mode,
+        bypass,
+        limit,
+        script: script.clone(),
+        report: report.clone(),
+    });
+    (script, report)
+}
+
+/// 将 DOMAIN、DOMAIN-SUFFIX、DOMAIN-KEYWORD、IP-CIDR 与 MATCH 规则编译为 `FindProxyForURL`
+///
+/// IP-CIDR 只匹配以 IPv4 字面量访问的主机，不会在 PAC 中解析域名；命中绕过列表的主机始终直连
+pub fn compile_pac(rules: &[&str], mode: &str, proxy: &str, bypass: &BypassList, limit: usize) -> (String, PacReport) {
+    let mut report = PacReport {
+        mode: mode.into(),
+        total: rules.len(),
+        limit,
+        ..PacReport::default()
+    };
+
+    let compiled = match mode {
+        "global" => CompiledRules {
+            fallback: Some((0, Target::Proxy)),
+            ..CompiledRules::default()
+        },
+        "direct" => CompiledRules {
+            fallback: Some((0, Target::Direct)),
+            ..CompiledRules::default()
+        },
+        _ => compile_rules(rules, limit, &mut report),
+    };
+    (render(&compiled, proxy, bypass, &report), report)
+}
+
+fn compile_rules(rules: &[&str], limit: usize, report: &mut PacReport) -> CompiledRules {
+    let mut compiled = CompiledRules::default();
+
+    for (index, rule) in rules.iter().enumerate() {
+        let fields = rule.split(',').map(str::trim).collect::<Vec<_>>();
+        let kind = fields[0].to_ascii_uppercase();
+
+        if kind == "MATCH" || kind == "FINAL" {
+            match fields.get(1).and_then(|policy| Target::parse(policy)) {
+                Some(target) => compiled.fallback = Some((index, target)),
+                None => *report.unsupported.entry(kind.into()).or_default() += 1,
+            }
+            report.unreachable = rules.len() - index - 1;
+            break;
+        }
+
+        let (Some(payload), Some(target)) = (fields.get(1), fields.get(2).and_then(|policy| Target::parse(policy)))
+        else {
+            *report.unsupported.entry(kind.into()).or_default() += 1;
+            continue;
+        };
+        if !matches!(kind.as_str(), "DOMAIN" | "DOMAIN-SUFFIX" | "DOMAIN-KEYWORD" | "IP-CIDR") {
+            *report.unsupported.entry(kind.into()).or_default() += 1;
+            continue;
+        }
+        if report.compiled >= limit {
+            report.truncated += 1;
+            continue;
+        }
+
+        let payload = payload.trim_end_matches('.').to_lowercase();
+        let added = match kind.as_str() {
+            "DOMAIN" => {
+                compiled.domains.entry(payload.into()).or_insert((index, target));
+                true
+            }
+            "DOMAIN-SUFFIX" => {
+                compiled.suffixes.entry(payload.into()).or_insert((index, target));
+                true
+            }
+            "DOMAIN-KEYWORD" => {
+                compiled.keywords.push((payload.into(), index, target));
+                true
+            }
+            _ => match parse_cidr(&payload) {
+                Some((network, mask)) => {
+                    compiled.cidrs.push((network, mask, index, target));
+                    true
+                }
+                None => false,
+            },
+        };
+        if added {
+            report.compiled += 1;
+        } else {
+            *report.unsupported.entry(kind.into()).or_default() += 1;
+        }
+    }
+    compiled
+}
+
+fn parse_cidr(cidr: &str) -> Option<(u32, u32)> {
+    let (ip, prefix) = cidr.split_once('/')?;
+    let ip = u32::from(ip.parse::<Ipv4Addr>().ok()?);
+    let prefix = prefix.parse::<u32>().ok().filter(|prefix| *prefix <= 32)?;
+    let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+    Some((ip & mask, mask))
+}
+
+fn js_string(value: &str) -> std::string::String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".into())
+}
+
+fn render_table(name: &str, table: &BTreeMap<String, (usize, Target)>) -> String {
+    let entries = table
+        .iter()
+        .map(|(key, (index, target))| format!("{}:[{index},{}]", js_string(key), target.code()))
+        .collect::<Vec<_>>();
+    format!("var {name} = {{{}}};\n", entries.join(",")).into()
+}
+
+/// 绕过列表中带端口或 IPv6 的条目无法在 PAC 中按主机匹配，予以忽略
+fn render_bypass(bypass: &BypassList) -> String {
+    let mut any = false;
+    let mut local = false;
+    let mut domains = Vec::new();
+    let mut suffixes = Vec::new();
+    let mut patterns = Vec::new();
+    let mut cidrs = Vec::new();
+    for entry in bypass.entries.iter().filter(|entry| entry.port.is_none()) {
+        match &entry.host {
+            BypassHost::Any => any = true,
+            BypassHost::Local => local = true,
+            BypassHost::Domain(domain) => domains.push(format!("{}:1", js_string(domain))),
+            BypassHost::Suffix(domain) => suffixes.push(format!("{}:1", js_string(domain))),
+            BypassHost::Pattern(pattern) => {
+                patterns.push(format!("/^{}$/", pattern.replace('.', "\\.").replace('*', ".*")));
+            }
+            BypassHost::Cidr(IpAddr::V4(ip), prefix) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(*prefix)).unwrap_or(0);
+                cidrs.push(format!("[{},{mask}]", u32::from(*ip) & mask));
+            }
+            BypassHost::Cidr(IpAddr::V6(_), _) | BypassHost::Special(_) => {}
+        }
+    }
+
+    let mut script = String::new();
+    let _ = writeln!(script, "var BYPASS_ANY = {any};");
+    let _ = writeln!(script, "var BYPASS_LOCAL = {local};");
+    let _ = writeln!(script, "var BYPASS_DOMAINS = {{{}}};", domains.join(","));
+    let _ = writeln!(script, "var BYPASS_SUFFIXES = {{{}}};", suffixes.join(","));
+    let _ = writeln!(script, "var BYPASS_PATTERNS = [{}];", patterns.join(","));
+    let _ = writeln!(script, "var BYPASS_CIDRS = [{}];", cidrs.join(","));
+    script
+}
+
+fn render(compiled: &CompiledRules, proxy: &str, bypass: &BypassList, report: &PacReport) -> String {
+    let mut script = String::new();
+    let _ = writeln!(
+        script,
+        "// Generated by Clash Verge from {} of {} rules, mode: {}",
+        report.compiled, report.total, report.mode
+    );
+    if report.truncated > 0 {
+        let _ = writeln!(
+            script,
+            "// Rule limit {} reached, {} rules dropped",
+            report.limit, report.truncated
+        );
+    }
+    for (kind, count) in &report.unsupported {
+        let _ = writeln!(script, "// Skipped {count} unsupported {kind} rules");
+    }
+
+    let (fallback_index, fallback_target) = compiled.fallback.unwrap_or((usize::MAX, Target::Direct));
+    let fallback_index = fallback_index.min(report.total);
+    let keywords = compiled
+        .keywords
+        .iter()
+        .map(|(keyword, index, target)| format!("[{},{index},{}]", js_string(keyword), target.code()))
+        .collect::<Vec<_>>();
+    let cidrs = compiled
+        .cidrs
+        .iter()
+        .map(|(network, mask, index, target)| format!("[{network},{mask},{index},{}]", target.code()))
+        .collect::<Vec<_>>();
+
+    let _ = writeln!(script, "var PROXY = {};", js_string(proxy));
+    script.push_str(&render_table("DOMAINS", &compiled.domains));
+    script.push_str(&render_table("SUFFIXES", &compiled.suffixes));
+    let _ = writeln!(script, "var KEYWORDS = [{}];", keywords.join(","));
+    let _ = writeln!(script, "var CIDRS = [{}];", cidrs.join(","));
+    let _ = writeln!(script, "var FALLBACK = [{fallback_index},{}];", fallback_target.code());
+    script.push_str(&render_bypass(bypass));
+    script.push_str(PAC_LOOKUP);
+    script
+}
+
+/// 按规则序号取最先命中的规则；KEYWORDS 与 CIDRS 按序号升序排列，可提前结束
+const PAC_LOOKUP: &str = r#"
+function pacLookup(table, key) {
+  return Object.prototype.hasOwnProperty.call(table, key) ? table[key] : null;
+}
+
+function pacParseIPv4(host) {
+  var parts = host.split(".");
+  if (parts.length !== 4) return -1;
+  var ip = 0;
+  for (var i = 0; i < 4; i++) {
+    if (!/^\d{1,3}$/.test(parts[i])) return -1;
+    var octet = parseInt(parts[i], 10);
+    if (octet > 255) return -1;
+    ip = ip * 256 + octet;
+  }
+  return ip;
+}
+
+function pacBypass(host) {
+  if (BYPASS_ANY) return true;
+  if (BYPASS_LOCAL && host.indexOf(".") < 0 && host.indexOf(":") < 0) return true;
+  if (pacLookup(BYPASS_DOMAINS, host)) return true;
+  for (var dot = host.indexOf("."); dot >= 0; dot = host.indexOf(".", dot + 1)) {
+    if (pacLookup(BYPASS_SUFFIXES, host.substring(dot + 1))) return true;
+  }
+  for (var i = 0; i < BYPASS_PATTERNS.length; i++) {
+    if (BYPASS_PATTERNS[i].test(host)) return true;
+  }
+  var ip = pacParseIPv4(host);
+  if (ip >= 0) {
+    for (var j = 0; j < BYPASS_CIDRS.length; j++) {
+      if (((ip & BYPASS_CIDRS[j][1]) >>> 0) === BYPASS_CIDRS[j][0]) return true;
+    }
+  }
+  return false;
+}
+
+function FindProxyForURL(url, host) {
+  host = host.toLowerCase();
+  if (host.charAt(host.length - 1) === ".") host = host.substring(0, host.length - 1);
+  if (pacBypass(host)) return "DIRECT";
+  var best = FALLBACK;
+  var hit = pacLookup(DOMAINS, host);
+  if (hit && hit[0] < best[0]) best = hit;
+  for (var suffix = host; ; ) {
+    hit = pacLookup(SUFFIXES, suffix);
+    if (hit && hit[0] < best[0]) best = hit;
+    var dot = suffix.indexOf(".");
+    if (dot < 0) break;
+    suffix = suffix.substring(dot + 1);
+  }
+  for (var i = 0; i < KEYWORDS.length && KEYWORDS[i][1] < best[0]; i++) {
+    if (host.indexOf(KEYWORDS[i][0]) >= 0) best = [KEYWORDS[i][1], KEYWORDS[i][2]];
+  }
+  var ip = pacParseIPv4(host);
+  if (ip >= 0) {
+    for (var j = 0; j < CIDRS.length && CIDRS[j][2] < best[0]; j++) {
+      if (((ip & CIDRS[j][1]) >>> 0) === CIDRS[j][0]) best = [CIDRS[j][2], CIDRS[j][3]];
+    }
+  }
+  return best[1] ? PROXY : "DIRECT";
+}
+"#;
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{PacReport, compile_pac};
+    use crate::utils::bypass::BypassList;
+    use boa_engine::{Context, Source};
+
+    const PROXY: &str = "PROXY 127.0.0.1:7897; SOCKS5 127.0.0.1:7897; DIRECT";
+
+    fn evaluate(script: &str, host: &str) -> std::string::String {
+        let mut context = Context::default();
+        context.eval(Source::from_bytes(script)).expect("valid PAC script");
+        let call = format!("FindProxyForURL('https://{host}/', '{host}')");
+        context
+            .eval(Source::from_bytes(call.as_str()))
+            .expect("FindProxyForURL result")
+            .to_string(&mut context)
+            .expect("string result")
+            .to_std_string_escaped()
+    }
+
+    fn compile(rules: &[&str], limit: usize) -> (String, PacReport) {
+        let (script, report) = compile_pac(rules, "rule", PROXY, &BypassList::default(), limit);
+        (script.to_string(), report)
+    }
+
+    #[test]
+    fn evaluate_generated_pac() {
+        let rules = [
+            "DOMAIN,direct.google.com,DIRECT",
+            "DOMAIN-SUFFIX,google.com,Proxy",
+            "DOMAIN-KEYWORD,github,🚀 节点选择",
+            "DOMAIN-SUFFIX,cn,DIRECT",
+            "GEOIP,CN,DIRECT",
+            "IP-CIDR,10.0.0.0/8,DIRECT,no-resolve",
+            "IP-CIDR,0.0.0.0/0,Proxy",
+            "DOMAIN-SUFFIX,ads.example,REJECT",
+            "MATCH,Proxy",
+            "DOMAIN,never.example,DIRECT",
+        ];
+        let (script, report) = compile(&rules, 100);
+
+        assert_eq!(evaluate(&script, "direct.google.com"), "DIRECT");
+        assert_eq!(evaluate(&script, "www.Google.com"), PROXY);
+        assert_eq!(evaluate(&script, "google.com"), PROXY);
+        assert_eq!(evaluate(&script, "notgoogle.com"), PROXY);
+        assert_eq!(evaluate(&script, "api.github.cn"), PROXY);
+        assert_eq!(evaluate(&script, "baidu.cn"), "DIRECT");
+        assert_eq!(evaluate(&script, "10.1.2.3"), "DIRECT");
+        assert_eq!(evaluate(&script, "8.8.8.8"), PROXY);
+        assert_eq!(evaluate(&script, "never.example"), PROXY);
+
+        assert_eq!(report.total, 10);
+        assert_eq!(report.compiled, 6);
+        assert_eq!(report.unreachable, 1);
+        assert_eq!(report.unsupported.get("GEOIP"), Some(&1));
+        assert_eq!(report.unsupported.get("DOMAIN-SUFFIX"), Some(&1));
+    }
+
+    #[test]
+    fn cap_rule_count() {
+        let rules = [
+            "DOMAIN-SUFFIX,a.example,DIRECT",
+            "DOMAIN-SUFFIX,b.example,DIRECT",
+            "DOMAIN-SUFFIX,c.example,Proxy",
+        ];
+        let (script, report) = compile(&rules, 2);
+        assert_eq!(report.compiled, 2);
+        assert_eq!(report.truncated, 1);
+        assert!(script.contains("Rule limit 2 reached, 1 rules dropped"));
+
+        // 被丢弃的规则不再生效，没有 MATCH 时默认直连
+        assert_eq!(evaluate(&script, "a.example"), "DIRECT");
+        assert_eq!(evaluate(&script, "c.example"), "DIRECT");
+        assert_eq!(evaluate(&script, "other.example"), "DIRECT");
+    }
+
+    #[test]
+    fn follow_clash_mode() {
+        let rules = ["DOMAIN-SUFFIX,cn,DIRECT", "MATCH,DIRECT"];
+        let bypass = BypassList::default();
+        let (global, _) = compile_pac(&rules, "global", PROXY, &bypass, 100);
+        let (direct, _) = compile_pac(&rules, "direct", PROXY, &bypass, 100);
+        assert_eq!(evaluate(&global, "baidu.cn"), PROXY);
+        assert_eq!(evaluate(&direct, "google.com"), "DIRECT");
+    }
+
+    #[test]
+    fn bypass_entries_go_direct() {
+        let bypass = BypassList::parse("<local>,*.corp.example,192.168.*,*epic*,example.com:8080");
+        let (script, _) = compile_pac(&[], "global", PROXY, &bypass, 100);
+
+        assert_eq!(evaluate(&script, "intranet"), "DIRECT");
+        assert_eq!(evaluate(&script, "git.corp.example"), "DIRECT");
+        assert_eq!(evaluate(&script, "corp.example"), PROXY);
+        assert_eq!(evaluate(&script, "192.168.1.5"), "DIRECT");
+        assert_eq!(evaluate(&script, "store.epicgames.com"), "DIRECT");
+        assert_eq!(evaluate(&script, "example.com"), PROXY);
+    }
+}
@@ -0,0 +1,726 @@
+use crate::{
+    core::{handle::Handle, proxy_watch},
+    process::AsyncHandler,
+    singleton,
+};
+use anyhow::{Result, bail};
+use chrono::Local;
+use clash_verge_logging::{Type, logging};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use smartstring::alias::String;
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use sysproxy::{Autoproxy, Sysproxy};
+use tokio::sync::{mpsc, watch};
+
+const HISTORY_LIMIT: usize = 100;
+/// 两次篡改间隔在此窗口内视为持续对抗
+const MIN_FIGHT_WINDOW: Duration = Duration::from_secs(60);
+const BACKOFF_BASE: Duration = Duration::from_secs(5);
+const BACKOFF_MAX: Duration = Duration::from_secs(600);
+/// 连续对抗达到该次数时提醒用户
+const CONFLICT_STREAK: u32 = 3;
+/// 合并短时间内连续到达的变化通知
+const EVENT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 守卫期望或实际读取到的系统代理设置
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "mode", rename_all = "kebab-case")]
+pub enum ProxySnapshot {
+    Sysproxy {
+        enable: bool,
+        host: String,
+        port: u16,
+        bypass: String,
+    },
+    Autoproxy {
+        enable: bool,
+        url: String,
+    },
+}
+
+impl ProxySnapshot {
+    /// 与 expected 对比，返回发生变化的字段
+    pub fn changed_fields(&self, found: &Self) -> Vec<&'static str> {
+        match (self, found) {
+            (
+                Self::Sysproxy {
+                    enable,
+                    host,
+                    port,
+                    bypass,
+                },
+                Self::Sysproxy {
+                    enable: found_enable,
+                    host: found_host,
+                    port: found_port,
+                    bypass: found_bypass,
+                },
+            ) => {
+                let mut changed = Vec::new();
+                if enable != found_enable {
+                    changed.push("enable");
+                }
+                if !host.eq_ignore_ascii_case(found_host) {
+                    changed.push("host");
+                }
+                if port != found_port {
+                    changed.push("port");
+                }
+                if bypass_entries(bypass) != bypass_entries(found_bypass) {
+                    changed.push("bypass");
+                }
+                changed
+            }
+            (
+                Self::Autoproxy { enable, url },
+                Self::Autoproxy {
+                    enable: found_enable,
+                    url: found_url,
+                },
+            ) => {
+                let mut changed = Vec::new();
+                if enable != found_enable {
+                    changed.push("enable");
+                }
+                if url != found_url {
+                    changed.push("url");
+                }
+                changed
+            }
+            _ => vec!["mode"],
+        }
+    }
+
+    const fn is_pac(&self) -> bool {
+        matches!(self, Self::Autoproxy { .. })
+    }
+}
+
+impl From<&Sysproxy> for ProxySnapshot {
+    fn from(sys: &Sysproxy) -> Self {
+        Self::Sysproxy {
+            enable: sys.enable,
+            host: sys.host.as_str().into(),
+            port: sys.port,
+            bypass: sys.bypass.as_str().into(),
+        }
+    }
+}
+
+impl From<&Autoproxy> for ProxySnapshot {
+    fn from(auto: &Autoproxy) -> Self {
+        Self::Autoproxy {
+            enable: auto.enable,
+            url: auto.url.as_str().into(),
+        }
+    }
+}
+
+/// 系统会对绕过列表重新排序或改写分隔符，按集合比较
+fn bypass_entries(bypass: &str) -> Vec<std::string::String> {
+    let mut entries: Vec<_> = bypass
+        .split([',', ';', '\n'])
+        .map(|item| item.trim().to_ascii_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect();
+    entries.sort_unstable();
+    entries.dedup();
+    entries
+}
+
+/// 读写系统代理的后端，测试中以模拟实现替换
+pub trait ProxyBackend {
+    /// 读取与 expected 同类型的当前设置
+    fn read(&self, expected: &ProxySnapshot) -> Result<ProxySnapshot>;
+    fn apply(&self, expected: &ProxySnapshot) -> Result<()>;
+}
+
+/// 通过 sysproxy 操作真实的系统设置
+pub struct SystemBackend;
+
+impl ProxyBackend for SystemBackend {
+    fn read(&self, expected: &ProxySnapshot) -> Result<ProxySnapshot> {
+        Ok(if expected.is_pac() {
+            (&Autoproxy::get_auto_proxy()?).into()
+        } else {
+            (&Sysproxy::get_system_proxy()?).into()
+        })
+    }
+
+    fn apply(&self, expected: &ProxySnapshot) -> Result<()> {
+        match expected {
+            ProxySnapshot::Sysproxy {
+                enable,
+                host,
+                port,
+                bypass,
+            } => Sysproxy {
+                enable: *enable,
+                host: host.as_str().into(),
+                port: *port,
+                bypass: bypass.as_str().into(),
+            }
+            .set_system_proxy()?,
+            ProxySnapshot::Autoproxy { enable, url } => Autoproxy {
+                enable: *enable,
+                url: url.as_str().into(),
+            }
+            .set_auto_proxy()?,
+        }
+        Ok(())
+    }
+}
+
+/// 发现篡改后守卫采取的处理
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GuardAction {
+    /// 已重新应用期望的设置
+    Reapplied,
+    /// 处于退避期，暂不处理
+    Throttled,
+    /// 重新应用失败
+    Failed,
+}
+
+/// 一次检测到的系统代理篡改
+#[derive(Clone, Debug, Serialize)]
+pub struct ProxyDeviation {
+    /// 检测时间戳 (ms)
+    pub timestamp: i64,
+    pub expected: ProxySnapshot,
+    pub found: ProxySnapshot,
+    pub changed: Vec<&'static str>,
+    pub action: GuardAction,
+    /// 当前连续对抗的次数
+    pub streak: u32,
+    pub error: Option<String>,
+}
+
+/// 其他程序反复改回设置时，逐步拉长重新应用的间隔
+#[derive(Debug)]
+struct Backoff {
+    window: Duration,
+    streak: u32,
+    until: Option<Instant>,
+    fight_until: Option<Instant>,
+}
+
+impl Backoff {
+    const fn new(window: Duration) -> Self {
+        Self {
+            window,
+            streak: 0,
+            until: None,
+            fight_until: None,
+        }
+    }
+
+    const fn reset(&mut self, window: Duration) {
+        *self = Self::new(window);
+    }
+
+    /// 允许重新应用时返回连续对抗次数，退避期内返回 None
+    fn try_reapply(&mut self, now: Instant) -> Option<u32> {
+        if self.until.is_some_and(|until| now < until) {
+            return None;
+        }
+        self.streak = if self.fight_until.is_some_and(|until| now <= until) {
+            self.streak + 1
+        } else {
+            1
+        };
+        self.until = (self.streak > 1).then(|| now + Self::delay(self.streak));
+        self.fight_until = Some(self.until.unwrap_or(now) + self.window);
+        Some(self.streak)
+    }
+
+    fn delay(streak: u32) -> Duration {
+        let exp = streak.saturating_sub(2).min(16);
+        BACKOFF_BASE.saturating_mul(1 << exp).min(BACKOFF_MAX)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct GuardSettings {
+    running: bool,
+    interval: Duration,
+    expected: Option<ProxySnapshot>,
+}
+
+impl GuardSettings {
+    fn fight_window(&self) -> Duration {
+        self.interval.saturating_mul(2).max(MIN_FIGHT_WINDOW)
+    }
+}
+
+impl Default for GuardSettings {
+    fn default() -> Self {
+        Self {
+            running: false,
+            interval: Duration::from_secs(30),
+            expected: None,
+        }
+    }
+}
+
+/// 系统代理守卫，检测并记录其他程序对系统代理的修改
+pub struct ProxyGuard {
+    settings_tx: watch::Sender<GuardSettings>,
+    runner_started: AtomicBool,
+    backoff: Mutex<Backoff>,
+    history: RwLock<VecDeque<ProxyDeviation>>,
+}
+
+singleton!(ProxyGuard, PROXY_GUARD);
+
+impl ProxyGuard {
+    fn new() -> Self {
+        let settings = GuardSettings::default();
+        let window = settings.fight_window();
+        let (tx, _rx) = watch::channel(settings);
+        Self {
+            settings_tx: tx,
+            runner_started: AtomicBool::new(false),
+            backoff: Mutex::new(Backoff::new(window)),
+            history: RwLock::new(VecDeque::with_capacity(HISTORY_LIMIT)),
+        }
+    }
+
+    /// 设置期望的系统代理，None 表示不需要守护
+    pub fn set_expected(&self, expected: Option<ProxySnapshot>) {
+        self.update(|settings| settings.expected = expected);
+    }
+
+    pub fn set_interval(&self, interval: Duration) {
+        self.update(|settings| settings.interval = interval.max(Duration::from_secs(1)));
+    }
+
+    pub fn start(&self) {
+        self.update(|settings| settings.running = true);
+        self.ensure_runner();
+    }
+
+    pub fn stop(&self) {
+        self.update(|settings| settings.running = false);
+    }
+
+    /// 最近检测到的篡改记录，按时间先后排列
+    pub fn history(&self) -> Vec<ProxyDeviation> {
+        self.history.read().iter().cloned().collect()
+    }
+
+    pub fn clear_history(&self) {
+        self.history.write().clear();
+    }
+
+    fn update(&self, f: impl FnOnce(&mut GuardSettings)) {
+        let changed = self.settings_tx.send_if_modified(|settings| {
+            let before = settings.clone();
+            f(settings);
+            *settings != before
+        });
+        // 设置变化即是新的期望状态，之前的对抗不再计入
+        if changed {
+            let window = self.settings_tx.borrow().fight_window();
+            self.backoff.lock().reset(window);
+        }
+    }
+
+    fn ensure_runner(&self) {
+        if self.runner_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let mut rx = self.settings_tx.subscribe();
+        AsyncHandler::spawn(move || async move {
+            Self::run_loop(&mut rx).await;
+        });
+    }
+
+    async fn run_loop(rx: &mut watch::Receiver<GuardSettings>) {
+        let mut current = rx.borrow().clone();
+        // 系统变化通知，首次守护时订阅；不可用或中断时为 None，改为轮询
+        let mut events: Option<mpsc::UnboundedReceiver<()>> = None;
+        let mut subscribed = false;
+        // 事件驱动时下一次补查的时间
+        let mut recheck: Option<Instant> = None;
+        loop {
+            let Some(expected) = current.expected.clone().filter(|_| current.running) else {
+                if rx.changed().await.is_err() {
+                    break;
+                }
+                current = rx.borrow().clone();
+                continue;
+            };
+
+            if !subscribed {
+                subscribed = true;
+                events = proxy_watch::watch();
+                // 订阅前的篡改不会产生通知，先检查一次
+                recheck = Self::check(expected.clone(), current.interval).await;
+            }
+
+            // 轮询时按间隔检查；事件驱动时只在退避结束或重新应用失败后补查
+            let deadline = match events {
+                Some(_) => recheck,
+                None => Some(Instant::now() + current.interval),
+            };
+            let sleeper = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now).into());
+            tokio::pin!(sleeper);
+
+            tokio::select! {
+                _ = &mut sleeper, if deadline.is_some() => {
+                    recheck = Self::check(expected, current.interval).await;
+                }
+                event = next_event(&mut events) => {
+                    if event.is_none() {
+                        logging!(warn, Type::Core, "System proxy watcher stopped, falling back to polling");
+                        events = None;
+                        continue;
+                    }
+                    // 一次修改通常触发多条通知，合并后再检查
+                    tokio::time::sleep(EVENT_DEBOUNCE).await;
+                    if let Some(events) = events.as_mut() {
+                        while events.try_recv().is_ok() {}
+                    }
+                    recheck = Self::check(expected, current.interval).await;
+                }
+                changed = rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    current = rx.borrow().clone();
+                }
+            }
+        }
+    }
+
+    /// 检查一次，返回需要再次检查的时间
+    async fn check(expected: ProxySnapshot, interval: Duration) -> Option<Instant> {
+        let deviation =
+            AsyncHandler::spawn_blocking(move || Self::global().inspect(&SystemBackend, &expected, Instant::now()))
+                .await
+                .ok()
+                .flatten()?;
+        Self::report(&deviation);
+        Self::global().recheck_at(&deviation, Instant::now(), interval)
+    }
+
+    /// 暂未处理的篡改不会再产生系统通知，需要主动补查：退避期结束时，或重新应用失败后间隔一段时间
+    fn recheck_at(&self, deviation: &ProxyDeviation, now: Instant, interval: Duration) -> Option<Instant> {
+        match deviation.action {
+            GuardAction::Reapplied => None,
+            GuardAction::Throttled => Some(self.backoff.lock().until.map_or(now, |until| until.max(now))),
+            GuardAction::Failed => Some(now + interval),
+        }
+    }
+
+    /// 对比当前设置与期望，必要时重新应用并记录
+    fn inspect(&self, backend: &dyn ProxyBackend, expected: &ProxySnapshot, now: Instant) -> Option<ProxyDeviation> {
+        let found = match backend.read(expected) {
+            Ok(found) => found,
+            Err(err) => {
+                logging!(warn, Type::Core, "Failed to read system proxy: {err}");
+                return None;
+            }
+        };
+        let changed = expected.changed_fields(&found);
+        if changed.is_empty() {
+            return None;
+        }
+
+        let mut deviation = ProxyDeviation {
+            timestamp: Local::now().timestamp_millis(),
+            expected: expected.clone(),
+            found,
+            changed,
+            action: GuardAction::Throttled,
+            streak: 0,
+            error: None,
+        };
+
+        let streak = self.backoff.lock().try_reapply(now);
+        match streak {
+            Some(streak) => {
+                deviation.streak = streak;
+                deviation.action = match apply_checked(backend, expected) {
+                    Ok(()) => GuardAction::Reapplied,
+                    Err(err) => {
+                        deviation.error = Some(err.to_string().into());
+                        GuardAction::Failed
+                    }
+                };
+            }
+            None => deviation.streak = self.backoff.lock().streak,
+        }
+
+        self.push_record(&deviation);
+        Some(deviation)
+    }
+
+    fn report(deviation: &ProxyDeviation) {
+        let changed = deviation.changed.join(",");
+        match deviation.action {
+            GuardAction::Reapplied => {
+                logging!(
+                    warn,
+                    Type::Core,
+                    "System proxy was modified externally ({changed}), re-applied (streak {})",
+                    deviation.streak
+                );
+                if deviation.streak == CONFLICT_STREAK {
+                    Handle::notice_message("proxy_guard::conflict", changed);
+                }
+            }
+            GuardAction::Throttled => {
+                logging!(
+                    debug,
+                    Type::Core,
+                    "System proxy was modified externally ({changed}), backing off"
+                );
+            }
+            GuardAction::Failed => {
+                logging!(
+                    error,
+                    Type::Core,
+                    "Failed to re-apply system proxy: {}",
+                    deviation.error.as_deref().unwrap_or_default()
+                );
+            }
+        }
+    }
+
+    fn push_record(&self, deviation: &ProxyDeviation) {
+        let mut history = self.history.write();
+        // 退避期间相同的篡改只记录一次
+        if deviation.action == GuardAction::Throttled
+            && history
+                .back()
+                .is_some_and(|last| last.action == GuardAction::Throttled && last.found == deviation.found)
+        {
+            return;
+        }
+        if history.len() >= HISTORY_LIMIT {
+            history.pop_front();
+        }
+        history.push_back(deviation.clone());
+    }
+}
+
+/// 等待下一条系统变化通知，未订阅时永不返回
+async fn next_event(events: &mut Option<mpsc::UnboundedReceiver<()>>) -> Option<()> {
+    match events {
+        Some(events) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 应用后立即回读，确认设置确实生效
+fn apply_checked(backend: &dyn ProxyBackend, expected: &ProxySnapshot) -> Result<()> {
+    backend.apply(expected)?;
+    let found = backend.read(expected)?;
+    let changed = expected.changed_fields(&found);
+    if !changed.is_empty() {
+        bail!("settings did not stick: {}", changed.join(","));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{Backoff, GuardAction, ProxyBackend, ProxyGuard, ProxySnapshot};
+    use anyhow::{Result, bail};
+    use parking_lot::Mutex;
+    use std::time::{Duration, Instant};
+
+    /// 模拟系统代理，可模拟其他程序的篡改或拒绝写入
+    struct MockBackend {
+        current: Mutex<ProxySnapshot>,
+        readonly: bool,
+        applied: Mutex<u32>,
+    }
+
+    impl MockBackend {
+        fn new(current: ProxySnapshot) -> Self {
+            Self {
+                current: Mutex::new(current),
+                readonly: false,
+                applied: Mutex::new(0),
+            }
+        }
+
+        fn tamper(&self, snapshot: ProxySnapshot) {
+            *self.current.lock() = snapshot;
+        }
+    }
+
+    impl ProxyBackend for MockBackend {
+        fn read(&self, _expected: &ProxySnapshot) -> Result<ProxySnapshot> {
+            Ok(self.current.lock().clone())
+        }
+
+        fn apply(&self, expected: &ProxySnapshot) -> Result<()> {
+            if self.readonly {
+                bail!("permission denied");
+            }
+            *self.applied.lock() += 1;
+            *self.current.lock() = expected.clone();
+            Ok(())
+        }
+    }
+
+    fn sys(enable: bool, port: u16, bypass: &str) -> ProxySnapshot {
+        ProxySnapshot::Sysproxy {
+            enable,
+            host: "127.0.0.1".into(),
+            port,
+            bypass: bypass.into(),
+        }
+    }
+
+    #[test]
+    fn reports_changed_fields() {
+        let expected = sys(true, 7897, "localhost,127.0.0.1");
+
+        assert!(
+            expected
+                .changed_fields(&sys(true, 7897, "127.0.0.1; localhost"))
+                .is_empty()
+        );
+        assert_eq!(
+            expected.changed_fields(&sys(false, 8080, "localhost")),
+            vec!["enable", "port", "bypass"]
+        );
+
+        let pac = ProxySnapshot::Autoproxy {
+            enable: true,
+            url: "http://127.0.0.1:33331/commands/pac".into(),
+        };
+        assert_eq!(expected.changed_fields(&pac), vec!["mode"]);
+    }
+
+    #[test]
+    fn stable_settings_are_not_recorded() {
+        let guard = ProxyGuard::new();
+        let expected = sys(true, 7897, "localhost");
+        let backend = MockBackend::new(expected.clone());
+
+        assert!(guard.inspect(&backend, &expected, Instant::now()).is_none());
+        assert!(guard.history().is_empty());
+        assert_eq!(*backend.applied.lock(), 0);
+    }
+
+    #[test]
+    fn records_and_reapplies_deviation() {
+        let guard = ProxyGuard::new();
+        let expected = sys(true, 7897, "localhost");
+        let backend = MockBackend::new(expected.clone());
+
+        backend.tamper(sys(true, 8888, "localhost"));
+        let deviation = guard.inspect(&backend, &expected, Instant::now());
+
+        let deviation = deviation.expect("deviation should be detected");
+        assert_eq!(deviation.action, GuardAction::Reapplied);
+        assert_eq!(deviation.changed, vec!["port"]);
+        assert_eq!(deviation.found, sys(true, 8888, "localhost"));
+        assert_eq!(*backend.current.lock(), expected);
+        assert_eq!(guard.history().len(), 1);
+    }
+
+    #[test]
+    fn backs_off_when_another_program_fights_back() {
+        let guard = ProxyGuard::new();
+        let expected = sys(true, 7897, "localhost");
+        let backend = MockBackend::new(expected.clone());
+        let start = Instant::now();
+
+        let mut actions = Vec::new();
+        for tick in 0..7 {
+            backend.tamper(sys(false, 7897, "localhost"));
+            let now = start + Duration::from_secs(tick * 5);
+            let deviation = guard
+                .inspect(&backend, &expected, now)
+                .expect("deviation should be detected");
+            actions.push(deviation.action);
+        }
+
+        // 第二次对抗起进入 5s、10s... 的退避
+        assert_eq!(
+            actions,
+            vec![
+                GuardAction::Reapplied,
+                GuardAction::Reapplied,
+                GuardAction::Reapplied,
+                GuardAction::Throttled,
+                GuardAction::Reapplied,
+                GuardAction::Throttled,
+                GuardAction::Throttled,
+            ]
+        );
+        assert_eq!(*backend.applied.lock(), 4);
+        // 相同的退避记录不重复保存
+        assert_eq!(guard.history().len(), 6);
+    }
+
+    #[test]
+    fn rechecks_when_backoff_expires() {
+        let guard = ProxyGuard::new();
+        let expected = sys(true, 7897, "localhost");
+        let backend = MockBackend::new(expected.clone());
+        let start = Instant::now();
+        let interval = Duration::from_secs(30);
+
+        let mut recheck = Vec::new();
+        for tick in 0..3 {
+            backend.tamper(sys(false, 7897, "localhost"));
+            let now = start + Duration::from_secs(tick);
+            let deviation = guard
+                .inspect(&backend, &expected, now)
+                .expect("deviation should be detected");
+            recheck.push((deviation.action, guard.recheck_at(&deviation, now, interval)));
+        }
+
+        // 第二次重新应用后进入 5s 退避，期间的篡改在退避结束时补查
+        let until = start + Duration::from_secs(1) + Duration::from_secs(5);
+        assert_eq!(
+            recheck,
+            vec![
+                (GuardAction::Reapplied, None),
+                (GuardAction::Reapplied, None),
+                (GuardAction::Throttled, Some(until)),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_failed_reapply() {
+        let guard = ProxyGuard::new();
+        let expected = sys(true, 7897, "localhost");
+        let mut backend = MockBackend::new(sys(false, 7897, "localhost"));
+        backend.readonly = true;
+
+        let deviation = guard.inspect(&backend, &expected, Instant::now());
+
+        let deviation = deviation.expect("deviation should be detected");
+        assert_eq!(deviation.action, GuardAction::Failed);
+        assert!(deviation.error.is_some());
+    }
+
+    #[test]
+    fn fight_ends_after_quiet_window() {
+        let mut backoff = Backoff::new(Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert_eq!(backoff.try_reapply(start), Some(1));
+        assert_eq!(backoff.try_reapply(start + Duration::from_secs(10)), Some(2));
+        assert_eq!(backoff.try_reapply(start + Duration::from_secs(12)), None);
+        assert_eq!(backoff.try_reapply(start + Duration::from_secs(200)), Some(1));
+    }
+}
@@ -0,0 +1,174 @@
+//! 订阅系统代理设置的变化通知，供系统代理守卫即时检测篡改
+//!
+//! - Windows：`RegNotifyChangeKeyValue` 监听 `Internet Settings`
+//! - macOS：`SCDynamicStore` 监听全局与各网络服务的代理配置
+//! - Linux：`dconf watch /system/proxy/`，KDE 的代理设置不在 dconf 中，仍使用轮询
+
+use anyhow::Result;
+use clash_verge_logging::{Type, logging};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// 开始监听系统代理设置，每次变化发送一次通知；平台不支持或订阅失败时返回 None。
+/// 监听线程意外退出时通道随之关闭，调用方应回退到轮询
+pub fn watch() -> Option<UnboundedReceiver<()>> {
+    let (tx, rx) = unbounded_channel();
+    match spawn(tx) {
+        Ok(()) => {
+            logging!(info, Type::Core, "Watching system proxy changes");
+            Some(rx)
+        }
+        Err(err) => {
+            logging!(
+                info,
+                Type::Core,
+                "System proxy change notifications unavailable, polling instead: {err}"
+            );
+            None
+        }
+    }
+}
+
+#[cfg(windows)]
+fn spawn(tx: UnboundedSender<()>) -> Result<()> {
+    use windows::{
+        Win32::System::Registry::{
+            HKEY, HKEY_CURRENT_USER, KEY_NOTIFY, REG_NOTIFY_CHANGE_LAST_SET, REG_NOTIFY_CHANGE_NAME, RegCloseKey,
+            RegNotifyChangeKeyValue, RegOpenKeyExW,
+        },
+        core::w,
+    };
+
+    std::thread::Builder::new().name("proxy-watch".into()).spawn(move || {
+        let mut key = HKEY::default();
+        let opened = unsafe {
+            RegOpenKeyExW(
+                HKEY_CURRENT_USER,
+                w!(r"Software\Microsoft\Windows\CurrentVersion\Internet Settings"),
+                None,
+                KEY_NOTIFY,
+                &mut key,
+            )
+        };
+        if let Err(err) = opened.ok() {
+            logging!(warn, Type::Core, "Failed to open Internet Settings for watching: {err}");
+            return;
+        }
+
+        loop {
+            // 同步等待该键及子键的值变化
+            let result = unsafe {
+                RegNotifyChangeKeyValue(
+                    key,
+                    true,
+                    REG_NOTIFY_CHANGE_NAME | REG_NOTIFY_CHANGE_LAST_SET,
+                    None,
+                    false,
+                )
+            };
+            if let Err(err) = result.ok() {
+                logging!(warn, Type::Core, "Stopped watching system proxy changes: {err}");
+                break;
+            }
+            if tx.send(()).is_err() {
+                break;
+            }
+        }
+        let _ = unsafe { RegCloseKey(key) };
+    })?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn spawn(tx: UnboundedSender<()>) -> Result<()> {
+    use core_foundation::{
+        array::CFArray,
+        runloop::{CFRunLoop, kCFRunLoopCommonModes},
+        string::CFString,
+    };
+    use system_configuration::dynamic_store::{SCDynamicStore, SCDynamicStoreBuilder, SCDynamicStoreCallBackContext};
+
+    fn on_change(_store: SCDynamicStore, _changed: CFArray<CFString>, tx: &mut UnboundedSender<()>) {
+        // 接收端已关闭，结束监听线程
+        if tx.send(()).is_err() {
+            CFRunLoop::get_current().stop();
+        }
+    }
+
+    std::thread::Builder::new().name("proxy-watch".into()).spawn(move || {
+        let context = SCDynamicStoreCallBackContext {
+            callout: on_change,
+            info: tx,
+        };
+        let Some(store) = SCDynamicStoreBuilder::new("clash-verge-proxy-guard")
+            .callback_context(context)
+            .build()
+        else {
+            logging!(warn, Type::Core, "Failed to create SCDynamicStore for watching");
+            return;
+        };
+
+        let keys = CFArray::from_CFTypes(&[CFString::new("State:/Network/Global/Proxies")]);
+        let patterns = CFArray::from_CFTypes(&[CFString::new("Setup:/Network/Service/[^/]+/Proxies")]);
+        if !store.set_notification_keys(&keys, &patterns) {
+            logging!(warn, Type::Core, "Failed to subscribe to system proxy changes");
+            return;
+        }
+
+        let source = store.create_run_loop_source();
+        CFRunLoop::get_current().add_source(&source, unsafe { kCFRunLoopCommonModes });
+        CFRunLoop::run_current();
+    })?;
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn spawn(tx: UnboundedSender<()>) -> Result<()> {
+    use anyhow::{Context as _, bail};
+    use std::{
+        io::{BufRead as _, BufReader},
+        os::unix::process::CommandExt as _,
+        process::{Command, Stdio},
+    };
+    use tauri_plugin_clash_verge_sysinfo::libc;
+
+    let desktop = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    if desktop.to_ascii_uppercase().contains("KDE") {
+        bail!("KDE proxy settings are not stored in dconf");
+    }
+
+    let mut command = Command::new("dconf");
+    command
+        .args(["watch", "/system/proxy/"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    // 应用退出时一并结束 dconf
+    unsafe {
+        command.pre_exec(|| {
+            libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM);
+            Ok(())
+        });
+    }
+    let mut child = command.spawn().context("failed to run dconf watch")?;
+    let stdout = child.stdout.take().context("dconf watch has no stdout")?;
+
+    std::thread::Builder::new().name("proxy-watch".into()).spawn(move || {
+        // 每次变化依次输出键名、新值与空行，只在键名行通知
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.starts_with('/') && tx.send(()).is_err() {
+                break;
+            }
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+        logging!(warn, Type::Core, "Stopped watching system proxy changes");
+    })?;
+    Ok(())
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn spawn(_tx: UnboundedSender<()>) -> Result<()> {
+    anyhow::bail!("unsupported platform")
+}
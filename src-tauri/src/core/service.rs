@@ -1,6 +1,6 @@
 use crate::{
     config::{Config, IClashTemp},
-    core::{logger::Logger, tray::Tray},
+    core::{logger::Logger, managed_core, service_version, tray::Tray},
     utils::dirs,
 };
 use anyhow::{Context as _, Result, anyhow, bail};
@@ -313,7 +313,7 @@ fn check_output_error(output: &std::process::Output) -> Option<(i32, Cow<'_, str
     Some((code, Cow::Borrowed("Unknown error")))
 }
 
-fn reinstall_service() -> Result<()> {
+pub(super) fn reinstall_service() -> Result<()> {
     logging!(info, Type::Service, "reinstall service");
 
     // 先卸载服务
@@ -347,6 +347,11 @@ pub(super) async fn start_with_existing_service(config_file: &PathBuf) -> Result
     let clash_core = verge_config.latest_arc().get_valid_clash_core();
     drop(verge_config);
 
+    // 服务以 root 运行，不能执行用户可写目录中的受管内核
+    if managed_core::is_managed_core(&clash_core) {
+        bail!("managed core `{clash_core}` cannot be started by the service");
+    }
+
     let bin_ext = if cfg!(windows) { ".exe" } else { "" };
     let bin_path = current_exe()?.with_file_name(format!("{clash_core}{bin_ext}"));
 
@@ -440,6 +445,13 @@ pub async fn wait_and_check_service_available(status: &mut ServiceManager) -> Re
 
 async fn wait_for_service_ipc(status: &mut ServiceManager, reason: &str) -> Result<()> {
     status.0 = ServiceStatus::Unavailable(reason.into());
+    poll_service_ipc().await?;
+    status.0 = ServiceStatus::Ready;
+    Ok(())
+}
+
+/// 轮询直到服务 IPC 可连接，不持有 `SERVICE_MANAGER` 的锁
+pub async fn poll_service_ipc() -> Result<()> {
     let config = ServiceManager::config();
     let mut attempts = 0u32;
     #[allow(unused_assignments)]
@@ -448,10 +460,7 @@ async fn wait_for_service_ipc(status: &mut ServiceManager, reason: &str) -> Resu
     loop {
         if Path::new(clash_verge_service_ipc::IPC_PATH).exists() {
             match clash_verge_service_ipc::connect().await {
-                Ok(_) => {
-                    status.0 = ServiceStatus::Ready;
-                    return Ok(());
-                }
+                Ok(_) => return Ok(()),
                 Err(e) => last_err = e,
             }
         } else {
@@ -497,6 +506,14 @@ impl ServiceManager {
         self.0.clone()
     }
 
+    pub fn set_unavailable(&mut self, reason: &str) {
+        self.0 = ServiceStatus::Unavailable(reason.into());
+    }
+
+    pub fn set_status(&mut self, status: ServiceStatus) {
+        self.0 = status;
+    }
+
     pub async fn refresh(&mut self) -> Result<()> {
         let status = self.check_service_comprehensive().await;
         self.0 = status.clone();
@@ -504,13 +521,12 @@ impl ServiceManager {
         Ok(())
     }
 
-    /// 综合服务状态检查（一次性完成所有检查）
+    /// 综合服务状态检查（一次性完成所有检查），版本不兼容时视为不可用，由升级流程处理
     pub async fn check_service_comprehensive(&self) -> ServiceStatus {
-        if clash_verge_service_ipc::is_reinstall_service_needed().await {
-            ServiceStatus::NeedsReinstall
-        } else {
-            ServiceStatus::Ready
+        if service_version::is_upgrading() {
+            return self.current();
         }
+        ServiceStatus::from_negotiation(&service_version::negotiate().await)
     }
 
     /// 根据服务状态执行相应操作
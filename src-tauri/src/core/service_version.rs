@@ -0,0 +1,394 @@
+use crate::{
+    config::Config,
+    core::{
+        CoreManager,
+        handle::Handle,
+        manager::RunningMode,
+        service::{self, SERVICE_MANAGER, ServiceStatus},
+    },
+    process::AsyncHandler,
+};
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use clash_verge_logging::{Type, logging};
+use parking_lot::RwLock;
+use serde::Serialize;
+use smartstring::alias::String;
+use std::{
+    cmp::Ordering,
+    sync::atomic::{AtomicBool, Ordering as AtomicOrdering},
+};
+
+static LAST_NEGOTIATION: RwLock<Option<Negotiation>> = RwLock::new(None);
+static UPGRADING: AtomicBool = AtomicBool::new(false);
+
+/// 服务的协议版本与构建版本，协议版本取主版本号
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ServiceVersion {
+    pub protocol: u64,
+    pub build: String,
+}
+
+impl ServiceVersion {
+    pub fn parse(version: &str) -> Option<Self> {
+        let build = version.trim().trim_start_matches('v');
+        let protocol = build.split('.').next()?.parse().ok()?;
+        Some(Self {
+            protocol,
+            build: build.into(),
+        })
+    }
+
+    /// 随应用一起发布的服务版本
+    pub fn bundled() -> Self {
+        Self::parse(clash_verge_service_ipc::VERSION).unwrap_or(Self {
+            protocol: 0,
+            build: clash_verge_service_ipc::VERSION.into(),
+        })
+    }
+
+    fn numbers(&self) -> Vec<u64> {
+        self.build
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+}
+
+/// 版本协商结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum Negotiation {
+    Compatible {
+        client: ServiceVersion,
+        service: ServiceVersion,
+    },
+    /// 协议兼容，但服务版本较旧
+    UpgradeAvailable {
+        client: ServiceVersion,
+        service: ServiceVersion,
+    },
+    /// 协议不兼容或无法获取版本，必须升级后才能使用
+    UpgradeRequired {
+        client: ServiceVersion,
+        service: Option<ServiceVersion>,
+        reason: String,
+    },
+    Unreachable {
+        reason: String,
+    },
+}
+
+impl Negotiation {
+    pub fn compare(client: ServiceVersion, service: ServiceVersion) -> Self {
+        if client.protocol != service.protocol {
+            let reason = format!(
+                "service protocol v{} is incompatible with v{}",
+                service.protocol, client.protocol
+            )
+            .into();
+            return Self::UpgradeRequired {
+                client,
+                service: Some(service),
+                reason,
+            };
+        }
+        match service.numbers().cmp(&client.numbers()) {
+            Ordering::Less => Self::UpgradeAvailable { client, service },
+            Ordering::Equal | Ordering::Greater => Self::Compatible { client, service },
+        }
+    }
+
+    /// 服务当前是否可以直接使用
+    pub const fn is_usable(&self) -> bool {
+        matches!(self, Self::Compatible { .. } | Self::UpgradeAvailable { .. })
+    }
+
+    pub const fn needs_upgrade(&self) -> bool {
+        matches!(self, Self::UpgradeAvailable { .. } | Self::UpgradeRequired { .. })
+    }
+}
+
+/// 与服务交换版本所需的 IPC 接口
+#[async_trait]
+pub trait ServiceIpc: Send + Sync {
+    async fn connect(&self) -> Result<()>;
+    async fn version(&self) -> Result<String>;
+}
+
+pub struct ServiceIpcClient;
+
+#[async_trait]
+impl ServiceIpc for ServiceIpcClient {
+    async fn connect(&self) -> Result<()> {
+        clash_verge_service_ipc::connect().await?;
+        Ok(())
+    }
+
+    async fn version(&self) -> Result<String> {
+        let response = clash_verge_service_ipc::get_version().await?;
+        if response.code > 0 {
+            bail!(response.message);
+        }
+        Ok(response.data.unwrap_or_default().as_str().into())
+    }
+}
+
+pub async fn negotiate_with(ipc: &dyn ServiceIpc, client: ServiceVersion) -> Negotiation {
+    if let Err(err) = ipc.connect().await {
+        return Negotiation::Unreachable {
+            reason: err.to_string().into(),
+        };
+    }
+
+    // 旧版服务没有版本接口，同样需要升级
+    let reported = match ipc.version().await {
+        Ok(version) => version,
+        Err(err) => {
+            return Negotiation::UpgradeRequired {
+                client,
+                service: None,
+                reason: format!("failed to query service version: {err}").into(),
+            };
+        }
+    };
+    match ServiceVersion::parse(&reported) {
+        Some(service) => Negotiation::compare(client, service),
+        None => Negotiation::UpgradeRequired {
+            client,
+            service: None,
+            reason: format!("invalid service version `{reported}`").into(),
+        },
+    }
+}
+
+/// 与已安装的服务协商版本并记录结果
+pub async fn negotiate() -> Negotiation {
+    let negotiation = negotiate_with(&ServiceIpcClient, ServiceVersion::bundled()).await;
+    logging!(info, Type::Service, "Service version negotiation: {negotiation:?}");
+    *LAST_NEGOTIATION.write() = Some(negotiation.clone());
+    negotiation
+}
+
+pub fn last_negotiation() -> Option<Negotiation> {
+    LAST_NEGOTIATION.read().clone()
+}
+
+/// 启动时根据协商结果自动升级服务，或提示用户升级
+pub async fn handle_negotiation(negotiation: &Negotiation) {
+    if !negotiation.needs_upgrade() {
+        return;
+    }
+
+    let setting = Config::verge().await.latest_arc().auto_upgrade_service;
+    if !should_auto_upgrade(negotiation, setting) {
+        Handle::notice_message("service_upgrade::available", describe(negotiation));
+        return;
+    }
+
+    if UPGRADING.swap(true, AtomicOrdering::SeqCst) {
+        return;
+    }
+    // 升级期间先以 Sidecar 模式启动内核
+    SERVICE_MANAGER
+        .lock()
+        .await
+        .set_unavailable("service upgrade in progress");
+    AsyncHandler::spawn(|| async {
+        if let Err(err) = run_upgrade().await {
+            logging!(error, Type::Service, "Service upgrade failed: {err}");
+        }
+    });
+}
+
+/// 不兼容的服务默认自动升级，仅有新版本时需要用户开启
+const fn should_auto_upgrade(negotiation: &Negotiation, setting: Option<bool>) -> bool {
+    match (negotiation, setting) {
+        (_, Some(false)) => false,
+        (Negotiation::UpgradeRequired { .. }, None) => true,
+        (_, setting) => matches!(setting, Some(true)),
+    }
+}
+
+fn describe(negotiation: &Negotiation) -> String {
+    match negotiation {
+        Negotiation::UpgradeAvailable { client, service } => {
+            format!("service {} -> {}", service.build, client.build).into()
+        }
+        Negotiation::UpgradeRequired { client, reason, .. } => format!("{reason}, bundled {}", client.build).into(),
+        Negotiation::Compatible { service, .. } => service.build.clone(),
+        Negotiation::Unreachable { reason } => reason.clone(),
+    }
+}
+
+/// 原地升级服务，期间内核以 Sidecar 模式运行，TUN 设置保持不变，升级完成后切回服务模式
+pub async fn upgrade_service() -> Result<()> {
+    if UPGRADING.swap(true, AtomicOrdering::SeqCst) {
+        bail!("service upgrade is already in progress");
+    }
+    run_upgrade().await
+}
+
+pub fn is_upgrading() -> bool {
+    UPGRADING.load(AtomicOrdering::SeqCst)
+}
+
+async fn run_upgrade() -> Result<()> {
+    scopeguard::defer! {
+        UPGRADING.store(false, AtomicOrdering::SeqCst);
+    }
+
+    let manager = CoreManager::global();
+    logging!(info, Type::Service, "Upgrading service in place");
+    Handle::notice_message(
+        "service_upgrade::started",
+        "Upgrading service, running core in sidecar mode meanwhile",
+    );
+
+    SERVICE_MANAGER
+        .lock()
+        .await
+        .set_unavailable("service upgrade in progress");
+    if *manager.get_running_mode() == RunningMode::Service {
+        manager.graceful_restart_core().await?;
+    }
+
+    let result = reinstall_and_verify().await;
+    match &result {
+        Ok(()) => {
+            // 服务就绪后重启会重新选择服务模式，TUN 随之恢复
+            if *manager.get_running_mode() == RunningMode::Sidecar {
+                manager.graceful_restart_core().await?;
+            }
+            logging!(info, Type::Service, "Service upgrade completed");
+            Handle::notice_message("service_upgrade::completed", "Service upgraded");
+        }
+        Err(err) => {
+            Handle::notice_message("service_upgrade::failed", format!("{err}"));
+        }
+    }
+    result
+}
+
+async fn reinstall_and_verify() -> Result<()> {
+    tokio::task::spawn_blocking(service::reinstall_service).await??;
+    // 重试期间不持有锁，状态检查与运行模式切换不会被阻塞，服务仍标记为升级中
+    service::poll_service_ipc().await?;
+
+    let negotiation = negotiate().await;
+    SERVICE_MANAGER
+        .lock()
+        .await
+        .set_status(ServiceStatus::from_negotiation(&negotiation));
+    if !negotiation.is_usable() {
+        bail!(
+            "service is still incompatible after upgrade: {}",
+            describe(&negotiation)
+        );
+    }
+    Ok(())
+}
+
+impl ServiceStatus {
+    /// 协商结果对应的服务状态
+    pub fn from_negotiation(negotiation: &Negotiation) -> Self {
+        match negotiation {
+            Negotiation::Compatible { .. } | Negotiation::UpgradeAvailable { .. } => Self::Ready,
+            Negotiation::UpgradeRequired { .. } => Self::Unavailable(describe(negotiation).into()),
+            Negotiation::Unreachable { reason } => Self::Unavailable(reason.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{Negotiation, ServiceIpc, ServiceStatus, ServiceVersion, negotiate_with, should_auto_upgrade};
+    use anyhow::{Result, bail};
+    use async_trait::async_trait;
+    use smartstring::alias::String;
+
+    /// 模拟服务端的 IPC
+    struct MockIpc {
+        reachable: bool,
+        version: Option<&'static str>,
+    }
+
+    #[async_trait]
+    impl ServiceIpc for MockIpc {
+        async fn connect(&self) -> Result<()> {
+            if !self.reachable {
+                bail!("connection refused");
+            }
+            Ok(())
+        }
+
+        async fn version(&self) -> Result<String> {
+            match self.version {
+                Some(version) => Ok(version.into()),
+                None => bail!("unknown command"),
+            }
+        }
+    }
+
+    fn version(build: &str) -> Option<ServiceVersion> {
+        ServiceVersion::parse(build)
+    }
+
+    #[tokio::test]
+    async fn compatibility_matrix() {
+        let client = || version("2.1.3").expect("client version");
+        let cases: &[(bool, Option<&'static str>, &str, bool)] = &[
+            (true, Some("2.1.3"), "compatible", true),
+            (true, Some("v2.2.0"), "compatible", true),
+            (true, Some("2.1.2"), "upgrade-available", true),
+            (true, Some("2.0.9-beta"), "upgrade-available", true),
+            (true, Some("1.9.0"), "upgrade-required", false),
+            (true, Some("3.0.0"), "upgrade-required", false),
+            (true, Some("nightly"), "upgrade-required", false),
+            (true, None, "upgrade-required", false),
+            (false, Some("2.1.3"), "unreachable", false),
+        ];
+
+        for &(reachable, reported, expected, usable) in cases {
+            let negotiation = negotiate_with(
+                &MockIpc {
+                    reachable,
+                    version: reported,
+                },
+                client(),
+            )
+            .await;
+            let status = match &negotiation {
+                Negotiation::Compatible { .. } => "compatible",
+                Negotiation::UpgradeAvailable { .. } => "upgrade-available",
+                Negotiation::UpgradeRequired { .. } => "upgrade-required",
+                Negotiation::Unreachable { .. } => "unreachable",
+            };
+            assert_eq!(status, expected, "service {reported:?}, reachable {reachable}");
+            assert_eq!(negotiation.is_usable(), usable, "service {reported:?}");
+            assert_eq!(
+                ServiceStatus::from_negotiation(&negotiation) == ServiceStatus::Ready,
+                usable
+            );
+        }
+    }
+
+    #[test]
+    fn auto_upgrade_only_incompatible_by_default() {
+        let client = || version("2.1.3").expect("client version");
+        let available = Negotiation::compare(client(), version("2.1.2").expect("service version"));
+        let required = Negotiation::compare(client(), version("1.9.0").expect("service version"));
+
+        assert!(should_auto_upgrade(&required, None));
+        assert!(should_auto_upgrade(&required, Some(true)));
+        assert!(!should_auto_upgrade(&required, Some(false)));
+
+        assert!(!should_auto_upgrade(&available, None));
+        assert!(should_auto_upgrade(&available, Some(true)));
+        assert!(!should_auto_upgrade(&available, Some(false)));
+    }
+}
@@ -1,10 +1,16 @@
 use crate::{
     config::{Config, IVerge},
+    core::{
+        CoreManager,
+        handle::Handle,
+        proxy_guard::{ProxyGuard, ProxySnapshot},
+    },
     singleton,
+    utils::bypass::{BypassFormat, BypassList, BypassPreset},
 };
 use anyhow::Result;
 use clash_verge_logging::{Type, logging};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use scopeguard::defer;
 use smartstring::alias::String;
 use std::{
@@ -14,13 +20,14 @@ use std::{
     },
     time::Duration,
 };
-use sysproxy::{Autoproxy, GuardMonitor, GuardType, Sysproxy};
+use sysproxy::{Autoproxy, Sysproxy};
 
 pub struct Sysopt {
     update_sysproxy: AtomicBool,
     reset_sysproxy: AtomicBool,
     inner_proxy: Arc<RwLock<(Sysproxy, Autoproxy)>>,
-    guard: Arc<RwLock<GuardMonitor>>,
+    /// 上次提醒过的无效绕过条目，相同内容不重复提醒
+    ignored_bypass: Mutex<String>,
 }
 
 impl Default for Sysopt {
@@ -29,7 +36,7 @@ impl Default for Sysopt {
             update_sysproxy: AtomicBool::new(false),
             reset_sysproxy: AtomicBool::new(false),
             inner_proxy: Arc::new(RwLock::new((Sysproxy::default(), Autoproxy::default()))),
-            guard: Arc::new(RwLock::new(GuardMonitor::new(GuardType::None, Duration::from_secs(30)))),
+            ignored_bypass: Mutex::new(String::new()),
         }
     }
 }
@@ -42,25 +49,50 @@ static DEFAULT_BYPASS: &str = "localhost,127.0.0.1,192.168.0.0/16,10.0.0.0/8,172
 static DEFAULT_BYPASS: &str =
     "127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,localhost,*.local,*.crashlytics.com,<local>";
 
-async fn get_bypass() -> String {
-    let use_default = Config::verge().await.latest_arc().use_default_bypass.unwrap_or(true);
-    let res = {
-        let verge = Config::verge().await;
-        let verge = verge.latest_arc();
-        verge.system_proxy_bypass.clone()
-    };
-    let custom_bypass = match res {
-        Some(bypass) => bypass,
-        None => "".into(),
-    };
+/// 默认绕过、预设与自定义绕过合并后的列表，同时用于系统代理、PAC 与 `NO_PROXY`
+pub(crate) async fn bypass_list() -> BypassList {
+    let verge = Config::verge().await.latest_arc();
+    let use_default = verge.use_default_bypass.unwrap_or(true);
+    let custom_bypass = verge.system_proxy_bypass.clone().unwrap_or_default();
+
+    let mut list = BypassList::default();
+    if custom_bypass.trim().is_empty() || use_default {
+        list.merge(BypassList::parse(DEFAULT_BYPASS));
+    }
+    for preset in verge
+        .system_proxy_bypass_presets
+        .iter()
+        .flatten()
+        .filter_map(|name| BypassPreset::parse(name))
+    {
+        list.merge(preset.list());
+    }
+    list.merge(BypassList::parse(&custom_bypass));
 
-    if custom_bypass.is_empty() {
-        DEFAULT_BYPASS.into()
-    } else if use_default {
-        format!("{DEFAULT_BYPASS},{custom_bypass}").into()
-    } else {
-        custom_bypass
+    if !list.errors.is_empty() {
+        let invalid = list.errors.iter().map(|error| error.entry.as_str()).collect::<Vec<_>>();
+        logging!(
+            warn,
+            Type::Core,
+            "Ignoring invalid bypass entries: {}",
+            invalid.join(", ")
+        );
     }
+    list
+}
+
+/// 系统代理与 PAC 指向的地址，连接外部内核时使用其代理端口
+pub(crate) async fn proxy_endpoint() -> (String, u16) {
+    if let Some(endpoint) = CoreManager::global().external_proxy() {
+        return endpoint;
+    }
+    let verge = Config::verge().await.latest_arc();
+    let host = verge.proxy_host.clone().unwrap_or_else(|| String::from("127.0.0.1"));
+    let port = match verge.verge_mixed_port {
+        Some(port) => port,
+        None => Config::clash().await.latest_arc().get_mixed_port(),
+    };
+    (host, port)
 }
 
 singleton!(Sysopt, SYSOPT);
@@ -70,8 +102,24 @@ impl Sysopt {
         Self::default()
     }
 
-    fn access_guard(&self) -> Arc<RwLock<GuardMonitor>> {
-        Arc::clone(&self.guard)
+    /// 生成系统代理的绕过列表，并提醒用户被忽略的无效条目
+    async fn get_bypass(&self) -> String {
+        let list = bypass_list().await;
+        let ignored: String = list
+            .errors
+            .iter()
+            .map(|error| error.entry.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+            .into();
+        let mut last = self.ignored_bypass.lock();
+        if *last != ignored {
+            if !ignored.is_empty() {
+                Handle::notice_message("sysproxy::bypass_ignored", ignored.as_str());
+            }
+            *last = ignored;
+        }
+        list.render(BypassFormat::system())
     }
 
     pub async fn refresh_guard(&self) {
@@ -79,7 +127,7 @@ impl Sysopt {
         let verge = Config::verge().await.latest_arc();
         if !verge.enable_system_proxy.unwrap_or_default() {
             logging!(info, Type::Core, "System proxy is disabled.");
-            self.access_guard().write().stop();
+            ProxyGuard::global().stop();
             return;
         }
         if !verge.enable_proxy_guard.unwrap_or_default() {
@@ -92,17 +140,10 @@ impl Sysopt {
             "Updating system proxy with duration: {} seconds",
             verge.proxy_guard_duration.unwrap_or(30)
         );
-        {
-            let guard = self.access_guard();
-            guard
-                .write()
-                .set_interval(Duration::from_secs(verge.proxy_guard_duration.unwrap_or(30)));
-        }
+        let guard = ProxyGuard::global();
+        guard.set_interval(Duration::from_secs(verge.proxy_guard_duration.unwrap_or(30)));
         logging!(info, Type::Core, "Starting system proxy guard...");
-        {
-            let guard = self.access_guard();
-            guard.write().start();
-        }
+        guard.start();
     }
 
     /// init the sysproxy
@@ -124,27 +165,22 @@ impl Sysopt {
             self.update_sysproxy.store(false, Ordering::Release);
         }
 
-        let verge = Config::verge().await.latest_arc();
-        let port = {
-            let verge_port = verge.verge_mixed_port;
-            match verge_port {
-                Some(port) => port,
-                None => Config::clash().await.latest_arc().get_mixed_port(),
-            }
-        };
+        let (proxy_host, port) = proxy_endpoint().await;
         let pac_port = IVerge::get_singleton_port();
 
-        let (sys_enable, pac_enable, proxy_host, proxy_guard) = {
+        let verge = Config::verge().await.latest_arc();
+        let (sys_enable, pac_enable, pac_host, proxy_guard) = {
             (
                 verge.enable_system_proxy.unwrap_or_default(),
                 verge.proxy_auto_config.unwrap_or_default(),
+                // PAC 始终由本机提供
                 verge.proxy_host.clone().unwrap_or_else(|| String::from("127.0.0.1")),
                 verge.enable_proxy_guard.unwrap_or_default(),
             )
         };
 
         // 先 await, 避免持有锁导致的 Send 问题
-        let bypass = get_bypass().await;
+        let bypass = self.get_bypass().await;
 
         let (sys, auto) = &mut *self.inner_proxy.write();
         sys.enable = false;
@@ -153,9 +189,9 @@ impl Sysopt {
         sys.bypass = bypass.into();
 
         auto.enable = false;
-        auto.url = format!("http://{proxy_host}:{pac_port}/commands/pac");
+        auto.url = format!("http://{pac_host}:{pac_port}/commands/pac");
 
-        self.access_guard().write().set_guard_type(GuardType::None);
+        ProxyGuard::global().set_expected(None);
 
         if !sys_enable && !pac_enable {
             // disable proxy
@@ -170,9 +206,7 @@ impl Sysopt {
             sys.set_system_proxy()?;
             auto.set_auto_proxy()?;
             if proxy_guard {
-                self.access_guard()
-                    .write()
-                    .set_guard_type(GuardType::Autoproxy(auto.clone()));
+                ProxyGuard::global().set_expected(Some(ProxySnapshot::from(&*auto)));
             }
             return Ok(());
         }
@@ -183,9 +217,7 @@ impl Sysopt {
             auto.set_auto_proxy()?;
             sys.set_system_proxy()?;
             if proxy_guard {
-                self.access_guard()
-                    .write()
-                    .set_guard_type(GuardType::Sysproxy(sys.clone()));
+                ProxyGuard::global().set_expected(Some(ProxySnapshot::from(&*sys)));
             }
             return Ok(());
         }
@@ -208,7 +240,7 @@ impl Sysopt {
         }
 
         // close proxy guard
-        self.access_guard().write().set_guard_type(GuardType::None);
+        ProxyGuard::global().set_expected(None);
 
         // 直接关闭所有代理
         let (sys, auto) = &mut *self.inner_proxy.write();
@@ -0,0 +1,560 @@
+#![cfg(target_os = "linux")]
+
+use crate::{
+    config::Config,
+    constants::tun as tun_const,
+    core::{managed_core, service},
+    process::AsyncHandler,
+};
+use clash_verge_logging::{Type, logging};
+use serde::Serialize;
+use serde_yaml_ng::Value;
+use smartstring::alias::String;
+use std::{env::current_exe, fs, os::unix::fs::FileTypeExt as _, path::PathBuf, process::Command};
+
+const CAP_NET_ADMIN: u32 = 12;
+const DEFAULT_DEVICE: &str = "Meta";
+const DEFAULT_TABLE_INDEX: u64 = 2022;
+const STANDARD_TABLES: [&str; 6] = ["local", "main", "default", "255", "254", "253"];
+const TUNNEL_PREFIXES: [&str; 8] = ["tun", "tap", "wg", "ppp", "utun", "tailscale", "zt", "nordlynx"];
+/// systemd-resolved 的本地监听地址 127.0.0.53:53 与 127.0.0.54:53
+const RESOLVED_STUBS: [&str; 2] = ["3500007F:0035", "3600007F:0035"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightCheck {
+    pub id: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// 修复建议，通过时为空
+    pub hint: Option<String>,
+}
+
+impl PreflightCheck {
+    fn pass(id: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(id: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(id: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Fail,
+            ..Self::warn(id, detail, hint)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TunPreflightReport {
+    /// 没有失败项时可以开启 TUN
+    pub ready: bool,
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl TunPreflightReport {
+    /// 汇总指定状态的检查项，用于通知
+    pub fn summary(&self, status: CheckStatus) -> String {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .map(|check| check.detail.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+            .into()
+    }
+}
+
+/// 检查所需的原始数据，全部来自 /proc、/etc 与 ip 命令的输出，便于用样例数据测试
+#[derive(Debug, Clone, Default)]
+pub struct PreflightInput {
+    /// /dev/net/tun 是否为字符设备，不存在时为 None
+    pub tun_device: Option<bool>,
+    pub proc_misc: std::string::String,
+    pub service_available: bool,
+    pub self_status: std::string::String,
+    /// 正在运行的内核进程的 /proc/<pid>/status
+    pub core_status: Option<std::string::String>,
+    pub core_path: PathBuf,
+    pub getcap: Option<std::string::String>,
+    /// `ip -o -4 route show default`
+    pub default_routes: std::string::String,
+    /// `ip rule show`
+    pub rules: std::string::String,
+    pub resolv_conf: std::string::String,
+    pub proc_net_udp: std::string::String,
+    pub proc_net_dev: std::string::String,
+    pub device: std::string::String,
+    pub table_index: u64,
+    pub dns_hijack: Vec<std::string::String>,
+    /// TUN 已开启时，已存在的网卡与路由规则属于当前内核
+    pub tun_active: bool,
+}
+
+/// 收集系统状态并执行检查
+pub async fn run() -> TunPreflightReport {
+    let input = collect().await;
+    let report = AsyncHandler::spawn_blocking(move || evaluate(&input.finish()))
+        .await
+        .unwrap_or_else(|err| TunPreflightReport {
+            ready: true,
+            checks: vec![PreflightCheck::warn(
+                "internal",
+                format!("preflight aborted: {err}"),
+                "retry the diagnostics",
+            )],
+        });
+    for check in report.checks.iter().filter(|check| check.status != CheckStatus::Pass) {
+        logging!(warn, Type::Core, "TUN preflight {}: {}", check.id, check.detail);
+    }
+    report
+}
+
+async fn collect() -> PreflightInput {
+    let verge = Config::verge().await.latest_arc();
+    let clash_core = verge.get_valid_clash_core();
+    let tun_active = verge.enable_tun_mode.unwrap_or(false);
+
+    let tun = Config::clash()
+        .await
+        .latest_arc()
+        .0
+        .get("tun")
+        .cloned()
+        .unwrap_or_default();
+    let device = tun
+        .get("device")
+        .and_then(Value::as_str)
+        .filter(|device| !device.is_empty())
+        .unwrap_or(DEFAULT_DEVICE)
+        .to_owned();
+    let table_index = tun
+        .get("iproute2-table-index")
+        .and_then(Value::as_u64)
+        .unwrap_or(DEFAULT_TABLE_INDEX);
+    let dns_hijack = tun
+        .get("dns-hijack")
+        .and_then(Value::as_sequence)
+        .map(|seq| seq.iter().filter_map(Value::as_str).map(ToOwned::to_owned).collect())
+        .unwrap_or_else(|| tun_const::DNS_HIJACK.iter().map(|s| (*s).to_owned()).collect());
+
+    let core_path = match managed_core::managed_core_path(&clash_core) {
+        Ok(Some(path)) => path,
+        _ => current_exe().unwrap_or_default().with_file_name(clash_core.as_str()),
+    };
+
+    PreflightInput {
+        // 受管内核不会交给服务启动
+        service_available: !managed_core::is_managed_core(&clash_core) && service::is_service_available().await.is_ok(),
+        core_path,
+        device,
+        table_index,
+        dns_hijack,
+        tun_active,
+        ..PreflightInput::default()
+    }
+}
+
+impl PreflightInput {
+    /// 读取文件与命令输出，需在阻塞线程中执行
+    fn finish(mut self) -> Self {
+        let read = |path: &str| fs::read_to_string(path).unwrap_or_default();
+        self.tun_device = fs::metadata("/dev/net/tun")
+            .ok()
+            .map(|meta| meta.file_type().is_char_device());
+        self.proc_misc = read("/proc/misc");
+        self.self_status = read("/proc/self/status");
+        self.core_status = self
+            .core_path
+            .file_name()
+            .and_then(|name| find_process_status(&name.to_string_lossy()));
+        self.getcap = command_output("getcap", &[self.core_path.as_os_str()]);
+        self.default_routes = command_output("ip", &["-o", "-4", "route", "show", "default"]).unwrap_or_default();
+        self.rules = command_output("ip", &["rule", "show"]).unwrap_or_default();
+        self.resolv_conf = read("/etc/resolv.conf");
+        self.proc_net_udp = read("/proc/net/udp");
+        self.proc_net_dev = read("/proc/net/dev");
+        self
+    }
+}
+
+fn command_output<S: AsRef<std::ffi::OsStr>>(program: &str, args: &[S]) -> Option<std::string::String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| std::string::String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 按进程名查找内核进程，comm 最长 15 个字符
+fn find_process_status(name: &str) -> Option<std::string::String> {
+    let comm: std::string::String = name.chars().take(15).collect();
+    fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+        let path = entry.path();
+        let matches = fs::read_to_string(path.join("comm")).is_ok_and(|value| value.trim() == comm);
+        matches.then(|| fs::read_to_string(path.join("status")).ok()).flatten()
+    })
+}
+
+pub fn evaluate(input: &PreflightInput) -> TunPreflightReport {
+    let checks = vec![
+        check_tun_device(input),
+        check_privileges(input),
+        check_default_route(input),
+        check_policy_rules(input),
+        check_dns_stub(input),
+        check_interface(input),
+    ];
+    TunPreflightReport {
+        ready: checks.iter().all(|check| check.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+fn check_tun_device(input: &PreflightInput) -> PreflightCheck {
+    const ID: &str = "tun-device";
+    match input.tun_device {
+        Some(true) => PreflightCheck::pass(ID, "/dev/net/tun is available"),
+        Some(false) => PreflightCheck::fail(
+            ID,
+            "/dev/net/tun is not a character device",
+            "sudo rm /dev/net/tun && sudo mknod /dev/net/tun c 10 200",
+        ),
+        None if module_loaded(&input.proc_misc) => PreflightCheck::fail(
+            ID,
+            "/dev/net/tun is missing although the tun module is loaded",
+            "sudo mkdir -p /dev/net && sudo mknod /dev/net/tun c 10 200 && sudo chmod 0666 /dev/net/tun",
+        ),
+        None => PreflightCheck::fail(
+            ID,
+            "/dev/net/tun is missing",
+            "load the module with `sudo modprobe tun`; containers need `--device /dev/net/tun`",
+        ),
+    }
+}
+
+fn module_loaded(proc_misc: &str) -> bool {
+    proc_misc
+        .lines()
+        .any(|line| line.split_whitespace().nth(1) == Some("tun"))
+}
+
+fn check_privileges(input: &PreflightInput) -> PreflightCheck {
+    const ID: &str = "privileges";
+    if input.service_available {
+        return PreflightCheck::pass(ID, "the service runs the core with root privileges");
+    }
+    if status_field(&input.self_status, "Uid:") == Some("0") {
+        return PreflightCheck::pass(ID, "the app runs as root");
+    }
+    if input.core_status.as_deref().is_some_and(has_net_admin) {
+        return PreflightCheck::pass(ID, "the running core has CAP_NET_ADMIN");
+    }
+    if input.getcap.as_deref().is_some_and(file_has_net_admin) {
+        return PreflightCheck::pass(ID, "the core binary has the cap_net_admin file capability");
+    }
+    PreflightCheck::fail(
+        ID,
+        "the core has neither the service nor CAP_NET_ADMIN",
+        format!(
+            "install the service mode, or run `sudo setcap cap_net_admin,cap_net_bind_service=+ep {}`",
+            input.core_path.display()
+        ),
+    )
+}
+
+fn status_field<'a>(status: &'a str, key: &str) -> Option<&'a str> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(key))
+        .and_then(|value| value.split_whitespace().next())
+}
+
+/// 有效 uid 为 0 或 CapEff 含 CAP_NET_ADMIN
+fn has_net_admin(status: &str) -> bool {
+    let root = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|value| value.split_whitespace().nth(1))
+        == Some("0");
+    let cap = status_field(status, "CapEff:")
+        .and_then(|value| u64::from_str_radix(value, 16).ok())
+        .is_some_and(|caps| caps & (1 << CAP_NET_ADMIN) != 0);
+    root || cap
+}
+
+/// 兼容 `cap_net_admin=ep` 与旧版 `= cap_net_admin+ep` 两种输出
+fn file_has_net_admin(getcap: &str) -> bool {
+    getcap.split_whitespace().any(|clause| {
+        let Some((caps, flags)) = clause.split_once(['=', '+']) else {
+            return false;
+        };
+        caps.split(',').any(|cap| cap == "cap_net_admin") && flags.contains('e') && flags.contains('p')
+    })
+}
+
+struct DefaultRoute<'a> {
+    dev: &'a str,
+    metric: u32,
+}
+
+fn parse_default_routes(output: &str) -> Vec<DefaultRoute<'_>> {
+    output
+        .lines()
+        .filter(|line| line.starts_with("default"))
+        .filter_map(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let value = |key: &str| words.iter().position(|w| *w == key).and_then(|i| words.get(i + 1));
+            Some(DefaultRoute {
+                dev: value("dev")?,
+                metric: value("metric").and_then(|m| m.parse().ok()).unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+fn check_default_route(input: &PreflightInput) -> PreflightCheck {
+    const ID: &str = "default-route";
+    let routes = parse_default_routes(&input.default_routes);
+    if routes.is_empty() {
+        return PreflightCheck::warn(
+            ID,
+            "no IPv4 default route found",
+            "connect to a network first, auto-route needs an outbound interface",
+        );
+    }
+
+    let foreign = routes
+        .iter()
+        .find(|route| route.dev != input.device && TUNNEL_PREFIXES.iter().any(|prefix| route.dev.starts_with(prefix)));
+    if let Some(route) = foreign {
+        return PreflightCheck::warn(
+            ID,
+            format!("the default route goes through tunnel `{}`", route.dev),
+            "disconnect the other VPN, or exclude its routes with `route-exclude-address`",
+        );
+    }
+
+    let mut metrics: Vec<u32> = routes.iter().map(|route| route.metric).collect();
+    metrics.sort_unstable();
+    if let Some(metric) = metrics.windows(2).find(|pair| pair[0] == pair[1]).map(|pair| pair[0]) {
+        let devs: Vec<&str> = routes.iter().filter(|r| r.metric == metric).map(|r| r.dev).collect();
+        return PreflightCheck::warn(
+            ID,
+            format!("default routes via {} share metric {metric}", devs.join(", ")),
+            "remove the duplicate default route or give each interface a distinct metric",
+        );
+    }
+
+    PreflightCheck::pass(ID, format!("default route via `{}`", routes[0].dev))
+}
+
+fn check_policy_rules(input: &PreflightInput) -> PreflightCheck {
+    const ID: &str = "policy-rules";
+    let own_table = input.table_index.to_string();
+    let mut stale = false;
+    let mut foreign = Vec::new();
+    for line in input.rules.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some(table) = words
+            .iter()
+            .position(|w| *w == "lookup" || *w == "table")
+            .and_then(|i| words.get(i + 1))
+        else {
+            continue;
+        };
+        if *table == own_table {
+            stale |= !input.tun_active;
+        } else if !STANDARD_TABLES.contains(table) {
+            foreign.push(line.trim());
+        }
+    }
+
+    if stale {
+        return PreflightCheck::warn(
+            ID,
+            format!("leftover policy rules for table {own_table} from a previous TUN session"),
+            format!("remove them with `sudo ip rule del table {own_table}` until none remain"),
+        );
+    }
+    if !foreign.is_empty() {
+        return PreflightCheck::warn(
+            ID,
+            format!("policy rules from other software: {}", foreign.join(" | ")),
+            "VPN clients such as wg-quick or tailscale may take traffic before TUN, disconnect them first",
+        );
+    }
+    PreflightCheck::pass(ID, "no conflicting policy rules")
+}
+
+fn check_dns_stub(input: &PreflightInput) -> PreflightCheck {
+    const ID: &str = "dns-stub";
+    let hijacks_53 = input.dns_hijack.iter().any(|entry| entry.ends_with(":53"));
+    let stub_listening = input.proc_net_udp.lines().skip(1).any(|line| {
+        line.split_whitespace()
+            .nth(1)
+            .is_some_and(|local| RESOLVED_STUBS.contains(&local))
+    });
+    let uses_stub = input.resolv_conf.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("nameserver") && words.next().is_some_and(|ns| ns.starts_with("127.0.0.5"))
+    });
+
+    if hijacks_53 && stub_listening && uses_stub {
+        return PreflightCheck::warn(
+            ID,
+            "systemd-resolved answers DNS on 127.0.0.53, queries never reach the TUN so dns-hijack is bypassed",
+            "set `DNSStubListener=no` in /etc/systemd/resolved.conf, or link /etc/resolv.conf to /run/systemd/resolve/resolv.conf",
+        );
+    }
+    PreflightCheck::pass(ID, "no local DNS stub in the way of dns-hijack")
+}
+
+fn check_interface(input: &PreflightInput) -> PreflightCheck {
+    const ID: &str = "tun-interface";
+    let existing = input.proc_net_dev.lines().skip(2).find_map(|line| {
+        let name = line.split_once(':')?.0.trim();
+        (name == input.device || name == DEFAULT_DEVICE || name.starts_with("utun")).then_some(name)
+    });
+    match existing {
+        Some(name) if !input.tun_active => PreflightCheck::warn(
+            ID,
+            format!("interface `{name}` already exists"),
+            format!("remove the stale interface with `sudo ip link delete {name}`"),
+        ),
+        _ => PreflightCheck::pass(ID, "no conflicting TUN interface"),
+    }
+}
+
+#[allow(clippy::expect_used)]
+#[cfg(test)]
+mod tests {
+    use super::{CheckStatus, PreflightInput, TunPreflightReport, evaluate};
+
+    const PROC_MISC: &str = "229 fuse\n200 tun\n237 loop-control\n";
+    const SELF_STATUS: &str = "Name:\tclash-verge\nUid:\t1000\t1000\t1000\t1000\nCapEff:\t0000000000000000\n";
+    const CORE_STATUS: &str = "Name:\tverge-mihomo\nUid:\t1000\t1000\t1000\t1000\nCapEff:\t0000000000003000\n";
+    const ROUTES: &str = "default via 192.168.1.1 dev eth0 proto dhcp src 192.168.1.20 metric 100 \n";
+    const RULES: &str = "0:\tfrom all lookup local\n32766:\tfrom all lookup main\n32767:\tfrom all lookup default\n";
+    const RESOLV: &str = "# stub\nnameserver 127.0.0.53\noptions edns0 trust-ad\n";
+    const UDP: &str = "  sl  local_address rem_address   st\n   0: 3500007F:0035 00000000:0000 07\n";
+    const NET_DEV: &str = "Inter-|   Receive\n face |bytes\n    lo: 0 0\n  eth0: 0 0\n";
+
+    fn healthy() -> PreflightInput {
+        PreflightInput {
+            tun_device: Some(true),
+            proc_misc: PROC_MISC.into(),
+            self_status: SELF_STATUS.into(),
+            core_status: Some(CORE_STATUS.into()),
+            core_path: "/usr/bin/verge-mihomo".into(),
+            default_routes: ROUTES.into(),
+            rules: RULES.into(),
+            resolv_conf: "nameserver 192.168.1.1\n".into(),
+            proc_net_udp: UDP.into(),
+            proc_net_dev: NET_DEV.into(),
+            device: "Mihomo".into(),
+            table_index: 2022,
+            dns_hijack: vec!["any:53".into()],
+            ..PreflightInput::default()
+        }
+    }
+
+    fn status(report: &TunPreflightReport, id: &str) -> CheckStatus {
+        report.checks.iter().find(|check| check.id == id).expect("check").status
+    }
+
+    #[test]
+    fn healthy_system_is_ready() {
+        let report = evaluate(&healthy());
+        assert!(report.ready);
+        assert!(report.checks.iter().all(|check| check.status == CheckStatus::Pass));
+    }
+
+    #[test]
+    fn missing_device_and_privileges_fail() {
+        let input = PreflightInput {
+            tun_device: None,
+            proc_misc: "229 fuse\n".into(),
+            core_status: Some(CORE_STATUS.replace("3000", "0000")),
+            getcap: Some("/usr/bin/verge-mihomo cap_net_bind_service=ep\n".into()),
+            ..healthy()
+        };
+        let report = evaluate(&input);
+        assert!(!report.ready);
+        assert_eq!(status(&report, "tun-device"), CheckStatus::Fail);
+        assert_eq!(status(&report, "privileges"), CheckStatus::Fail);
+        assert!(report.checks[0].hint.as_deref().is_some_and(|h| h.contains("modprobe")));
+
+        let input = PreflightInput {
+            core_status: None,
+            getcap: Some("/usr/bin/verge-mihomo = cap_net_admin,cap_net_bind_service+ep\n".into()),
+            ..healthy()
+        };
+        assert_eq!(status(&evaluate(&input), "privileges"), CheckStatus::Pass);
+    }
+
+    #[test]
+    fn routing_conflicts_warn() {
+        let input = PreflightInput {
+            default_routes: format!("{ROUTES}default dev wg0 scope link metric 50\n"),
+            rules: format!("{RULES}9000:\tfrom all to 198.18.0.0/30 lookup 2022\n"),
+            ..healthy()
+        };
+        let report = evaluate(&input);
+        assert!(report.ready);
+        assert_eq!(status(&report, "default-route"), CheckStatus::Warn);
+        assert_eq!(status(&report, "policy-rules"), CheckStatus::Warn);
+
+        let input = PreflightInput {
+            default_routes: format!("{ROUTES}default via 10.0.0.1 dev wlan0 metric 100\n"),
+            rules: format!("{RULES}32765:\tnot from all fwmark 0xca6c lookup 51820\n"),
+            tun_active: true,
+            ..healthy()
+        };
+        let report = evaluate(&input);
+        let route = &report.checks[2];
+        assert_eq!(route.status, CheckStatus::Warn);
+        assert!(route.detail.contains("eth0, wlan0"));
+        assert!(report.checks[3].detail.contains("51820"));
+    }
+
+    #[test]
+    fn resolved_stub_and_stale_interface_warn() {
+        let input = PreflightInput {
+            resolv_conf: RESOLV.into(),
+            proc_net_dev: format!("{NET_DEV}  Mihomo: 0 0\n"),
+            ..healthy()
+        };
+        let report = evaluate(&input);
+        assert_eq!(status(&report, "dns-stub"), CheckStatus::Warn);
+        assert_eq!(status(&report, "tun-interface"), CheckStatus::Warn);
+
+        let input = PreflightInput {
+            dns_hijack: vec![],
+            tun_active: true,
+            ..input
+        };
+        let report = evaluate(&input);
+        assert_eq!(status(&report, "dns-stub"), CheckStatus::Pass);
+        assert_eq!(status(&report, "tun-interface"), CheckStatus::Pass);
+    }
+}
@@ -2,11 +2,10 @@ use anyhow::Result;
 use scopeguard::defer;
 use smartstring::alias::String;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri_plugin_shell::ShellExt as _;
 use tokio::fs;
 
 use crate::config::{Config, ConfigType};
-use crate::core::handle;
+use crate::core::{handle, managed_core};
 use crate::singleton;
 use crate::utils::dirs;
 use clash_verge_logging::{Type, logging};
@@ -173,6 +172,30 @@ impl CoreConfigValidator {
         }
     }
 
+    /// 验证 WebAssembly 模块：能否编译以及是否导出了约定的接口
+    async fn validate_wasm_file(path: &str) -> Result<(bool, String)> {
+        let wasm = match fs::read(path).await {
+            Ok(wasm) => wasm,
+            Err(err) => {
+                let error_msg = format!("Failed to read wasm file: {err}").into();
+                logging!(warn, Type::Validate, "无法读取Wasm文件: {}", err);
+                return Ok((false, error_msg));
+            }
+        };
+
+        match crate::enhance::wasm::validate_wasm(&wasm) {
+            Ok(_) => {
+                logging!(debug, Type::Validate, "Wasm模块验证通过: {}", path);
+                Ok((true, String::new()))
+            }
+            Err(err) => {
+                let error_msg = format!("Wasm module error: {err}").into();
+                logging!(warn, Type::Validate, "Wasm模块验证失败: {}", err);
+                Ok((false, error_msg))
+            }
+        }
+    }
+
     /// 验证指定的配置文件
     pub async fn validate_config_file(config_path: &str, is_merge_file: Option<bool>) -> Result<(bool, String)> {
         // 检查程序是否正在退出，如果是则跳过验证
@@ -188,6 +211,11 @@ impl CoreConfigValidator {
             return Ok((false, error_msg));
         }
 
+        if has_ext(config_path, "wasm") {
+            logging!(info, Type::Validate, "检测到Wasm模块，进行模块验证: {}", config_path);
+            return Self::validate_wasm_file(config_path).await;
+        }
+
         // 如果是合并文件且不是强制验证，执行语法检查但不进行完整验证
         if is_merge_file.unwrap_or(false) {
             logging!(info, Type::Validate, "检测到Merge文件，仅进行语法检查: {}", config_path);
@@ -236,17 +264,12 @@ impl CoreConfigValidator {
         let clash_core = Config::verge().await.latest_arc().get_valid_clash_core();
         logging!(info, Type::Validate, "使用内核: {}", clash_core);
 
-        let app_handle = handle::Handle::app_handle();
         let app_dir = dirs::app_home_dir()?;
         let app_dir_str = dirs::path_to_str(&app_dir)?;
         logging!(info, Type::Validate, "验证目录: {}", app_dir_str);
 
         // 使用子进程运行clash验证配置
-        let command =
-            app_handle
-                .shell()
-                .sidecar(clash_core.as_str())?
-                .args(["-t", "-d", app_dir_str, "-f", config_path]);
+        let command = managed_core::core_command(&clash_core)?.args(["-t", "-d", app_dir_str, "-f", config_path]);
         let output = command.output().await?;
 
         let status = &output.status;
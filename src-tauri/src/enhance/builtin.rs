@@ -0,0 +1,160 @@
+use super::{chain::ChainSupport, use_lowercase};
+use serde::Serialize;
+use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+
+/// 内建的配置转换
+pub struct BuiltinTransform {
+    pub uid: &'static str,
+    pub description: &'static str,
+    pub supports: &'static [ChainSupport],
+    pub apply: fn(Mapping) -> Mapping,
+}
+
+/// 提供给前端展示的内建转换信息
+#[derive(Debug, Clone, Serialize)]
+pub struct BuiltinTransformInfo {
+    pub uid: &'static str,
+    pub description: &'static str,
+}
+
+/// 内建转换注册表，按顺序执行
+pub static BUILTIN_TRANSFORMS: &[BuiltinTransform] = &[
+    BuiltinTransform {
+        uid: "verge_hy_alpn",
+        description: "Convert hysteria alpn string to array (meta 1.13.2)",
+        supports: &[ChainSupport::ClashMeta, ChainSupport::ClashMetaAlpha],
+        apply: meta_hy_alpn,
+    },
+    BuiltinTransform {
+        uid: "verge_meta_guard",
+        description: "Replace unsupported script mode with rule mode",
+        supports: &[ChainSupport::ClashMeta, ChainSupport::ClashMetaAlpha],
+        apply: meta_guard,
+    },
+];
+
+impl BuiltinTransform {
+    pub fn is_support(&self, core: Option<&String>) -> bool {
+        self.supports.iter().any(|support| support.is_support(core))
+    }
+}
+
+pub fn builtin_transform_infos() -> Vec<BuiltinTransformInfo> {
+    BUILTIN_TRANSFORMS
+        .iter()
+        .map(|transform| BuiltinTransformInfo {
+            uid: transform.uid,
+            description: transform.description,
+        })
+        .collect()
+}
+
+/// 依次执行当前内核支持且未被禁用的内建转换
+pub fn use_builtin(config: Mapping, core: Option<&String>, disabled: &[String]) -> Mapping {
+    let mut transforms = BUILTIN_TRANSFORMS
+        .iter()
+        .filter(|transform| transform.is_support(core))
+        .filter(|transform| !disabled.iter().any(|uid| uid == transform.uid))
+        .peekable();
+
+    if transforms.peek().is_none() {
+        return config;
+    }
+
+    // 与脚本执行保持一致，顶层字段统一小写
+    transforms.fold(use_lowercase(&config), |config, transform| (transform.apply)(config))
+}
+
+/// meta 不支持 script 模式，回退到 rule
+fn meta_guard(mut config: Mapping) -> Mapping {
+    if config.get("mode").and_then(Value::as_str) == Some("script") {
+        config.insert("mode".into(), "rule".into());
+    }
+    config
+}
+
+/// meta 1.13.2 起 hysteria 的 alpn 需要为数组
+fn meta_hy_alpn(mut config: Mapping) -> Mapping {
+    if let Some(Value::Sequence(proxies)) = config.get_mut("proxies") {
+        for proxy in proxies.iter_mut().filter_map(Value::as_mapping_mut) {
+            if proxy.get("type").and_then(Value::as_str) != Some("hysteria") {
+                continue;
+            }
+            if let Some(alpn) = proxy.get_mut("alpn")
+                && alpn.is_string()
+            {
+                let value = std::mem::take(alpn);
+                *alpn = Value::Sequence(vec![value]);
+            }
+        }
+    }
+    config
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{BUILTIN_TRANSFORMS, use_builtin};
+    use crate::enhance::script::use_script;
+    use serde_yaml_ng::Mapping;
+    use smartstring::alias::String;
+
+    const FIXTURES: &[&str] = &[
+        "mode: script\nproxies: []\n",
+        "Mode: script\nlog-level: info\n",
+        "mode: rule\n",
+        r"
+mode: global
+proxies:
+  - { name: a, type: hysteria, server: a.example.com, port: 443, alpn: h3 }
+  - { name: b, type: hysteria, server: b.example.com, port: 443, alpn: [h3, h2] }
+  - { name: c, type: hysteria2, server: c.example.com, port: 443, alpn: h3 }
+  - { name: d, type: ss, server: d.example.com, port: 443, alpn: h3 }
+  - { name: e, type: hysteria, server: e.example.com, port: 443 }
+",
+        "proxies: { name: a, type: hysteria, alpn: h3 }\n",
+        "proxies:\n  - DIRECT\n  - { type: hysteria, alpn: '' }\n",
+    ];
+
+    // 原先的 JS 实现，作为等价性参照
+    const JS_REFERENCE: &[(&str, &str)] = &[
+        ("verge_meta_guard", include_str!("./builtin/meta_guard.js")),
+        ("verge_hy_alpn", include_str!("./builtin/meta_hy_alpn.js")),
+    ];
+
+    fn js_source(uid: &str) -> &'static str {
+        JS_REFERENCE
+            .iter()
+            .find(|(name, _)| *name == uid)
+            .map(|(_, script)| *script)
+            .expect("Missing js reference for builtin transform")
+    }
+
+    #[test]
+    fn native_matches_js() {
+        for fixture in FIXTURES {
+            let config: Mapping = serde_yaml_ng::from_str(fixture).expect("Failed to parse fixture");
+
+            let expected = BUILTIN_TRANSFORMS.iter().fold(config.clone(), |config, transform| {
+                let (res, _) =
+                    use_script(js_source(transform.uid).into(), &config, &String::new()).expect("Script should run");
+                res
+            });
+            let actual = use_builtin(config, None, &[]);
+
+            assert_eq!(actual, expected, "fixture:\n{fixture}");
+        }
+    }
+
+    #[test]
+    fn disabled_transform_is_skipped() {
+        let config: Mapping = serde_yaml_ng::from_str("mode: script").expect("Failed to parse fixture");
+
+        let res = use_builtin(config.clone(), None, &["verge_meta_guard".into()]);
+        assert_eq!(res, config);
+
+        let res = use_builtin(config, Some(&String::from("clash")), &[]);
+        assert_eq!(res.get("mode").and_then(|v| v.as_str()), Some("script"));
+    }
+}
@@ -0,0 +1,296 @@
+use super::chain::{ChainItem, ChainType};
+use crate::{singleton, utils::dirs};
+use clash_verge_logging::{Type, logging};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::Mapping;
+use sha2::{Digest as _, Sha256};
+use smartstring::alias::String;
+use std::{collections::HashMap, fmt::Write as _, path::PathBuf, sync::Arc};
+use tokio::fs;
+
+/// 缓存格式版本，格式或内建脚本变化时需要递增
+const CACHE_VERSION: &str = "2";
+
+/// 增强流程中可缓存的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// 全局 Merge / Script
+    Global,
+    /// 订阅自身的 Rules / Proxies / Groups / Merge / Script / Wasm
+    Profile,
+    /// 内建脚本
+    Builtin,
+}
+
+impl Stage {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Profile => "profile",
+            Self::Builtin => "builtin",
+        }
+    }
+}
+
+/// 某一阶段的输出
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StageOutput {
+    pub config: Mapping,
+    pub exists_keys: Vec<String>,
+    pub logs: HashMap<String, Vec<(String, String)>>,
+}
+
+type Parts = (Mapping, Vec<String>, HashMap<String, Vec<(String, String)>>);
+
+impl StageOutput {
+    pub fn into_parts(self) -> Parts {
+        (self.config, self.exists_keys, self.logs)
+    }
+}
+
+impl From<Parts> for StageOutput {
+    fn from((config, exists_keys, logs): Parts) -> Self {
+        Self {
+            config,
+            exists_keys,
+            logs,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    digest: String,
+    output: StageOutput,
+}
+
+/// 计算阶段输入的内容哈希
+pub struct StageHasher(Sha256);
+
+impl StageHasher {
+    pub fn new(stage: Stage) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(CACHE_VERSION);
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update(stage.as_str());
+        Self(hasher)
+    }
+
+    /// 以上一阶段的摘要作为本阶段输入的一部分
+    pub fn chain(mut self, digest: &str) -> Self {
+        self.bytes(digest.as_bytes());
+        self
+    }
+
+    pub fn str(mut self, value: &str) -> Self {
+        self.bytes(value.as_bytes());
+        self
+    }
+
+    pub fn flag(mut self, value: bool) -> Self {
+        self.bytes(&[u8::from(value)]);
+        self
+    }
+
+    pub fn mapping(mut self, value: &Mapping) -> Self {
+        self.serialized(value);
+        self
+    }
+
+    pub fn item(mut self, item: &ChainItem) -> Self {
+        self.bytes(item.uid.as_bytes());
+        match &item.data {
+            ChainType::Merge(merge) => self.serialized(merge),
+            ChainType::Script(script) => self.bytes(script.as_bytes()),
+            ChainType::Rules(seq) | ChainType::Proxies(seq) | ChainType::Groups(seq) => self.serialized(seq),
+            ChainType::Wasm(wasm) => self.bytes(wasm),
+        }
+        self
+    }
+
+    pub fn maybe_item(self, item: Option<&ChainItem>) -> Self {
+        match item {
+            Some(item) => self.flag(true).item(item),
+            None => self.flag(false),
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.0.finalize().iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+    }
+
+    // 带长度前缀，避免相邻字段拼接产生歧义
+    fn bytes(&mut self, value: &[u8]) {
+        self.0.update((value.len() as u64).to_le_bytes());
+        self.0.update(value);
+    }
+
+    fn serialized<T: Serialize>(&mut self, value: &T) {
+        let data = serde_json::to_vec(value)
+            .or_else(|_| serde_yaml_ng::to_string(value).map(std::string::String::into_bytes))
+            .unwrap_or_default();
+        self.bytes(&data);
+    }
+}
+
+/// 增强阶段输出缓存
+///
+/// 每个阶段只保留最近一次的结果，输入摘要不一致即视为失效。
+/// 内存中未命中时会尝试读取磁盘缓存，便于重启后复用。
+pub struct EnhanceCache {
+    memory: RwLock<HashMap<Stage, Arc<CacheEntry>>>,
+    disk_dir: Option<PathBuf>,
+}
+
+impl EnhanceCache {
+    fn new() -> Self {
+        Self::with_dir(dirs::enhance_cache_dir().ok())
+    }
+
+    /// `disk_dir` 为 `None` 时只使用内存缓存
+    pub fn with_dir(disk_dir: Option<PathBuf>) -> Self {
+        Self {
+            memory: RwLock::new(HashMap::new()),
+            disk_dir,
+        }
+    }
+
+    pub async fn get(&self, stage: Stage, digest: &str) -> Option<StageOutput> {
+        let cached = self.memory.read().get(&stage).map(Arc::clone);
+        if let Some(entry) = cached
+            && entry.digest == digest
+        {
+            logging!(debug, Type::Config, "enhance cache hit: {}", stage.as_str());
+            return Some(entry.output.clone());
+        }
+
+        let entry = self.read_disk(stage).await.filter(|entry| entry.digest == digest)?;
+        logging!(debug, Type::Config, "enhance disk cache hit: {}", stage.as_str());
+        let output = entry.output.clone();
+        self.memory.write().insert(stage, Arc::new(entry));
+        Some(output)
+    }
+
+    pub async fn put(&self, stage: Stage, digest: String, output: &StageOutput) {
+        let entry = Arc::new(CacheEntry {
+            digest,
+            output: output.clone(),
+        });
+        self.memory.write().insert(stage, Arc::clone(&entry));
+        self.write_disk(stage, &entry).await;
+    }
+
+    /// 清空内存与磁盘缓存
+    pub async fn clear(&self) {
+        self.memory.write().clear();
+        if let Some(dir) = &self.disk_dir
+            && let Err(err) = fs::remove_dir_all(dir).await
+            && err.kind() != std::io::ErrorKind::NotFound
+        {
+            logging!(warn, Type::Config, "failed to clear enhance cache: {err}");
+        }
+    }
+
+    fn disk_path(&self, stage: Stage) -> Option<PathBuf> {
+        self.disk_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", stage.as_str())))
+    }
+
+    async fn read_disk(&self, stage: Stage) -> Option<CacheEntry> {
+        let path = self.disk_path(stage)?;
+        let data = fs::read(&path).await.ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    async fn write_disk(&self, stage: Stage, entry: &CacheEntry) {
+        let Some(path) = self.disk_path(stage) else {
+            return;
+        };
+
+        let data = match serde_json::to_vec(entry) {
+            Ok(data) => data,
+            Err(err) => {
+                logging!(debug, Type::Config, "skip persisting enhance cache: {err}");
+                return;
+            }
+        };
+
+        if let Some(dir) = path.parent()
+            && let Err(err) = fs::create_dir_all(dir).await
+        {
+            logging!(warn, Type::Config, "failed to create enhance cache dir: {err}");
+            return;
+        }
+        if let Err(err) = fs::write(&path, data).await {
+            logging!(warn, Type::Config, "failed to persist enhance cache: {err}");
+        }
+    }
+}
+
+singleton!(EnhanceCache, ENHANCE_CACHE);
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{EnhanceCache, Stage, StageHasher, StageOutput};
+    use crate::enhance::chain::{ChainItem, ChainType};
+    use serde_yaml_ng::Mapping;
+
+    fn mapping(yaml: &str) -> Mapping {
+        serde_yaml_ng::from_str(yaml).expect("Failed to parse test YAML")
+    }
+
+    #[test]
+    fn digest_tracks_inputs() {
+        let config = mapping("mixed-port: 7897");
+        let script = ChainItem::to_script("Script", "function main(c){return c}");
+
+        let base = StageHasher::new(Stage::Global).mapping(&config).item(&script).finish();
+        let same = StageHasher::new(Stage::Global).mapping(&config).item(&script).finish();
+        assert_eq!(base, same);
+
+        let other_config = StageHasher::new(Stage::Global)
+            .mapping(&mapping("mixed-port: 7890"))
+            .item(&script)
+            .finish();
+        assert_ne!(base, other_config);
+
+        let other_script = ChainItem {
+            uid: "Script".into(),
+            data: ChainType::Script("function main(c){c.a=1;return c}".into()),
+        };
+        let other_item = StageHasher::new(Stage::Global)
+            .mapping(&config)
+            .item(&other_script)
+            .finish();
+        assert_ne!(base, other_item);
+
+        let other_stage = StageHasher::new(Stage::Profile).mapping(&config).item(&script).finish();
+        assert_ne!(base, other_stage);
+    }
+
+    #[tokio::test]
+    async fn cache_hit_and_invalidate() {
+        let cache = EnhanceCache::with_dir(None);
+        let output = StageOutput {
+            config: mapping("mode: rule"),
+            ..Default::default()
+        };
+
+        assert!(cache.get(Stage::Builtin, "a").await.is_none());
+        cache.put(Stage::Builtin, "a".into(), &output).await;
+
+        let hit = cache.get(Stage::Builtin, "a").await.expect("cache should hit");
+        assert_eq!(hit.config, output.config);
+        assert!(cache.get(Stage::Builtin, "b").await.is_none());
+        assert!(cache.get(Stage::Global, "a").await.is_none());
+
+        cache.clear().await;
+        assert!(cache.get(Stage::Builtin, "a").await.is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use super::SeqMap;
 use crate::{
     config::PrfItem,
+    core::managed_core,
     utils::{dirs, help},
 };
 use serde_yaml_ng::Mapping;
@@ -20,6 +21,7 @@ pub enum ChainType {
     Rules(SeqMap),
     Proxies(SeqMap),
     Groups(SeqMap),
+    Wasm(Vec<u8>),
 }
 
 #[derive(Debug, Clone)]
@@ -110,33 +112,15 @@ impl AsyncChainItemFrom for Option<ChainItem> {
                     data: ChainType::Groups(seq_map),
                 })
             }
+            "wasm" => Some(ChainItem {
+                uid,
+                data: ChainType::Wasm(fs::read(path).await.ok()?),
+            }),
             _ => None,
         }
     }
 }
 impl ChainItem {
-    /// 内建支持一些脚本
-    pub fn builtin() -> Vec<(ChainSupport, Self)> {
-        // meta 的一些处理
-        let meta_guard = Self::to_script("verge_meta_guard", include_str!("./builtin/meta_guard.js"));
-
-        // meta 1.13.2 alpn string 转 数组
-        let hy_alpn = Self::to_script("verge_hy_alpn", include_str!("./builtin/meta_hy_alpn.js"));
-
-        // meta 的一些处理
-        let meta_guard_alpha = Self::to_script("verge_meta_guard", include_str!("./builtin/meta_guard.js"));
-
-        // meta 1.13.2 alpn string 转 数组
-        let hy_alpn_alpha = Self::to_script("verge_hy_alpn", include_str!("./builtin/meta_hy_alpn.js"));
-
-        vec![
-            (ChainSupport::ClashMeta, hy_alpn),
-            (ChainSupport::ClashMeta, meta_guard),
-            (ChainSupport::ClashMetaAlpha, hy_alpn_alpha),
-            (ChainSupport::ClashMetaAlpha, meta_guard_alpha),
-        ]
-    }
-
     pub fn to_script<U: Into<String>, D: Into<String>>(uid: U, data: D) -> Self {
         Self {
             uid: uid.into(),
@@ -149,7 +133,7 @@ impl ChainSupport {
     pub fn is_support(&self, core: Option<&String>) -> bool {
         match core {
             Some(core) => matches!(
-                (self, core.as_str()),
+                (self, managed_core::core_flavour(core)),
                 (Self::ClashMeta, "verge-mihomo") | (Self::ClashMetaAlpha, "verge-mihomo-alpha")
             ),
             None => true,
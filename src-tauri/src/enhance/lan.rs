@@ -0,0 +1,105 @@
+use super::vars::{PathSegment, SecretValues};
+use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+
+/// 本机回环地址始终免认证，避免影响系统代理
+const LOOPBACK_PREFIXES: [&str; 2] = ["127.0.0.1/8", "::1/128"];
+
+/// 局域网共享开启时注入认证用户，保留订阅中已有的用户
+pub fn use_lan_auth(mut config: Mapping, credentials: &[String]) -> Mapping {
+    let allow_lan = config.get("allow-lan").and_then(Value::as_bool).unwrap_or(false);
+    if !allow_lan || credentials.is_empty() {
+        return config;
+    }
+
+    let mut authentication = config
+        .get("authentication")
+        .and_then(Value::as_sequence)
+        .cloned()
+        .unwrap_or_default();
+    for credential in credentials {
+        let credential = Value::from(credential.as_str());
+        if !authentication.contains(&credential) {
+            authentication.push(credential);
+        }
+    }
+    config.insert("authentication".into(), Value::Sequence(authentication));
+
+    let mut prefixes = config
+        .get("skip-auth-prefixes")
+        .and_then(Value::as_sequence)
+        .cloned()
+        .unwrap_or_default();
+    for prefix in LOOPBACK_PREFIXES {
+        let prefix = Value::from(prefix);
+        if !prefixes.contains(&prefix) {
+            prefixes.push(prefix);
+        }
+    }
+    config.insert("skip-auth-prefixes".into(), Value::Sequence(prefixes));
+
+    config
+}
+
+/// 记录注入的认证用户位置，展示运行时配置时隐去密码
+pub fn record_lan_auth(config: &Mapping, credentials: &[String], found: &mut SecretValues) {
+    let Some(users) = config.get("authentication").and_then(Value::as_sequence) else {
+        return;
+    };
+    for (index, user) in users.iter().enumerate() {
+        if user
+            .as_str()
+            .is_some_and(|user| credentials.iter().any(|c| c.as_str() == user))
+        {
+            let path = vec![PathSegment::Key("authentication".into()), PathSegment::Index(index)];
+            found.push(path, user.clone());
+        }
+    }
+}
+
+#[allow(clippy::expect_used)]
+#[cfg(test)]
+mod tests {
+    use super::use_lan_auth;
+    use serde_yaml_ng::{Mapping, Value};
+
+    fn parse(yaml: &str) -> Mapping {
+        serde_yaml_ng::from_str(yaml).expect("valid yaml")
+    }
+
+    fn strings(config: &Mapping, key: &str) -> Vec<std::string::String> {
+        config
+            .get(key)
+            .and_then(Value::as_sequence)
+            .expect("sequence")
+            .iter()
+            .filter_map(|v| v.as_str().map(ToOwned::to_owned))
+            .collect()
+    }
+
+    #[test]
+    fn lan_auth_requires_allow_lan() {
+        let config = use_lan_auth(parse("allow-lan: false"), &["a:b".into()]);
+        assert!(!config.contains_key("authentication"));
+    }
+
+    #[test]
+    fn lan_auth_merges_existing_users() {
+        let config = parse(
+            r#"
+allow-lan: true
+authentication: ["sub:pass", "a:b"]
+skip-auth-prefixes: ["192.168.1.0/24"]
+"#,
+        );
+        let config = use_lan_auth(config, &["a:b".into(), "phone:secret".into()]);
+        assert_eq!(strings(&config, "authentication"), ["sub:pass", "a:b", "phone:secret"]);
+        assert_eq!(
+            strings(&config, "skip-auth-prefixes"),
+            ["192.168.1.0/24", "127.0.0.1/8", "::1/128"]
+        );
+
+        let config = use_lan_auth(parse("allow-lan: true"), &["a:b".into()]);
+        assert_eq!(strings(&config, "skip-auth-prefixes"), ["127.0.0.1/8", "::1/128"]);
+    }
+}
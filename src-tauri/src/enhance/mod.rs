@@ -1,21 +1,34 @@
+pub mod builtin;
+pub mod cache;
 mod chain;
 pub mod field;
-mod merge;
-mod script;
+mod lan;
+pub mod merge;
+pub mod script;
 pub mod seq;
 mod tun;
+pub mod vars;
+pub mod wasm;
 
 use self::{
+    builtin::use_builtin,
+    cache::{EnhanceCache, Stage, StageHasher, StageOutput},
     chain::{AsyncChainItemFrom as _, ChainItem, ChainType},
     field::{use_keys, use_lowercase, use_sort},
+    lan::{record_lan_auth, use_lan_auth},
     merge::use_merge,
     script::use_script,
     seq::{SeqMap, use_seq},
     tun::use_tun,
+    vars::{SecretValues, Variables},
+    wasm::use_wasm,
 };
 use crate::utils::dirs;
 use crate::{config::Config, utils::tmpl};
-use crate::{config::IVerge, constants};
+use crate::{
+    config::{IProfiles, IVerge, PrfItem},
+    constants,
+};
 use clash_verge_logging::{Type, logging};
 use serde_yaml_ng::{Mapping, Value};
 use smartstring::alias::String;
@@ -30,9 +43,13 @@ struct ConfigValues {
     clash_core: Option<String>,
     enable_tun: bool,
     enable_builtin: bool,
+    disabled_builtin: Vec<String>,
+    enable_cache: bool,
+    variables: Variables,
     socks_enabled: bool,
     http_enabled: bool,
     enable_dns_settings: bool,
+    lan_credentials: Vec<String>,
     #[cfg(not(target_os = "windows"))]
     redir_enabled: bool,
     #[cfg(target_os = "linux")]
@@ -47,8 +64,11 @@ struct ProfileItems {
     rules_item: ChainItem,
     proxies_item: ChainItem,
     groups_item: ChainItem,
-    global_merge: ChainItem,
-    global_script: ChainItem,
+    wasm_item: Option<ChainItem>,
+    global_merge: Option<ChainItem>,
+    global_script: Option<ChainItem>,
+    global_logs: HashMap<String, ResultLog>,
+    profile_uid: String,
     profile_name: String,
 }
 
@@ -56,6 +76,7 @@ impl Default for ProfileItems {
     fn default() -> Self {
         Self {
             config: Default::default(),
+            profile_uid: Default::default(),
             profile_name: Default::default(),
             merge_item: ChainItem {
                 uid: "".into(),
@@ -77,14 +98,16 @@ impl Default for ProfileItems {
                 uid: "".into(),
                 data: ChainType::Groups(SeqMap::default()),
             },
-            global_merge: ChainItem {
+            wasm_item: None,
+            global_merge: Some(ChainItem {
                 uid: "Merge".into(),
                 data: ChainType::Merge(Mapping::new()),
-            },
-            global_script: ChainItem {
+            }),
+            global_script: Some(ChainItem {
                 uid: "Script".into(),
                 data: ChainType::Script(tmpl::ITEM_SCRIPT.into()),
-            },
+            }),
+            global_logs: HashMap::new(),
         }
     }
 }
@@ -102,21 +125,35 @@ async fn get_config_values() -> ConfigValues {
     let IVerge {
         ref enable_tun_mode,
         ref enable_builtin_enhanced,
+        ref disabled_builtin_enhanced,
+        ref variables,
+        ref secret_variables,
+        ref enable_enhance_cache,
         ref verge_socks_enabled,
         ref verge_http_enabled,
         ref enable_dns_settings,
         ..
     } = *verge_arc;
 
-    let (clash_core, enable_tun, enable_builtin, socks_enabled, http_enabled, enable_dns_settings) = (
+    let (clash_core, enable_tun, enable_builtin, enable_cache, socks_enabled, http_enabled, enable_dns_settings) = (
         Some(verge_arc.get_valid_clash_core()),
         enable_tun_mode.unwrap_or(false),
         enable_builtin_enhanced.unwrap_or(true),
+        enable_enhance_cache.unwrap_or(true),
         verge_socks_enabled.unwrap_or(false),
         verge_http_enabled.unwrap_or(false),
         enable_dns_settings.unwrap_or(false),
     );
 
+    let disabled_builtin = disabled_builtin_enhanced.clone().unwrap_or_default();
+    let variables = Variables::new(variables.as_ref(), secret_variables.as_ref());
+    let lan_credentials = verge_arc
+        .lan_sharing_users
+        .iter()
+        .flatten()
+        .map(|user| format!("{}:{}", user.username, user.password).into())
+        .collect();
+
     #[cfg(not(target_os = "windows"))]
     let redir_enabled = verge_arc.verge_redir_enabled.unwrap_or(false);
 
@@ -131,9 +168,13 @@ async fn get_config_values() -> ConfigValues {
         clash_core,
         enable_tun,
         enable_builtin,
+        disabled_builtin,
+        enable_cache,
+        variables,
         socks_enabled,
         http_enabled,
         enable_dns_settings,
+        lan_credentials,
         #[cfg(not(target_os = "windows"))]
         redir_enabled,
         #[cfg(target_os = "linux")]
@@ -191,6 +232,8 @@ async fn collect_profile_items() -> ProfileItems {
         Cow::Owned("Groups".into())
     };
 
+    let wasm_uid = current_item.current_wasm();
+
     let name = profiles_arc
         .get_item(current_profile_uid)
         .ok()
@@ -262,31 +305,46 @@ async fn collect_profile_items() -> ProfileItems {
         data: ChainType::Groups(SeqMap::default()),
     });
 
-    let global_merge = {
-        let item = profiles_arc.get_item("Merge").ok().cloned();
+    let wasm_item = {
+        let item = wasm_uid.and_then(|uid| profiles_arc.get_item(uid).ok()).cloned();
         if let Some(item) = item {
             <Option<ChainItem>>::from_async(&item).await
         } else {
             None
         }
-    }
-    .unwrap_or_else(|| ChainItem {
-        uid: "Merge".into(),
-        data: ChainType::Merge(Mapping::new()),
-    });
+    };
+
+    let mut global_logs = HashMap::new();
 
-    let global_script = {
+    let global_merge = if global_in_scope("Merge", &profiles_arc, current_item, &mut global_logs) {
+        let item = profiles_arc.get_item("Merge").ok().cloned();
+        let chain = if let Some(item) = item {
+            <Option<ChainItem>>::from_async(&item).await
+        } else {
+            None
+        };
+        Some(chain.unwrap_or_else(|| ChainItem {
+            uid: "Merge".into(),
+            data: ChainType::Merge(Mapping::new()),
+        }))
+    } else {
+        None
+    };
+
+    let global_script = if global_in_scope("Script", &profiles_arc, current_item, &mut global_logs) {
         let item = profiles_arc.get_item("Script").ok().cloned();
-        if let Some(item) = item {
+        let chain = if let Some(item) = item {
             <Option<ChainItem>>::from_async(&item).await
         } else {
             None
-        }
-    }
-    .unwrap_or_else(|| ChainItem {
-        uid: "Script".into(),
-        data: ChainType::Script(tmpl::ITEM_SCRIPT.into()),
-    });
+        };
+        Some(chain.unwrap_or_else(|| ChainItem {
+            uid: "Script".into(),
+            data: ChainType::Script(tmpl::ITEM_SCRIPT.into()),
+        }))
+    } else {
+        None
+    };
 
     drop(profiles_arc);
 
@@ -297,27 +355,101 @@ async fn collect_profile_items() -> ProfileItems {
         rules_item,
         proxies_item,
         groups_item,
+        wasm_item,
         global_merge,
         global_script,
+        global_logs,
+        profile_uid: current_profile_uid.clone(),
         profile_name: name,
     }
 }
 
+/// 展开订阅与 Merge / Rules / Proxies / Groups 中的普通变量引用，未定义的变量记录到对应项的日志
+fn expand_profile_variables(profile: &mut ProfileItems, variables: &Variables) -> HashMap<String, ResultLog> {
+    let mut result_map: HashMap<String, ResultLog> = HashMap::new();
+    let mut record = |uid: &String, errors: Vec<String>| {
+        if !errors.is_empty() {
+            result_map
+                .entry(uid.clone())
+                .or_default()
+                .extend(errors.into_iter().map(|err| ("error".into(), err)));
+        }
+    };
+
+    let mut errors = vec![];
+    variables.expand_mapping(&mut profile.config, &mut errors);
+    record(&profile.profile_uid, errors);
+
+    let items = [
+        Some(&mut profile.merge_item),
+        Some(&mut profile.rules_item),
+        Some(&mut profile.proxies_item),
+        Some(&mut profile.groups_item),
+        profile.global_merge.as_mut(),
+    ];
+    for item in items.into_iter().flatten() {
+        let mut errors = vec![];
+        match &mut item.data {
+            ChainType::Merge(merge) => variables.expand_mapping(merge, &mut errors),
+            ChainType::Rules(seq) | ChainType::Proxies(seq) | ChainType::Groups(seq) => {
+                variables.expand_seq_map(seq, &mut errors);
+            }
+            ChainType::Script(_) | ChainType::Wasm(_) => {}
+        }
+        record(&item.uid, errors);
+    }
+
+    result_map
+}
+
+/// 按全局扩展的作用范围判断是否作用于当前订阅，并记录到增强日志
+fn global_in_scope(uid: &str, profiles: &IProfiles, current: &PrfItem, logs: &mut HashMap<String, ResultLog>) -> bool {
+    let scope = profiles
+        .get_item(uid)
+        .ok()
+        .and_then(|item| item.option.as_ref())
+        .and_then(|option| option.scope.clone())
+        .unwrap_or_default();
+
+    let (applied, log): (bool, (String, String)) = match scope.matches(current) {
+        Ok(true) => (
+            true,
+            ("info".into(), format!("applied, scope: {}", scope.describe()).into()),
+        ),
+        Ok(false) => (
+            false,
+            ("info".into(), format!("skipped, scope: {}", scope.describe()).into()),
+        ),
+        Err(err) => (false, ("error".into(), format!("skipped, {err:#}").into())),
+    };
+    logging!(debug, Type::Config, "global {uid} {}", log.1);
+    logs.insert(uid.into(), vec![log]);
+    applied
+}
+
 fn process_global_items(
     mut config: Mapping,
-    global_merge: ChainItem,
-    global_script: ChainItem,
+    global_merge: Option<ChainItem>,
+    global_script: Option<ChainItem>,
     profile_name: &String,
 ) -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
     let mut result_map = HashMap::new();
     let mut exists_keys = use_keys(&config).collect::<Vec<_>>();
 
-    if let ChainType::Merge(merge) = global_merge.data {
+    if let Some(ChainItem {
+        data: ChainType::Merge(merge),
+        ..
+    }) = global_merge
+    {
         exists_keys.extend(use_keys(&merge));
         config = use_merge(&merge, config.to_owned());
     }
 
-    if let ChainType::Script(script) = global_script.data {
+    if let Some(ChainItem {
+        uid,
+        data: ChainType::Script(script),
+    }) = global_script
+    {
         let mut logs = vec![];
         match use_script(script, &config, profile_name) {
             Ok((res_config, res_logs)) => {
@@ -327,7 +459,7 @@ fn process_global_items(
             }
             Err(err) => logs.push(("exception".into(), err.to_string().into())),
         }
-        result_map.insert(global_script.uid, logs);
+        result_map.insert(uid, logs);
     }
 
     (config, exists_keys, result_map)
@@ -343,6 +475,7 @@ fn process_profile_items(
     groups_item: ChainItem,
     merge_item: ChainItem,
     script_item: ChainItem,
+    wasm_item: Option<ChainItem>,
     profile_name: &String,
 ) -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
     if let ChainType::Rules(rules) = rules_item.data {
@@ -375,9 +508,136 @@ fn process_profile_items(
         result_map.insert(script_item.uid, logs);
     }
 
+    if let Some(ChainItem {
+        uid,
+        data: ChainType::Wasm(wasm),
+    }) = wasm_item
+    {
+        let mut logs = vec![];
+        match use_wasm(&wasm, &config, profile_name) {
+            Ok((res_config, res_logs)) => {
+                exists_keys.extend(use_keys(&res_config));
+                config = res_config;
+                logs.extend(res_logs);
+            }
+            Err(err) => logs.push(("exception".into(), err.to_string().into())),
+        }
+        result_map.insert(uid, logs);
+    }
+
     (config, exists_keys, result_map)
 }
 
+/// 依次执行全局与订阅扩展，输入未变化时直接复用缓存结果
+async fn process_chain_items(
+    profile: ProfileItems,
+    cache: Option<&EnhanceCache>,
+) -> (Mapping, Vec<String>, HashMap<String, ResultLog>) {
+    let ProfileItems {
+        config,
+        merge_item,
+        script_item,
+        rules_item,
+        proxies_item,
+        groups_item,
+        wasm_item,
+        global_merge,
+        global_script,
+        profile_name,
+        ..
+    } = profile;
+
+    let Some(cache) = cache else {
+        let (config, exists_keys, result_map) =
+            process_global_items(config, global_merge, global_script, &profile_name);
+        return process_profile_items(
+            config,
+            exists_keys,
+            result_map,
+            rules_item,
+            proxies_item,
+            groups_item,
+            merge_item,
+            script_item,
+            wasm_item,
+            &profile_name,
+        );
+    };
+
+    let global_digest = StageHasher::new(Stage::Global)
+        .mapping(&config)
+        .maybe_item(global_merge.as_ref())
+        .maybe_item(global_script.as_ref())
+        .str(&profile_name)
+        .finish();
+    let profile_digest = StageHasher::new(Stage::Profile)
+        .chain(&global_digest)
+        .item(&rules_item)
+        .item(&proxies_item)
+        .item(&groups_item)
+        .item(&merge_item)
+        .item(&script_item)
+        .maybe_item(wasm_item.as_ref())
+        .str(&profile_name)
+        .finish();
+
+    if let Some(output) = cache.get(Stage::Profile, &profile_digest).await {
+        return output.into_parts();
+    }
+
+    let (config, exists_keys, result_map) = match cache.get(Stage::Global, &global_digest).await {
+        Some(output) => output.into_parts(),
+        None => {
+            let output = StageOutput::from(process_global_items(config, global_merge, global_script, &profile_name));
+            cache.put(Stage::Global, global_digest, &output).await;
+            output.into_parts()
+        }
+    };
+
+    let output = StageOutput::from(process_profile_items(
+        config,
+        exists_keys,
+        result_map,
+        rules_item,
+        proxies_item,
+        groups_item,
+        merge_item,
+        script_item,
+        wasm_item,
+        &profile_name,
+    ));
+    cache.put(Stage::Profile, profile_digest, &output).await;
+    output.into_parts()
+}
+
+/// 以给定的全局与订阅扩展执行增强链，供基准测试对比缓存效果
+#[doc(hidden)]
+pub async fn process_chain_for_bench(
+    config: Mapping,
+    global_merge: Mapping,
+    global_script: &str,
+    profile_merge: Mapping,
+    profile_script: &str,
+    cache: Option<&EnhanceCache>,
+) -> Mapping {
+    let profile = ProfileItems {
+        config,
+        merge_item: ChainItem {
+            uid: "bench-merge".into(),
+            data: ChainType::Merge(profile_merge),
+        },
+        script_item: ChainItem::to_script("bench-script", profile_script),
+        global_merge: Some(ChainItem {
+            uid: "Merge".into(),
+            data: ChainType::Merge(global_merge),
+        }),
+        global_script: Some(ChainItem::to_script("Script", global_script)),
+        profile_name: "bench".into(),
+        ..Default::default()
+    };
+    process_chain_items(profile, cache).await.0
+}
+
 async fn merge_default_config(
     mut config: Mapping,
     clash_config: Mapping,
@@ -455,28 +715,53 @@ async fn merge_default_config(
     config
 }
 
-fn apply_builtin_scripts(mut config: Mapping, clash_core: Option<String>, enable_builtin: bool) -> Mapping {
-    if enable_builtin {
-        ChainItem::builtin()
-            .into_iter()
-            .filter(|(s, _)| s.is_support(clash_core.as_ref()))
-            .map(|(_, c)| c)
-            .for_each(|item| {
-                logging!(debug, Type::Core, "run builtin script {}", item.uid);
-                if let ChainType::Script(script) = item.data {
-                    match use_script(script, &config, &String::from("")) {
-                        Ok((res_config, _)) => {
-                            config = res_config;
-                        }
-                        Err(err) => {
-                            logging!(error, Type::Core, "builtin script error `{err}`");
-                        }
-                    }
-                }
-            });
+fn apply_builtin_transforms(
+    config: Mapping,
+    clash_core: Option<&String>,
+    enable_builtin: bool,
+    disabled_builtin: &[String],
+) -> Mapping {
+    if !enable_builtin {
+        return config;
     }
+    logging!(
+        debug,
+        Type::Core,
+        "run builtin transforms, disabled: {:?}",
+        disabled_builtin
+    );
+    use_builtin(config, clash_core, disabled_builtin)
+}
 
-    config
+/// 内建转换的输出只取决于合并后的配置、内核类型与禁用列表，命中缓存时跳过转换
+pub async fn apply_builtin_transforms_cached(
+    config: Mapping,
+    clash_core: Option<&String>,
+    enable_builtin: bool,
+    disabled_builtin: &[String],
+    cache: Option<&EnhanceCache>,
+) -> Mapping {
+    let Some(cache) = cache.filter(|_| enable_builtin) else {
+        return apply_builtin_transforms(config, clash_core, enable_builtin, disabled_builtin);
+    };
+
+    let mut hasher = StageHasher::new(Stage::Builtin)
+        .mapping(&config)
+        .str(clash_core.map(String::as_str).unwrap_or_default());
+    for uid in disabled_builtin {
+        hasher = hasher.str(uid);
+    }
+    let digest = hasher.finish();
+    if let Some(output) = cache.get(Stage::Builtin, &digest).await {
+        return output.config;
+    }
+
+    let output = StageOutput {
+        config: apply_builtin_transforms(config, clash_core, enable_builtin, disabled_builtin),
+        ..Default::default()
+    };
+    cache.put(Stage::Builtin, digest, &output).await;
+    output.config
 }
 
 fn cleanup_proxy_groups(mut config: Mapping) -> Mapping {
@@ -590,8 +875,8 @@ async fn apply_dns_settings(mut config: Mapping, enable_dns_settings: bool) -> M
 }
 
 /// Enhance mode
-/// 返回最终订阅、该订阅包含的键、和script执行的结果
-pub async fn enhance() -> (Mapping, HashSet<String>, HashMap<String, ResultLog>) {
+/// 返回最终订阅、该订阅包含的键、script执行的结果和机密值的位置
+pub async fn enhance() -> (Mapping, HashSet<String>, HashMap<String, ResultLog>, SecretValues) {
     // gather config values
     let cfg_vals = get_config_values().await;
     let ConfigValues {
@@ -599,42 +884,31 @@ pub async fn enhance() -> (Mapping, HashSet<String>, HashMap<String, ResultLog>)
         clash_core,
         enable_tun,
         enable_builtin,
+        disabled_builtin,
+        enable_cache,
+        variables,
         socks_enabled,
         http_enabled,
         enable_dns_settings,
+        lan_credentials,
         #[cfg(not(target_os = "windows"))]
         redir_enabled,
         #[cfg(target_os = "linux")]
         tproxy_enabled,
     } = cfg_vals;
 
-    // collect profile items
-    let profile = collect_profile_items().await;
-    let config = profile.config;
-    let merge_item = profile.merge_item;
-    let script_item = profile.script_item;
-    let rules_item = profile.rules_item;
-    let proxies_item = profile.proxies_item;
-    let groups_item = profile.groups_item;
-    let global_merge = profile.global_merge;
-    let global_script = profile.global_script;
-    let profile_name = profile.profile_name;
-
-    // process globals
-    let (config, exists_keys, result_map) = process_global_items(config, global_merge, global_script, &profile_name);
-
-    // process profile-specific items
-    let (config, exists_keys, result_map) = process_profile_items(
-        config,
-        exists_keys,
-        result_map,
-        rules_item,
-        proxies_item,
-        groups_item,
-        merge_item,
-        script_item,
-        &profile_name,
-    );
+    let cache = enable_cache.then(EnhanceCache::global);
+
+    // collect profile items, then process globals and profile-specific items
+    let mut profile = collect_profile_items().await;
+    let mut pre_logs = std::mem::take(&mut profile.global_logs);
+    for (uid, logs) in expand_profile_variables(&mut profile, &variables) {
+        pre_logs.entry(uid).or_default().extend(logs);
+    }
+    let (config, exists_keys, mut result_map) = process_chain_items(profile, cache).await;
+    for (uid, logs) in pre_logs {
+        result_map.entry(uid).or_default().splice(0..0, logs);
+    }
 
     // merge default clash config
     let config = merge_default_config(
@@ -649,21 +923,28 @@ pub async fn enhance() -> (Mapping, HashSet<String>, HashMap<String, ResultLog>)
     )
     .await;
 
-    // builtin scripts
-    let mut config = apply_builtin_scripts(config, clash_core, enable_builtin);
+    // builtin transforms
+    let mut config =
+        apply_builtin_transforms_cached(config, clash_core.as_ref(), enable_builtin, &disabled_builtin, cache).await;
 
     config = cleanup_proxy_groups(config);
 
     config = use_tun(config, enable_tun);
+    // 认证用户在内建转换之后注入，避免明文密码进入转换缓存
+    config = use_lan_auth(config, &lan_credentials);
     config = use_sort(config);
 
     // dns settings
     config = apply_dns_settings(config, enable_dns_settings).await;
 
+    // 机密变量在所有缓存阶段之后展开，明文不会写入增强缓存
+    let mut secret_values = variables.expand_secrets(&mut config);
+    record_lan_auth(&config, &lan_credentials, &mut secret_values);
+
     let mut exists_keys_set = HashSet::new();
     exists_keys_set.extend(exists_keys);
 
-    (config, exists_keys_set, result_map)
+    (config, exists_keys_set, result_map, secret_values)
 }
 
 #[allow(clippy::expect_used)]
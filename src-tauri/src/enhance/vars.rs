@@ -0,0 +1,339 @@
+use super::seq::SeqMap;
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::{Mapping, Value};
+use smartstring::alias::String;
+use std::{collections::HashMap, fmt};
+
+const REDACTED: &str = "******";
+
+/// 订阅与扩展中 `${NAME}` 引用的变量，`$${` 可用于输出字面量 `${`
+///
+/// 普通变量在增强链之前展开；机密变量的引用原样保留到缓存阶段之后再展开，
+/// 避免明文写入增强缓存
+#[derive(Default, Clone)]
+pub struct Variables {
+    values: HashMap<String, String>,
+    secrets: HashMap<String, String>,
+}
+
+// 只输出变量名，避免机密值出现在日志中
+impl fmt::Debug for Variables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.values.keys().chain(self.secrets.keys()))
+            .finish()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    Plain,
+    Secret,
+}
+
+/// 值在配置中的位置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PathSegment {
+    Key(Value),
+    Index(usize),
+}
+
+/// 被机密变量替换过的标量及其位置，用于展示运行时配置时只隐去这些值
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecretValues(Vec<(Vec<PathSegment>, Value)>);
+
+impl SecretValues {
+    pub fn push(&mut self, path: Vec<PathSegment>, value: Value) {
+        self.0.push((path, value));
+    }
+
+    /// 返回隐去机密值的副本，位置上的值已被后续步骤改动时不处理
+    pub fn redact(&self, config: &Mapping) -> Mapping {
+        let mut config = config.clone();
+        for (path, expected) in &self.0 {
+            if let Some(value) = value_at(&mut config, path)
+                && value == expected
+            {
+                *value = Value::from(REDACTED);
+            }
+        }
+        config
+    }
+}
+
+fn value_at<'a>(config: &'a mut Mapping, path: &[PathSegment]) -> Option<&'a mut Value> {
+    let (first, rest) = path.split_first()?;
+    let PathSegment::Key(key) = first else {
+        return None;
+    };
+    rest.iter()
+        .try_fold(config.get_mut(key)?, |value, segment| match segment {
+            PathSegment::Key(key) => value.as_mapping_mut()?.get_mut(key),
+            PathSegment::Index(index) => value.as_sequence_mut()?.get_mut(*index),
+        })
+}
+
+impl Variables {
+    /// 同名时机密变量优先
+    pub fn new(plain: Option<&HashMap<String, String>>, secrets: Option<&HashMap<String, String>>) -> Self {
+        let secrets: HashMap<String, String> = secrets.cloned().unwrap_or_default();
+        let values = plain
+            .into_iter()
+            .flatten()
+            .filter(|(k, _)| !secrets.contains_key(*k))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        Self { values, secrets }
+    }
+
+    pub fn expand_mapping(&self, mapping: &mut Mapping, errors: &mut Vec<String>) {
+        for (_, value) in mapping.iter_mut() {
+            self.expand_value(value, errors);
+        }
+    }
+
+    pub fn expand_seq_map(&self, seq: &mut SeqMap, errors: &mut Vec<String>) {
+        for value in seq.prepend.iter_mut().chain(seq.append.iter_mut()) {
+            self.expand_value(value, errors);
+        }
+    }
+
+    /// 展开普通变量，机密变量的引用与 `$${` 转义保留给 [`Self::expand_secrets`]
+    pub fn expand_value(&self, value: &mut Value, errors: &mut Vec<String>) {
+        match value {
+            Value::String(text) => {
+                if let Some(expanded) = self.expand_whole(text, Pass::Plain) {
+                    *value = expanded;
+                } else if let Some((expanded, _)) = self.expand_str(text, Pass::Plain, errors) {
+                    *text = expanded;
+                }
+            }
+            Value::Sequence(seq) => seq.iter_mut().for_each(|value| self.expand_value(value, errors)),
+            Value::Mapping(mapping) => self.expand_mapping(mapping, errors),
+            Value::Tagged(tagged) => self.expand_value(&mut tagged.value, errors),
+            _ => {}
+        }
+    }
+
+    /// 在最终配置上展开机密变量并处理转义，返回被替换的标量位置
+    pub fn expand_secrets(&self, config: &mut Mapping) -> SecretValues {
+        let mut found = SecretValues::default();
+        let mut path = vec![];
+        for (key, value) in config.iter_mut() {
+            path.push(PathSegment::Key(key.clone()));
+            self.expand_secret_value(value, &mut path, &mut found);
+            path.pop();
+        }
+        found
+    }
+
+    fn expand_secret_value(&self, value: &mut Value, path: &mut Vec<PathSegment>, found: &mut SecretValues) {
+        match value {
+            Value::String(text) => {
+                if let Some(expanded) = self.expand_whole(text, Pass::Secret) {
+                    *value = expanded;
+                    found.push(path.clone(), value.clone());
+                } else if let Some((expanded, substituted)) = self.expand_str(text, Pass::Secret, &mut vec![]) {
+                    *text = expanded;
+                    if substituted {
+                        found.push(path.clone(), value.clone());
+                    }
+                }
+            }
+            Value::Sequence(seq) => {
+                for (index, value) in seq.iter_mut().enumerate() {
+                    path.push(PathSegment::Index(index));
+                    self.expand_secret_value(value, path, found);
+                    path.pop();
+                }
+            }
+            Value::Mapping(mapping) => {
+                for (key, value) in mapping.iter_mut() {
+                    path.push(PathSegment::Key(key.clone()));
+                    self.expand_secret_value(value, path, found);
+                    path.pop();
+                }
+            }
+            Value::Tagged(tagged) => self.expand_secret_value(&mut tagged.value, path, found),
+            _ => {}
+        }
+    }
+
+    fn lookup(&self, name: &str, pass: Pass) -> Option<&String> {
+        match pass {
+            Pass::Plain => self.values.get(name),
+            Pass::Secret => self.secrets.get(name),
+        }
+    }
+
+    /// 整个值只有一个引用时保留数字与布尔类型，便于用于端口等字段
+    fn expand_whole(&self, text: &str, pass: Pass) -> Option<Value> {
+        let name = text.strip_prefix("${")?.strip_suffix('}')?;
+        let raw = self.lookup(name, pass)?;
+        match serde_yaml_ng::from_str::<Value>(raw) {
+            Ok(value @ (Value::Number(_) | Value::Bool(_))) => Some(value),
+            _ => Some(Value::String(raw.to_string())),
+        }
+    }
+
+    /// 返回展开后的文本及是否替换过变量
+    fn expand_str(&self, text: &str, pass: Pass, errors: &mut Vec<String>) -> Option<(std::string::String, bool)> {
+        if !text.contains("${") {
+            return None;
+        }
+
+        let mut out = std::string::String::with_capacity(text.len());
+        let mut substituted = false;
+        let mut rest = text;
+        while let Some(pos) = rest.find("${") {
+            let (head, tail) = rest.split_at(pos);
+            let after = &tail[2..];
+
+            if let Some(head) = head.strip_suffix('$') {
+                out.push_str(head);
+                // 转义在最后一步才还原，避免被机密变量展开
+                out.push_str(if pass == Pass::Secret { "${" } else { "$${" });
+                rest = after;
+                continue;
+            }
+            out.push_str(head);
+
+            match after.split_once('}') {
+                Some((name, remain)) if is_valid_name(name) => {
+                    if let Some(value) = self.lookup(name, pass) {
+                        out.push_str(value);
+                        substituted = true;
+                    } else {
+                        if pass == Pass::Plain && !self.secrets.contains_key(name) {
+                            let error: String = format!("undefined variable `{name}`").into();
+                            if !errors.contains(&error) {
+                                errors.push(error);
+                            }
+                        }
+                        out.push_str(&tail[..name.len() + 3]);
+                    }
+                    rest = remain;
+                }
+                _ => {
+                    out.push_str("${");
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        Some((out, substituted))
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::Variables;
+    use serde_yaml_ng::Mapping;
+    use smartstring::alias::String;
+    use std::collections::HashMap;
+
+    fn variables() -> Variables {
+        let plain = HashMap::from([
+            (String::from("DNS"), String::from("10.0.0.53")),
+            (String::from("PORT"), String::from("7890")),
+        ]);
+        let secrets = HashMap::from([
+            (String::from("PASS"), String::from("s3cret")),
+            (String::from("ON"), String::from("true")),
+        ]);
+        Variables::new(Some(&plain), Some(&secrets))
+    }
+
+    fn mapping(yaml: &str) -> Mapping {
+        serde_yaml_ng::from_str(yaml).expect("Failed to parse test YAML")
+    }
+
+    #[test]
+    fn expand_references() {
+        let mut config = mapping(
+            r"
+dns:
+  nameserver: ['${DNS}', 'tls://${DNS}:853']
+port: ${PORT}
+proxies:
+  - { name: a, password: '${PASS}', note: '$${PASS} and ${ not a var' }
+",
+        );
+        let mut errors = vec![];
+        let variables = variables();
+        variables.expand_mapping(&mut config, &mut errors);
+        assert!(errors.is_empty());
+
+        // 机密变量在缓存阶段之前保持原样
+        let deferred = mapping(
+            r"
+dns:
+  nameserver: ['10.0.0.53', 'tls://10.0.0.53:853']
+port: 7890
+proxies:
+  - { name: a, password: '${PASS}', note: '$${PASS} and ${ not a var' }
+",
+        );
+        assert_eq!(config, deferred);
+
+        variables.expand_secrets(&mut config);
+        let expected = mapping(
+            r"
+dns:
+  nameserver: ['10.0.0.53', 'tls://10.0.0.53:853']
+port: 7890
+proxies:
+  - { name: a, password: 's3cret', note: '${PASS} and ${ not a var' }
+",
+        );
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn undefined_reference_is_reported() {
+        let mut config = mapping("a: '${MISSING}'\nb: 'x-${MISSING}-${DNS}'");
+        let mut errors = vec![];
+        variables().expand_mapping(&mut config, &mut errors);
+
+        assert_eq!(config, mapping("a: '${MISSING}'\nb: 'x-${MISSING}-10.0.0.53'"));
+        assert_eq!(errors, vec![String::from("undefined variable `MISSING`")]);
+    }
+
+    #[test]
+    fn redact_only_substituted_values() {
+        let mut config = mapping(
+            r"
+allow-lan: true
+proxies:
+  - { name: a, password: '${PASS}', udp: '${ON}', note: s3cret }
+",
+        );
+        let found = variables().expand_secrets(&mut config);
+        assert_eq!(
+            found.redact(&config),
+            mapping(
+                r"
+allow-lan: true
+proxies:
+  - { name: a, password: '******', udp: '******', note: s3cret }
+",
+            )
+        );
+
+        // 值已被后续步骤修改时不再隐去
+        config.insert("allow-lan".into(), false.into());
+        if let Some(proxies) = config.get_mut("proxies").and_then(|v| v.as_sequence_mut()) {
+            proxies.clear();
+        }
+        assert_eq!(found.redact(&config), config);
+    }
+}
@@ -0,0 +1,268 @@
+//! WebAssembly enhance plugins
+//!
+//! A plugin is a sandboxed module that talks to the host through linear memory.
+//!
+//! Exports required from the module:
+//! - `memory`: the linear memory
+//! - `verge_alloc(len: i32) -> i32`: reserve `len` bytes and return the pointer
+//! - `verge_main(ptr: i32, len: i32) -> i64`: receive the input JSON and return the
+//!   output JSON location packed as `(ptr << 32) | len`
+//!
+//! Imports provided by the host:
+//! - `env.verge_log(level_ptr: i32, level_len: i32, data_ptr: i32, data_len: i32)`
+//!
+//! Input is `{"config": {...}, "profileName": "..."}`, output is the transformed config object.
+
+use super::use_lowercase;
+use anyhow::{Context as _, Result, anyhow, bail};
+use serde_yaml_ng::Mapping;
+use smartstring::alias::String;
+use wasmi::{Caller, Config, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+const MAX_OUTPUTS: usize = 1000;
+const MAX_OUTPUT_SIZE: usize = 1024 * 1024; // 1MB
+const MAX_JSON_SIZE: usize = 10 * 1024 * 1024; // 10MB
+const MAX_MODULE_SIZE: usize = 16 * 1024 * 1024; // 16MB
+const MAX_MEMORY_SIZE: usize = 256 * 1024 * 1024; // 256MB
+const MAX_FUEL: u64 = 2_000_000_000;
+
+const EXPORT_MEMORY: &str = "memory";
+const EXPORT_ALLOC: &str = "verge_alloc";
+const EXPORT_MAIN: &str = "verge_main";
+
+struct HostState {
+    limits: StoreLimits,
+    outputs: Vec<(String, String)>,
+    output_size: usize,
+}
+
+impl HostState {
+    fn new() -> Self {
+        Self {
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY_SIZE)
+                .instances(1)
+                .memories(1)
+                .tables(1)
+                .build(),
+            outputs: vec![],
+            output_size: 0,
+        }
+    }
+
+    fn push_log(&mut self, level: std::string::String, data: std::string::String) -> Result<()> {
+        if self.outputs.len() >= MAX_OUTPUTS {
+            bail!("Maximum number of log outputs exceeded");
+        }
+
+        let new_size = self.output_size + level.len() + data.len();
+        if new_size > MAX_OUTPUT_SIZE {
+            bail!("Maximum output size exceeded");
+        }
+        self.output_size = new_size;
+        self.outputs.push((level.into(), data.into()));
+        Ok(())
+    }
+}
+
+fn new_engine() -> Engine {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    Engine::new(&config)
+}
+
+/// 编译并检查模块导出，用于保存前的校验
+pub fn validate_wasm(wasm: &[u8]) -> Result<()> {
+    compile(&new_engine(), wasm).map(|_| ())
+}
+
+fn compile(engine: &Engine, wasm: &[u8]) -> Result<Module> {
+    if wasm.len() > MAX_MODULE_SIZE {
+        bail!("Wasm module size exceeds maximum allowed size");
+    }
+
+    let module = Module::new(engine, wasm).map_err(|e| anyhow!("Wasm module compile failed: {e}"))?;
+
+    for name in [EXPORT_MEMORY, EXPORT_ALLOC, EXPORT_MAIN] {
+        if module.get_export(name).is_none() {
+            bail!("Wasm module must export `{name}`");
+        }
+    }
+
+    Ok(module)
+}
+
+fn read_memory(memory: &Memory, store: impl wasmi::AsContext, ptr: i32, len: i32) -> Result<Vec<u8>> {
+    let (ptr, len) = (usize::try_from(ptr)?, usize::try_from(len)?);
+    if len > MAX_JSON_SIZE {
+        bail!("Wasm memory read exceeds maximum allowed size");
+    }
+    let mut buf = vec![0u8; len];
+    memory
+        .read(store, ptr, &mut buf)
+        .map_err(|e| anyhow!("Wasm memory access out of bounds: {e}"))?;
+    Ok(buf)
+}
+
+fn link(engine: &Engine) -> Result<Linker<HostState>> {
+    let mut linker = Linker::<HostState>::new(engine);
+    linker.func_wrap(
+        "env",
+        "verge_log",
+        |caller: Caller<'_, HostState>,
+         level_ptr: i32,
+         level_len: i32,
+         data_ptr: i32,
+         data_len: i32|
+         -> Result<(), wasmi::Error> {
+            let memory = match caller.get_export(EXPORT_MEMORY) {
+                Some(Extern::Memory(memory)) => memory,
+                _ => return Err(wasmi::Error::new("failed to find memory export")),
+            };
+            let read_str = |ptr, len| {
+                read_memory(&memory, &caller, ptr, len)
+                    .map(|buf| std::string::String::from_utf8_lossy(&buf).into_owned())
+                    .map_err(|e| wasmi::Error::new(e.to_string()))
+            };
+            let level = read_str(level_ptr, level_len)?;
+            let data = read_str(data_ptr, data_len)?;
+
+            let mut caller = caller;
+            caller
+                .data_mut()
+                .push_log(level, data)
+                .map_err(|e| wasmi::Error::new(e.to_string()))
+        },
+    )?;
+    Ok(linker)
+}
+
+pub fn use_wasm(wasm: &[u8], config: &Mapping, name: &String) -> Result<(Mapping, Vec<(String, String)>)> {
+    run_wasm(wasm, config, name, MAX_FUEL)
+}
+
+fn run_wasm(wasm: &[u8], config: &Mapping, name: &String, fuel: u64) -> Result<(Mapping, Vec<(String, String)>)> {
+    let engine = new_engine();
+    let module = compile(&engine, wasm)?;
+    let linker = link(&engine)?;
+
+    let mut store = Store::new(&engine, HostState::new());
+    store.limiter(|state| &mut state.limits);
+    store
+        .set_fuel(fuel)
+        .map_err(|e| anyhow!("Wasm fuel setup failed: {e}"))?;
+
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| anyhow!("Wasm module instantiate failed: {e}"))?;
+
+    let memory = instance
+        .get_memory(&store, EXPORT_MEMORY)
+        .ok_or_else(|| anyhow!("Wasm module must export `{EXPORT_MEMORY}`"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&store, EXPORT_ALLOC)?;
+    let main = instance.get_typed_func::<(i32, i32), i64>(&store, EXPORT_MAIN)?;
+
+    let input = serde_json::to_vec(&serde_json::json!({
+        "config": use_lowercase(config),
+        "profileName": name.as_str(),
+    }))?;
+    if input.len() > MAX_JSON_SIZE {
+        bail!("Configuration size exceeds maximum allowed size");
+    }
+    let input_len = i32::try_from(input.len())?;
+
+    let input_ptr = alloc
+        .call(&mut store, input_len)
+        .map_err(|e| anyhow!("Wasm `{EXPORT_ALLOC}` failed: {e}"))?;
+    memory
+        .write(&mut store, usize::try_from(input_ptr)?, &input)
+        .map_err(|e| anyhow!("Wasm memory access out of bounds: {e}"))?;
+
+    let packed = match main.call(&mut store, (input_ptr, input_len)) {
+        Ok(packed) => packed,
+        Err(err) => {
+            let mut outputs = std::mem::take(&mut store.data_mut().outputs);
+            outputs.push(("exception".into(), format!("Wasm execution failed: {err}").into()));
+            return Ok((config.to_owned(), outputs));
+        }
+    };
+
+    let (output_ptr, output_len) = ((packed >> 32) as i32, (packed & 0xffff_ffff) as i32);
+    let output = read_memory(&memory, &store, output_ptr, output_len)?;
+    let outputs = std::mem::take(&mut store.data_mut().outputs);
+
+    let res = serde_json::from_slice::<Mapping>(&output).context("Wasm `verge_main` should return object");
+    match res {
+        Ok(config) => Ok((use_lowercase(&config), outputs)),
+        Err(err) => {
+            let mut outputs = outputs;
+            outputs.push(("exception".into(), err.to_string().into()));
+            Ok((config.to_owned(), outputs))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{run_wasm, use_wasm, validate_wasm};
+    use smartstring::alias::String;
+
+    // 原样返回输入中的 config 字段，并输出一条日志
+    const ECHO_PLUGIN: &str = r#"
+    (module
+      (import "env" "verge_log" (func $log (param i32 i32 i32 i32)))
+      (memory (export "memory") 1)
+      (data (i32.const 0) "info")
+      (data (i32.const 8) "hello")
+      (global $heap (mut i32) (i32.const 1024))
+      (func (export "verge_alloc") (param $len i32) (result i32)
+        (local $ptr i32)
+        (local.set $ptr (global.get $heap))
+        (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+        (local.get $ptr))
+      (func (export "verge_main") (param $ptr i32) (param $len i32) (result i64)
+        (call $log (i32.const 0) (i32.const 4) (i32.const 8) (i32.const 5))
+        ;; skip `{"config":` and the trailing `,"profileName":""}`
+        (i64.or
+          (i64.shl (i64.extend_i32_u (i32.add (local.get $ptr) (i32.const 10))) (i64.const 32))
+          (i64.extend_i32_u (i32.sub (local.get $len) (i32.const 28))))))
+    "#;
+
+    const LOOP_PLUGIN: &str = r#"
+    (module
+      (memory (export "memory") 1)
+      (func (export "verge_alloc") (param i32) (result i32) (i32.const 0))
+      (func (export "verge_main") (param i32 i32) (result i64)
+        (loop $l (br $l))
+        (i64.const 0)))
+    "#;
+
+    #[test]
+    fn test_wasm_echo() {
+        let wasm = wat::parse_str(ECHO_PLUGIN).expect("Failed to parse test wat");
+        let config = serde_yaml_ng::from_str("rules:\n  - MATCH,DIRECT\n").expect("Failed to parse test YAML");
+
+        let (result, logs) = use_wasm(&wasm, &config, &String::new()).expect("Wasm execution should succeed");
+        assert_eq!(result, config);
+        assert_eq!(logs, vec![("info".into(), "hello".into())]);
+    }
+
+    #[test]
+    fn test_wasm_fuel_limit() {
+        let wasm = wat::parse_str(LOOP_PLUGIN).expect("Failed to parse test wat");
+        let config = serde_yaml_ng::from_str("test: value").expect("Failed to parse test YAML");
+
+        let (result, logs) =
+            run_wasm(&wasm, &config, &String::new(), 100_000).expect("Fuel exhaustion should not bail");
+        assert_eq!(result, config);
+        assert!(logs.iter().any(|(level, _)| level == "exception"));
+    }
+
+    #[test]
+    fn test_wasm_missing_exports() {
+        let wasm = wat::parse_str("(module (memory (export \"memory\") 1))").expect("Failed to parse test wat");
+        assert!(validate_wasm(&wasm).is_err());
+    }
+}
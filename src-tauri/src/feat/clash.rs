@@ -11,7 +11,7 @@ use smartstring::alias::String;
 
 /// Restart the Clash core
 pub async fn restart_clash_core() {
-    match CoreManager::global().restart_core().await {
+    match CoreManager::global().graceful_restart_core().await {
         Ok(_) => {
             handle::Handle::refresh_clash();
             handle::Handle::notice_message("set_config::ok", "ok");
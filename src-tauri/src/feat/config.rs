@@ -1,9 +1,11 @@
 use crate::{
-    config::{Config, IVerge},
-    core::{CoreManager, autostart, handle, hotkey, logger::Logger, sysopt, tray},
-    module::{auto_backup::AutoBackupManager, lightweight},
+    config::{Config, IVerge, SecretStrength, generate_secret},
+    core::{CoreManager, autostart, core_log::CORE_LOGS, handle, hotkey, logger::Logger, sysopt, tray},
+    enhance::cache::EnhanceCache,
+    module::{auto_backup::AutoBackupManager, core_watchdog::CoreWatchdog, lightweight},
+    utils::server,
 };
-use anyhow::Result;
+use anyhow::{Result, bail};
 use bitflags::bitflags;
 use clash_verge_draft::SharedDraft;
 use clash_verge_logging::{Type, logging, logging_error};
@@ -14,10 +16,16 @@ pub async fn patch_clash(patch: &Mapping) -> Result<()> {
     Config::clash().await.edit_draft(|d| d.patch_config(patch));
 
     let res = {
-        // 激活订阅
-        if patch.get("secret").is_some() || patch.get("external-controller").is_some() {
+        // 密钥变更走热重载，应用自身经 IPC 连接内核不受影响
+        if patch.get("external-controller").is_some() {
+            // 快照须取自重新生成前的运行时配置
+            let manager = CoreManager::global();
+            let mut snapshot = manager.snapshot_selections().await;
+            if patch.contains_key("mode") {
+                snapshot = snapshot.map(|snapshot| snapshot.without_mode());
+            }
             Config::generate().await?;
-            CoreManager::global().restart_core().await?;
+            manager.restart_and_restore(snapshot).await?;
         } else {
             if patch.get("mode").is_some() {
                 logging_error!(Type::Tray, tray::Tray::global().update_menu().await);
@@ -40,6 +48,12 @@ pub async fn patch_clash(patch: &Mapping) -> Result<()> {
             // 分离数据获取和异步调用
             let clash_data = Config::clash().await.data_arc();
             clash_data.save_config().await?;
+            if patch.contains_key("allow-lan") {
+                server::refresh_lan_pac_server().await;
+            }
+            if patch.contains_key("secret") || patch.contains_key("external-controller") {
+                check_controller_secret().await;
+            }
             Ok(())
         }
         Err(err) => {
@@ -49,6 +63,65 @@ pub async fn patch_clash(patch: &Mapping) -> Result<()> {
     }
 }
 
+/// 生成新的随机密钥并热更新到内核，返回新密钥
+///
+/// 不经过 `update_config` 的防抖，内核未换用新密钥时不保存也不返回
+pub async fn rotate_clash_secret() -> Result<String> {
+    let secret = generate_secret()?;
+    let mut patch = Mapping::new();
+    patch.insert("secret".into(), secret.as_str().into());
+    Config::clash().await.edit_draft(|d| d.patch_config(&patch));
+
+    let applied = async {
+        Config::generate().await?;
+        let (applied, message) = CoreManager::global().apply_generate_config().await?;
+        if !applied {
+            bail!("config validation failed: {message}");
+        }
+        <Result<()>>::Ok(())
+    }
+    .await;
+    if let Err(err) = applied {
+        Config::clash().await.discard();
+        return Err(err);
+    }
+
+    Config::clash().await.apply();
+    Config::clash().await.data_arc().save_config().await?;
+    handle::Handle::refresh_clash();
+    check_controller_secret().await;
+    logging!(info, Type::Config, "Controller secret rotated");
+    Ok(secret)
+}
+
+/// 外部控制器开启且密钥为默认或弱密钥时提示用户
+pub async fn check_controller_secret() {
+    let enabled = Config::verge()
+        .await
+        .latest_arc()
+        .enable_external_controller
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+    let secret = Config::clash()
+        .await
+        .latest_arc()
+        .get_client_info()
+        .secret
+        .unwrap_or_default();
+    let strength = SecretStrength::assess(&secret);
+    if !strength.is_strong() {
+        logging!(
+            warn,
+            Type::Config,
+            "External controller is exposed with a {} secret",
+            strength.as_str()
+        );
+        handle::Handle::notice_message("external_controller::weak_secret", strength.as_str());
+    }
+}
+
 // Define update flags as bitflags for better performance
 bitflags! {
      #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -76,11 +149,13 @@ bitflags! {
 
 fn determine_update_flags(patch: &IVerge) -> UpdateFlags {
     let tun_mode = patch.enable_tun_mode;
+    let lan_sharing_users = &patch.lan_sharing_users;
     let auto_launch = patch.enable_auto_launch;
     let system_proxy = patch.enable_system_proxy;
     let pac = patch.proxy_auto_config;
     let pac_content = &patch.pac_file_content;
     let proxy_bypass = &patch.system_proxy_bypass;
+    let proxy_bypass_presets = &patch.system_proxy_bypass_presets;
     let language = &patch.language;
     let mixed_port = patch.verge_mixed_port;
     #[cfg(target_os = "macos")]
@@ -116,6 +191,10 @@ fn determine_update_flags(patch: &IVerge) -> UpdateFlags {
     let log_level = &patch.app_log_level;
     let log_max_size = patch.app_log_max_size;
     let log_max_count = patch.app_log_max_count;
+    let external_core = patch.enable_external_core.is_some()
+        || patch.external_core_url.is_some()
+        || patch.external_core_socket.is_some()
+        || patch.external_core_secret.is_some();
 
     #[cfg(target_os = "windows")]
     let restart_core_needed = socks_enabled.is_some()
@@ -123,14 +202,16 @@ fn determine_update_flags(patch: &IVerge) -> UpdateFlags {
         || socks_port.is_some()
         || http_port.is_some()
         || mixed_port.is_some()
-        || enable_external_controller.is_some();
+        || enable_external_controller.is_some()
+        || external_core;
     #[cfg(not(target_os = "windows"))]
     let mut restart_core_needed = socks_enabled.is_some()
         || http_enabled.is_some()
         || socks_port.is_some()
         || http_port.is_some()
         || mixed_port.is_some()
-        || enable_external_controller.is_some();
+        || enable_external_controller.is_some()
+        || external_core;
     #[cfg(not(target_os = "windows"))]
     {
         restart_core_needed |= redir_enabled.is_some() || redir_port.is_some();
@@ -147,6 +228,12 @@ fn determine_update_flags(patch: &IVerge) -> UpdateFlags {
     if tun_mode.is_some() {
         update_flags.insert(UpdateFlags::CLASH_CONFIG | UpdateFlags::GROUP_SYS_TRAY);
     }
+    if lan_sharing_users.is_some() {
+        update_flags.insert(UpdateFlags::CLASH_CONFIG);
+    }
+    if external_core {
+        update_flags.insert(UpdateFlags::SYS_PROXY);
+    }
     if enable_global_hotkey.is_some() || home_cards.is_some() {
         update_flags.insert(UpdateFlags::VERGE_CONFIG);
     }
@@ -157,6 +244,7 @@ fn determine_update_flags(patch: &IVerge) -> UpdateFlags {
         update_flags.insert(UpdateFlags::SYS_PROXY | UpdateFlags::GROUP_SYS_TRAY);
     }
     if proxy_bypass.is_some()
+        || proxy_bypass_presets.is_some()
         || pac_content.is_some()
         || pac.is_some()
         || enable_proxy_guard.is_some()
@@ -204,8 +292,10 @@ fn determine_update_flags(patch: &IVerge) -> UpdateFlags {
 async fn process_terminated_flags(update_flags: UpdateFlags, patch: &IVerge) -> Result<()> {
     // Process updates based on flags
     if update_flags.contains(UpdateFlags::RESTART_CORE) {
+        let manager = CoreManager::global();
+        let snapshot = manager.snapshot_selections().await;
         Config::generate().await?;
-        CoreManager::global().restart_core().await?;
+        manager.restart_and_restore(snapshot).await?;
     }
     if update_flags.contains(UpdateFlags::CLASH_CONFIG) {
         CoreManager::global().update_config().await?;
@@ -281,7 +371,14 @@ pub async fn patch_verge(patch: &IVerge, not_save_file: bool) -> Result<()> {
         return Err(err);
     }
     Config::verge().await.apply();
+    if patch.enable_enhance_cache == Some(false) {
+        EnhanceCache::global().clear().await;
+    }
     logging_error!(Type::Backup, AutoBackupManager::global().refresh_settings().await);
+    logging_error!(Type::Core, CoreWatchdog::global().refresh_settings().await);
+    if let Some(size) = patch.core_log_buffer_size {
+        CORE_LOGS.set_capacity(size);
+    }
     if !not_save_file {
         // 分离数据获取和异步调用
         let verge_data = Config::verge().await.data_arc();
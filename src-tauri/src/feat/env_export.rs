@@ -0,0 +1,461 @@
+use crate::{
+    core::{handle, sysopt},
+    utils::bypass::BypassFormat,
+};
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Map, Value, json};
+use smartstring::alias::String;
+use std::{
+    env,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use tauri::Manager as _;
+
+/// 代理环境的导出目标，对应 `env_type` 的取值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvTarget {
+    Bash,
+    Zsh,
+    Fish,
+    Nushell,
+    PowerShell,
+    Cmd,
+    DockerDaemon,
+    DockerClient,
+    Git,
+    Npm,
+    Pip,
+    Cargo,
+    Systemd,
+}
+
+impl EnvTarget {
+    pub fn parse(name: &str) -> Option<Self> {
+        let target = match name {
+            "bash" => Self::Bash,
+            "zsh" => Self::Zsh,
+            "fish" => Self::Fish,
+            "nushell" => Self::Nushell,
+            "powershell" => Self::PowerShell,
+            "cmd" => Self::Cmd,
+            "docker-daemon" => Self::DockerDaemon,
+            "docker-client" => Self::DockerClient,
+            "git" => Self::Git,
+            "npm" => Self::Npm,
+            "pip" => Self::Pip,
+            "cargo" => Self::Cargo,
+            "systemd" => Self::Systemd,
+            _ => return None,
+        };
+        Some(target)
+    }
+
+    pub const fn platform_default() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::PowerShell
+        } else {
+            Self::Bash
+        }
+    }
+}
+
+/// 导出的代理地址，`no_proxy` 为空时不导出
+#[derive(Debug, Clone)]
+pub struct ProxyEnv {
+    pub http: String,
+    pub socks: String,
+    pub no_proxy: String,
+}
+
+impl ProxyEnv {
+    pub async fn current() -> Self {
+        // 与系统代理使用相同的入口，包括内核的 mixed-port 与外部内核
+        let (host, port) = sysopt::proxy_endpoint().await;
+        let env_ip = env::var("CLASH_VERGE_REV_IP").ok();
+        let ip = env_ip.as_deref().unwrap_or(host.as_str());
+
+        Self {
+            http: format!("http://{ip}:{port}").into(),
+            socks: format!("socks5://{ip}:{port}").into(),
+            no_proxy: sysopt::bypass_list().await.render(BypassFormat::NoProxy),
+        }
+    }
+
+    fn shell_vars(&self) -> Vec<(&'static str, &str)> {
+        let mut vars = vec![
+            ("http_proxy", self.http.as_str()),
+            ("https_proxy", self.http.as_str()),
+            ("all_proxy", self.socks.as_str()),
+        ];
+        if !self.no_proxy.is_empty() {
+            vars.push(("no_proxy", self.no_proxy.as_str()));
+        }
+        vars
+    }
+}
+
+/// 生成可复制执行的导出文本，`unset` 时生成对应的清除命令
+pub fn env_export_text(target: EnvTarget, env: &ProxyEnv, unset: bool) -> String {
+    let vars = env.shell_vars();
+    let names = vars.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    let text = match (target, unset) {
+        (EnvTarget::Bash | EnvTarget::Zsh, false) => {
+            let assigns = vars.iter().map(|(name, value)| format!("{name}=\"{value}\""));
+            format!("export {}", assigns.collect::<Vec<_>>().join(" "))
+        }
+        (EnvTarget::Bash | EnvTarget::Zsh, true) => format!("unset {}", names.join(" ")),
+        (EnvTarget::Fish, false) => join_lines(
+            vars.iter().map(|(name, value)| format!("set -gx {name} \"{value}\"")),
+            "; ",
+        ),
+        (EnvTarget::Fish, true) => join_lines(names.iter().map(|name| format!("set -e {name}")), "; "),
+        (EnvTarget::Nushell, false) => {
+            let fields = vars.iter().map(|(name, value)| format!("{name}: \"{value}\""));
+            format!("load-env {{ {} }}", fields.collect::<Vec<_>>().join(", "))
+        }
+        (EnvTarget::Nushell, true) => format!("hide-env --ignore-errors {}", names.join(" ")),
+        (EnvTarget::PowerShell, false) => join_lines(
+            vars.iter()
+                .map(|(name, value)| format!("$env:{}=\"{value}\"", name.to_uppercase())),
+            "; ",
+        ),
+        (EnvTarget::PowerShell, true) => {
+            let items = names.iter().map(|name| format!("Env:{}", name.to_uppercase()));
+            format!(
+                "Remove-Item {} -ErrorAction SilentlyContinue",
+                items.collect::<Vec<_>>().join(", ")
+            )
+        }
+        (EnvTarget::Cmd, false) => join_lines(vars.iter().map(|(name, value)| format!("set {name}={value}")), "\r\n"),
+        (EnvTarget::Cmd, true) => join_lines(names.iter().map(|name| format!("set {name}=")), "\r\n"),
+        // git 的 http.proxy 同时作用于 https 地址
+        (EnvTarget::Git, false) => format!("git config --global http.proxy {}", env.http),
+        (EnvTarget::Git, true) => "git config --global --unset http.proxy".into(),
+        (EnvTarget::Npm | EnvTarget::Pip | EnvTarget::Systemd, _) => config_commands(target, env, unset),
+        (EnvTarget::Cargo, false) => format!("[http]\nproxy = \"{}\"", env.http),
+        (EnvTarget::Cargo, true) => "# remove `proxy` from the [http] table of ~/.cargo/config.toml".into(),
+        (EnvTarget::DockerDaemon | EnvTarget::DockerClient, _) => {
+            let (path, value) = json_setting(target, env, unset);
+            let fragment = path
+                .iter()
+                .rev()
+                .fold(value.unwrap_or_else(|| json!({})), |inner, key| {
+                    Value::Object(Map::from_iter([((*key).into(), inner)]))
+                });
+            serde_json::to_string_pretty(&fragment).unwrap_or_default()
+        }
+    };
+    text.into()
+}
+
+fn join_lines(lines: impl Iterator<Item = std::string::String>, separator: &str) -> std::string::String {
+    lines.collect::<Vec<_>>().join(separator)
+}
+
+fn config_commands(target: EnvTarget, env: &ProxyEnv, unset: bool) -> std::string::String {
+    let settings = ini_settings(target, env, unset);
+    let lines = settings.iter().map(|setting| {
+        let key = setting.key;
+        match (target, &setting.value) {
+            (EnvTarget::Npm, Some(value)) => format!("npm config set {key} {value}"),
+            (EnvTarget::Npm, None) => format!("npm config delete {key}"),
+            (EnvTarget::Pip, Some(value)) => format!("pip config set global.{key} {value}"),
+            (EnvTarget::Pip, None) => format!("pip config unset global.{key}"),
+            (_, Some(value)) => format!("systemctl --user set-environment {key}={value}"),
+            (_, None) => format!("systemctl --user unset-environment {key}"),
+        }
+    });
+    join_lines(lines, "\n")
+}
+
+/// 按键合并到 INI 类配置文件中的一项，`value` 为 `None` 时删除
+#[derive(Debug)]
+struct IniSetting {
+    section: Option<&'static str>,
+    key: &'static str,
+    value: Option<std::string::String>,
+}
+
+fn ini_settings(target: EnvTarget, env: &ProxyEnv, unset: bool) -> Vec<IniSetting> {
+    let entries: Vec<(Option<&'static str>, &'static str, std::string::String)> = match target {
+        EnvTarget::Git => vec![(Some("http"), "proxy", env.http.to_string())],
+        EnvTarget::Npm => vec![
+            (None, "proxy", env.http.to_string()),
+            (None, "https-proxy", env.http.to_string()),
+            (None, "noproxy", env.no_proxy.to_string()),
+        ],
+        EnvTarget::Pip => vec![(Some("global"), "proxy", env.http.to_string())],
+        EnvTarget::Cargo => vec![(Some("http"), "proxy", format!("\"{}\"", env.http))],
+        EnvTarget::Systemd => env
+            .shell_vars()
+            .into_iter()
+            .map(|(name, value)| (None, name, value.to_string()))
+            .collect(),
+        _ => vec![],
+    };
+    entries
+        .into_iter()
+        .filter(|(_, _, value)| unset || !value.is_empty())
+        .map(|(section, key, value)| IniSetting {
+            section,
+            key,
+            value: (!unset).then_some(value),
+        })
+        .collect()
+}
+
+fn json_setting(target: EnvTarget, env: &ProxyEnv, unset: bool) -> (&'static [&'static str], Option<Value>) {
+    let (path, value): (&'static [&'static str], Value) = match target {
+        EnvTarget::DockerClient => (
+            &["proxies", "default"],
+            json!({ "httpProxy": env.http, "httpsProxy": env.http, "noProxy": env.no_proxy }),
+        ),
+        _ => (
+            &["proxies"],
+            json!({ "http-proxy": env.http, "https-proxy": env.http, "no-proxy": env.no_proxy }),
+        ),
+    };
+    (path, (!unset).then_some(value))
+}
+
+/// 目标默认写入的配置文件，终端类目标需要指定路径
+pub fn default_env_file(target: EnvTarget) -> Result<PathBuf> {
+    let path = handle::Handle::app_handle().path();
+    let home = path.home_dir()?;
+    let file = match target {
+        EnvTarget::Git => home.join(".gitconfig"),
+        EnvTarget::Npm => home.join(".npmrc"),
+        EnvTarget::Pip if cfg!(target_os = "windows") => path.config_dir()?.join("pip").join("pip.ini"),
+        EnvTarget::Pip => home.join(".config").join("pip").join("pip.conf"),
+        EnvTarget::Cargo => home.join(".cargo").join("config.toml"),
+        EnvTarget::DockerClient => home.join(".docker").join("config.json"),
+        EnvTarget::DockerDaemon if cfg!(target_os = "linux") => PathBuf::from("/etc/docker/daemon.json"),
+        EnvTarget::DockerDaemon => home.join(".docker").join("daemon.json"),
+        EnvTarget::Systemd => home
+            .join(".config")
+            .join("environment.d")
+            .join("90-clash-verge-proxy.conf"),
+        _ => bail!("a file path is required for {target:?}"),
+    };
+    Ok(file)
+}
+
+/// 写入文件；配置类目标只合并相关的键，保留文件中的其他内容
+pub async fn write_env_file(target: EnvTarget, env: &ProxyEnv, path: &Path, unset: bool) -> Result<()> {
+    let existing = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => std::string::String::new(),
+        Err(err) => return Err(err.into()),
+    };
+    let content = merge_env_file(target, env, &existing, unset)?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, content).await?;
+    Ok(())
+}
+
+fn merge_env_file(target: EnvTarget, env: &ProxyEnv, existing: &str, unset: bool) -> Result<std::string::String> {
+    match target {
+        EnvTarget::Git | EnvTarget::Npm | EnvTarget::Pip | EnvTarget::Cargo | EnvTarget::Systemd => {
+            Ok(merge_ini(existing, &ini_settings(target, env, unset)))
+        }
+        EnvTarget::DockerDaemon | EnvTarget::DockerClient => {
+            let (path, value) = json_setting(target, env, unset);
+            merge_json(existing, path, value)
+        }
+        _ => {
+            let line_ending = if target == EnvTarget::Cmd { "\r\n" } else { "\n" };
+            Ok(format!("{}{line_ending}", env_export_text(target, env, unset)))
+        }
+    }
+}
+
+fn ini_key(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.starts_with(['#', ';', '[']) {
+        return None;
+    }
+    line.split_once('=').map(|(key, _)| key.trim())
+}
+
+fn ini_section(line: &str) -> Option<&str> {
+    line.trim().strip_prefix('[')?.strip_suffix(']').map(str::trim)
+}
+
+/// 返回节内容的行范围，无节名时为第一个节之前的部分
+fn ini_section_range(lines: &[std::string::String], section: Option<&str>) -> Option<(usize, usize)> {
+    let start = match section {
+        Some(section) => lines.iter().position(|line| ini_section(line) == Some(section))? + 1,
+        None => 0,
+    };
+    let end = lines[start..]
+        .iter()
+        .position(|line| ini_section(line).is_some())
+        .map_or(lines.len(), |offset| start + offset);
+    Some((start, end))
+}
+
+fn merge_ini(existing: &str, settings: &[IniSetting]) -> std::string::String {
+    let mut lines = existing.lines().map(std::string::String::from).collect::<Vec<_>>();
+
+    for setting in settings {
+        let range = ini_section_range(&lines, setting.section);
+        let separator = if setting.section.is_some() { " = " } else { "=" };
+        match (&setting.value, range) {
+            (Some(value), Some((start, end))) => {
+                let line = format!("{}{separator}{value}", setting.key);
+                match (start..end).find(|index| ini_key(&lines[*index]) == Some(setting.key)) {
+                    Some(index) => lines[index] = line,
+                    None => {
+                        // 插入到节内最后一个非空行之后
+                        let last = (start..end).rev().find(|index| !lines[*index].trim().is_empty());
+                        lines.insert(last.map_or(start, |index| index + 1), line);
+                    }
+                }
+            }
+            (Some(value), None) => {
+                if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                    lines.push(std::string::String::new());
+                }
+                lines.push(format!("[{}]", setting.section.unwrap_or_default()));
+                lines.push(format!("{}{separator}{value}", setting.key));
+            }
+            (None, Some((start, end))) => {
+                let mut index = end;
+                while index > start {
+                    index -= 1;
+                    if ini_key(&lines[index]) == Some(setting.key) {
+                        lines.remove(index);
+                    }
+                }
+                remove_empty_section(&mut lines, setting.section);
+            }
+            (None, None) => {}
+        }
+    }
+
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    content
+}
+
+fn remove_empty_section(lines: &mut Vec<std::string::String>, section: Option<&str>) {
+    let Some((start, end)) = section.and_then(|section| ini_section_range(lines, Some(section))) else {
+        return;
+    };
+    if lines[start..end].iter().all(|line| line.trim().is_empty()) {
+        lines.drain(start - 1..end);
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+    }
+}
+
+fn merge_json(existing: &str, path: &[&str], value: Option<Value>) -> Result<std::string::String> {
+    let mut root = if existing.trim().is_empty() {
+        Value::Object(Map::new())
+    } else {
+        serde_json::from_str::<Value>(existing)?
+    };
+
+    let Some((last, parents)) = path.split_last() else {
+        bail!("empty json path");
+    };
+    let mut object = root.as_object_mut().ok_or_else(|| anyhow!("expected a JSON object"))?;
+    for key in parents {
+        if value.is_none() && !object.contains_key(*key) {
+            return Ok(format!("{}\n", serde_json::to_string_pretty(&root)?));
+        }
+        object = object
+            .entry(*key)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("expected \"{key}\" to be a JSON object"))?;
+    }
+    match value {
+        Some(value) => {
+            object.insert((*last).into(), value);
+        }
+        None => {
+            object.remove(*last);
+        }
+    }
+    Ok(format!("{}\n", serde_json::to_string_pretty(&root)?))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{EnvTarget, ProxyEnv, env_export_text, merge_env_file};
+
+    fn proxy_env() -> ProxyEnv {
+        ProxyEnv {
+            http: "http://127.0.0.1:7897".into(),
+            socks: "socks5://127.0.0.1:7897".into(),
+            no_proxy: "localhost,.local".into(),
+        }
+    }
+
+    #[test]
+    fn render_shell_exports() {
+        let env = proxy_env();
+        assert_eq!(
+            env_export_text(EnvTarget::Bash, &env, false),
+            "export http_proxy=\"http://127.0.0.1:7897\" https_proxy=\"http://127.0.0.1:7897\" \
+             all_proxy=\"socks5://127.0.0.1:7897\" no_proxy=\"localhost,.local\""
+        );
+        assert_eq!(
+            env_export_text(EnvTarget::Zsh, &env, true),
+            "unset http_proxy https_proxy all_proxy no_proxy"
+        );
+        assert_eq!(
+            env_export_text(EnvTarget::PowerShell, &env, true),
+            "Remove-Item Env:HTTP_PROXY, Env:HTTPS_PROXY, Env:ALL_PROXY, Env:NO_PROXY -ErrorAction SilentlyContinue"
+        );
+        assert_eq!(
+            env_export_text(EnvTarget::Npm, &env, true),
+            "npm config delete proxy\nnpm config delete https-proxy\nnpm config delete noproxy"
+        );
+    }
+
+    #[test]
+    fn merge_ini_files() {
+        let env = proxy_env();
+        let gitconfig = "[user]\n\tname = someone\n[http]\n\tsslVerify = true\n";
+        let merged = merge_env_file(EnvTarget::Git, &env, gitconfig, false).expect("merge gitconfig");
+        assert_eq!(
+            merged,
+            "[user]\n\tname = someone\n[http]\n\tsslVerify = true\nproxy = http://127.0.0.1:7897\n"
+        );
+
+        let restored = merge_env_file(EnvTarget::Git, &env, &merged, true).expect("unset gitconfig");
+        assert_eq!(restored, gitconfig);
+
+        let npmrc = "registry=https://registry.npmjs.org/\nproxy=http://old:1\n";
+        assert_eq!(
+            merge_env_file(EnvTarget::Npm, &env, npmrc, false).expect("merge npmrc"),
+            "registry=https://registry.npmjs.org/\nproxy=http://127.0.0.1:7897\n\
+             https-proxy=http://127.0.0.1:7897\nnoproxy=localhost,.local\n"
+        );
+    }
+
+    #[test]
+    fn merge_json_files() {
+        let env = proxy_env();
+        let config = "{\n  \"auths\": {}\n}";
+        let merged = merge_env_file(EnvTarget::DockerClient, &env, config, false).expect("merge docker config");
+        let value: serde_json::Value = serde_json::from_str(&merged).expect("valid json");
+        assert_eq!(value["proxies"]["default"]["httpProxy"], "http://127.0.0.1:7897");
+        assert_eq!(value["proxies"]["default"]["noProxy"], "localhost,.local");
+        assert!(value["auths"].is_object());
+
+        let restored = merge_env_file(EnvTarget::DockerClient, &env, &merged, true).expect("unset docker config");
+        let value: serde_json::Value = serde_json::from_str(&restored).expect("valid json");
+        assert!(value["proxies"].get("default").is_none());
+        assert!(merge_env_file(EnvTarget::DockerDaemon, &env, "[]", false).is_err());
+    }
+}
@@ -1,7 +1,9 @@
 mod backup;
 mod clash;
 mod config;
+mod env_export;
 mod icon;
+mod lan_sharing;
 mod profile;
 mod proxy;
 mod window;
@@ -10,7 +12,9 @@ mod window;
 pub use backup::*;
 pub use clash::*;
 pub use config::*;
+pub use env_export::*;
 pub use icon::*;
+pub use lan_sharing::*;
 pub use profile::*;
 pub use proxy::*;
 pub use window::*;
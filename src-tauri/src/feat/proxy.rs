@@ -1,9 +1,9 @@
+use super::{EnvTarget, ProxyEnv, env_export_text};
 use crate::{
     config::{Config, IVerge},
     core::handle,
 };
 use clash_verge_logging::{Type, logging};
-use std::env;
 use tauri_plugin_clipboard_manager::ClipboardExt as _;
 
 /// Toggle system proxy on/off
@@ -40,6 +40,11 @@ pub async fn toggle_tun_mode(not_save_file: Option<bool>) {
     let enable = Config::verge().await.latest_arc().enable_tun_mode;
     let enable = enable.unwrap_or(false);
 
+    #[cfg(target_os = "linux")]
+    if !enable && !tun_preflight_passed().await {
+        return;
+    }
+
     match super::patch_verge(
         &IVerge {
             enable_tun_mode: Some(!enable),
@@ -54,50 +59,43 @@ pub async fn toggle_tun_mode(not_save_file: Option<bool>) {
     }
 }
 
-/// Copy proxy environment variables to clipboard
-pub async fn copy_clash_env() {
-    let env_ip = env::var("CLASH_VERGE_REV_IP").ok();
-    let verge_cfg = Config::verge().await.latest_arc();
-    let ip = env_ip
-        .as_deref()
-        .unwrap_or_else(|| verge_cfg.proxy_host.as_deref().unwrap_or("127.0.0.1"));
+/// 开启 TUN 前检查系统环境，存在失败项时中止并通知前端
+#[cfg(target_os = "linux")]
+async fn tun_preflight_passed() -> bool {
+    use crate::core::tun_preflight::{self, CheckStatus};
 
-    let app_handle = handle::Handle::app_handle();
-    let port = verge_cfg.verge_mixed_port.unwrap_or(7897);
-    let http_proxy = format!("http://{ip}:{port}");
-    let socks5_proxy = format!("socks5://{ip}:{port}");
-
-    let clipboard = app_handle.clipboard();
-
-    let default_env = {
-        #[cfg(not(target_os = "windows"))]
-        {
-            "bash"
-        }
-        #[cfg(target_os = "windows")]
-        {
-            "powershell"
-        }
-    };
-    let env_type = verge_cfg.env_type.as_deref().unwrap_or(default_env);
+    let report = tun_preflight::run().await;
+    if !report.ready {
+        handle::Handle::notice_message("tun_preflight::failed", report.summary(CheckStatus::Fail));
+        return false;
+    }
+    let warnings = report.summary(CheckStatus::Warn);
+    if !warnings.is_empty() {
+        handle::Handle::notice_message("tun_preflight::warning", warnings);
+    }
+    true
+}
 
-    let export_text = match env_type {
-        "bash" => format!("export https_proxy={http_proxy} http_proxy={http_proxy} all_proxy={socks5_proxy}"),
-        "cmd" => format!("set http_proxy={http_proxy}\r\nset https_proxy={http_proxy}"),
-        "powershell" => {
-            format!("$env:HTTP_PROXY=\"{http_proxy}\"; $env:HTTPS_PROXY=\"{http_proxy}\"")
-        }
-        "nushell" => {
-            format!("load-env {{ http_proxy: \"{http_proxy}\", https_proxy: \"{http_proxy}\" }}")
-        }
-        "fish" => format!("set -x http_proxy {http_proxy}; set -x https_proxy {http_proxy}"),
-        _ => {
-            logging!(error, Type::ProxyMode, "copy_clash_env: Invalid env type! {env_type}");
-            return;
-        }
+/// Copy proxy environment variables to clipboard
+pub async fn copy_clash_env() {
+    let env_type = Config::verge().await.latest_arc().env_type.clone();
+    let target = match env_type.as_deref() {
+        Some(env_type) => match EnvTarget::parse(env_type) {
+            Some(target) => target,
+            None => {
+                logging!(error, Type::ProxyMode, "copy_clash_env: Invalid env type! {env_type}");
+                return;
+            }
+        },
+        None => EnvTarget::platform_default(),
     };
 
-    if clipboard.write_text(&export_text).is_err() {
+    let export_text = env_export_text(target, &ProxyEnv::current().await, false);
+    if handle::Handle::app_handle()
+        .clipboard()
+        .write_text(export_text.as_str())
+        .is_err()
+    {
         logging!(error, Type::ProxyMode, "Failed to write to clipboard");
     }
 }
@@ -26,6 +26,7 @@ pub async fn quit() {
 
     logging!(info, Type::System, "开始异步清理资源");
     let cleanup_result = clean_async().await;
+    utils::headless::stop_virtual_display();
 
     logging!(
         info,
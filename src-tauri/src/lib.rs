@@ -14,7 +14,7 @@ use crate::constants::files;
 use crate::{
     core::handle,
     process::AsyncHandler,
-    utils::{resolve, server},
+    utils::{headless, resolve, server},
 };
 use anyhow::Result;
 use clash_verge_logging::{Type, logging};
@@ -25,6 +25,17 @@ use tauri_plugin_autostart::MacosLauncher;
 use tauri_plugin_deep_link::DeepLinkExt as _;
 use tauri_plugin_mihomo::RejectPolicy;
 
+/// 基准测试使用的增强链入口
+#[doc(hidden)]
+pub use crate::enhance::{
+    apply_builtin_transforms_cached,
+    cache::EnhanceCache,
+    merge::use_merge,
+    process_chain_for_bench,
+    script::use_script,
+    seq::{SeqMap, use_seq},
+};
+
 pub static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
 /// Application initialization helper functions
 mod app_init {
@@ -136,6 +147,11 @@ mod app_init {
             cmd::is_port_in_use,
             cmd::get_sys_proxy,
             cmd::get_auto_proxy,
+            cmd::get_pac_report,
+            cmd::check_bypass,
+            cmd::get_bypass_presets,
+            cmd::get_proxy_guard_history,
+            cmd::clear_proxy_guard_history,
             cmd::open_app_dir,
             cmd::open_logs_dir,
             cmd::open_web_url,
@@ -146,9 +162,14 @@ mod app_init {
             cmd::get_network_interfaces,
             cmd::get_system_hostname,
             cmd::restart_app,
+            cmd::install_managed_core,
+            cmd::list_managed_cores,
+            cmd::remove_managed_core,
             cmd::start_core,
             cmd::stop_core,
             cmd::restart_core,
+            cmd::get_core_restart_stats,
+            cmd::get_core_health_history,
             cmd::notify_ui_ready,
             cmd::update_ui_stage,
             cmd::get_running_mode,
@@ -158,10 +179,14 @@ mod app_init {
             cmd::install_service,
             cmd::uninstall_service,
             cmd::reinstall_service,
+            cmd::upgrade_service,
+            cmd::get_service_negotiation,
             cmd::repair_service,
             cmd::is_service_available,
             cmd::get_clash_info,
             cmd::patch_clash_config,
+            cmd::rotate_clash_secret,
+            cmd::check_secret_strength,
             cmd::patch_clash_mode,
             cmd::change_clash_core,
             cmd::get_runtime_config,
@@ -170,8 +195,13 @@ mod app_init {
             cmd::get_runtime_logs,
             cmd::get_runtime_proxy_chain_config,
             cmd::update_proxy_chain_config_in_runtime,
+            cmd::revert_runtime_config,
+            cmd::get_last_config_rollback,
             cmd::invoke_uwp_tool,
+            cmd::check_tun_preflight,
             cmd::copy_clash_env,
+            cmd::get_env_export,
+            cmd::write_env_export,
             cmd::sync_tray_proxy_selection,
             cmd::save_dns_config,
             cmd::apply_dns_config,
@@ -179,8 +209,12 @@ mod app_init {
             cmd::get_dns_config_content,
             cmd::validate_dns_config,
             cmd::get_clash_logs,
+            cmd::query_clash_logs,
             cmd::get_verge_config,
             cmd::patch_verge_config,
+            cmd::create_automation_token,
+            cmd::revoke_automation_token,
+            cmd::get_builtin_transforms,
             cmd::test_delay,
             cmd::get_app_dir,
             cmd::copy_icon_file,
@@ -188,6 +222,11 @@ mod app_init {
             cmd::open_devtools,
             cmd::exit_app,
             cmd::get_network_interfaces_info,
+            cmd::get_lan_sharing,
+            cmd::patch_lan_sharing,
+            cmd::add_lan_user,
+            cmd::remove_lan_user,
+            cmd::get_lan_onboarding,
             cmd::get_profiles,
             cmd::enhance_profiles,
             cmd::patch_profiles_config,
@@ -195,6 +234,9 @@ mod app_init {
             cmd::patch_profile,
             cmd::create_profile,
             cmd::import_profile,
+            cmd::get_pending_deep_links,
+            cmd::respond_deep_link,
+            cmd::import_wasm_item,
             cmd::reorder_profile,
             cmd::update_profile,
             cmd::delete_profile,
@@ -230,6 +272,9 @@ pub fn run() {
 
     let _ = utils::dirs::init_portable_flag();
 
+    // 无界面模式下没有显示环境时启动虚拟显示
+    headless::ensure_display();
+
     let builder = app_init::setup_plugins(tauri::Builder::default())
         .setup(|app| {
             #[allow(clippy::expect_used)]
@@ -238,16 +283,21 @@ pub fn run() {
                 .expect("failed to set global app handle");
 
             resolve::init_work_dir_and_logger()?;
+            headless::flush_startup_messages();
 
             logging!(info, Type::Setup, "开始应用初始化...");
-            if let Err(e) = app_init::setup_autostart(app) {
-                logging!(error, Type::Setup, "Failed to setup autostart: {}", e);
-            }
+            if headless::is_headless() {
+                logging!(info, Type::Setup, "Headless mode, skipping desktop integration");
+            } else {
+                if let Err(e) = app_init::setup_autostart(app) {
+                    logging!(error, Type::Setup, "Failed to setup autostart: {}", e);
+                }
 
-            app_init::setup_deep_links(app);
+                app_init::setup_deep_links(app);
 
-            if let Err(e) = app_init::setup_window_state(app) {
-                logging!(error, Type::Setup, "Failed to setup window state: {}", e);
+                if let Err(e) = app_init::setup_window_state(app) {
+                    logging!(error, Type::Setup, "Failed to setup window state: {}", e);
+                }
             }
 
             resolve::resolve_setup_async();
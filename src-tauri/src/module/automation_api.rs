@@ -0,0 +1,588 @@
+use crate::{
+    cmd,
+    config::{Config, IVerge, IVergeApiToken},
+    core::{
+        CoreManager,
+        core_log::{CoreLogLevel, CoreLogQuery},
+        handle::Handle,
+        tray::Tray,
+    },
+    feat,
+};
+use anyhow::{Result, anyhow, bail};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use clash_verge_logging::{Type, logging, logging_error};
+use serde::{Deserialize, Serialize};
+use smartstring::alias::String;
+use warp::{Filter, Rejection, Reply, http::StatusCode};
+
+const API_VERSION: &str = "v1";
+const TOKEN_BYTES: usize = 32;
+const MAX_BODY_SIZE: u64 = 16 * 1024;
+const CLASH_MODES: [&str; 3] = ["rule", "global", "direct"];
+
+#[derive(Debug)]
+enum ApiRejection {
+    Unauthorized,
+    BadRequest(String),
+    Failed(String),
+}
+
+impl warp::reject::Reject for ApiRejection {}
+
+fn failed(err: impl std::fmt::Display) -> Rejection {
+    warp::reject::custom(ApiRejection::Failed(err.to_string().into()))
+}
+
+fn bad_request(message: impl Into<String>) -> Rejection {
+    warp::reject::custom(ApiRejection::BadRequest(message.into()))
+}
+
+#[derive(Serialize)]
+struct ApiError {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct ApiStatus {
+    version: &'static str,
+    running_mode: String,
+    mode: Option<String>,
+    current_profile: Option<ApiProfile>,
+    system_proxy: bool,
+    tun: bool,
+    ports: ApiPorts,
+}
+
+#[derive(Serialize)]
+struct ApiProfile {
+    uid: String,
+    name: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ApiPorts {
+    mixed: u16,
+    socks: u16,
+    http: u16,
+    controller: String,
+}
+
+#[derive(Serialize)]
+struct ApiProfileItem {
+    uid: String,
+    name: Option<String>,
+    #[serde(rename = "type")]
+    itype: Option<String>,
+    current: bool,
+    /// 上次更新时间，unix 秒
+    updated: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct LogsParams {
+    /// 只返回序号大于该值的日志，用于持续跟踪
+    after: Option<u64>,
+    /// 逗号分隔的级别
+    level: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ToggleBody {
+    enable: bool,
+}
+
+#[derive(Deserialize)]
+struct ModeBody {
+    mode: String,
+}
+
+#[derive(Deserialize)]
+struct ProfileBody {
+    uid: String,
+}
+
+#[derive(Deserialize)]
+struct UpdateBody {
+    /// 为空时更新当前订阅
+    uid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SelectBody {
+    group: String,
+    name: String,
+}
+
+/// `/api/v1` 下的自动化接口，未启用时表现为不存在
+pub fn routes() -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone + Send + Sync + 'static {
+    api(authorized())
+}
+
+/// 接口路由，`auth` 负责鉴权
+fn api(
+    auth: impl Filter<Extract = (), Error = Rejection> + Clone + Send + Sync + 'static,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone + Send + Sync + 'static {
+    let status = warp::path!("status")
+        .and(warp::get())
+        .and_then(|| async { Ok::<_, Rejection>(warp::reply::json(&collect_status().await)) });
+    let profiles = warp::path!("profiles")
+        .and(warp::get())
+        .and_then(|| async { Ok::<_, Rejection>(warp::reply::json(&list_profiles().await)) });
+    let logs = warp::path!("logs")
+        .and(warp::get())
+        .and(warp::query::<LogsParams>())
+        .and_then(get_logs);
+    let system_proxy = warp::path!("system-proxy")
+        .and(warp::put())
+        .and(json_body())
+        .and_then(set_system_proxy);
+    let tun = warp::path!("tun").and(warp::put()).and(json_body()).and_then(set_tun);
+    let mode = warp::path!("mode").and(warp::put()).and(json_body()).and_then(set_mode);
+    let profile = warp::path!("profile")
+        .and(warp::put())
+        .and(json_body())
+        .and_then(switch_profile);
+    let update = warp::path!("profiles" / "update")
+        .and(warp::post())
+        .and(json_body())
+        .and_then(update_profile);
+    let selection = warp::path!("selection")
+        .and(warp::put())
+        .and(json_body())
+        .and_then(select_node);
+
+    warp::path("api")
+        .and(warp::path(API_VERSION))
+        .and(auth)
+        .and(
+            status
+                .or(profiles)
+                .or(logs)
+                .or(system_proxy)
+                .or(tun)
+                .or(mode)
+                .or(profile)
+                .or(update)
+                .or(selection),
+        )
+        .recover(recover)
+}
+
+fn json_body<T: for<'de> Deserialize<'de> + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Clone {
+    warp::body::content_length_limit(MAX_BODY_SIZE).and(warp::body::json())
+}
+
+fn authorized() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<std::string::String>("authorization")
+        .and_then(|header: Option<std::string::String>| async move { check_auth(header.as_deref()).await })
+        .untuple_one()
+}
+
+async fn check_auth(header: Option<&str>) -> Result<(), Rejection> {
+    let verge = Config::verge().await.latest_arc();
+    authorize(
+        verge.enable_automation_api.unwrap_or(false),
+        verge.automation_api_tokens.as_deref().unwrap_or_default(),
+        header,
+    )
+}
+
+fn authorize(enabled: bool, tokens: &[IVergeApiToken], header: Option<&str>) -> Result<(), Rejection> {
+    if !enabled {
+        return Err(warp::reject::not_found());
+    }
+
+    match header.and_then(bearer_token) {
+        Some(token) if verify_token(tokens, token) => Ok(()),
+        _ => Err(warp::reject::custom(ApiRejection::Unauthorized)),
+    }
+}
+
+async fn recover(err: Rejection) -> Result<impl Reply, Rejection> {
+    // 交给其它路由处理
+    if err.is_not_found() {
+        return Err(err);
+    }
+
+    let (status, error) = if let Some(rejection) = err.find::<ApiRejection>() {
+        match rejection {
+            ApiRejection::Unauthorized => (StatusCode::UNAUTHORIZED, "invalid or missing token".into()),
+            ApiRejection::BadRequest(message) => (StatusCode::BAD_REQUEST, message.clone()),
+            ApiRejection::Failed(message) => (StatusCode::INTERNAL_SERVER_ERROR, message.clone()),
+        }
+    } else if let Some(err) = err.find::<warp::body::BodyDeserializeError>() {
+        (StatusCode::BAD_REQUEST, err.to_string().into())
+    } else {
+        return Err(err);
+    };
+    Ok(warp::reply::with_status(warp::reply::json(&ApiError { error }), status))
+}
+
+async fn collect_status() -> ApiStatus {
+    let verge = Config::verge().await.latest_arc();
+    let clash = Config::clash().await.latest_arc();
+    let info = clash.get_client_info();
+    let profiles = Config::profiles().await.latest_arc();
+
+    let current_profile = profiles.get_current().map(|uid| ApiProfile {
+        uid: uid.clone(),
+        name: profiles.get_item(uid).ok().and_then(|item| item.name.clone()),
+    });
+    ApiStatus {
+        version: env!("CARGO_PKG_VERSION"),
+        running_mode: CoreManager::global().get_running_mode().to_string().into(),
+        mode: clash.0.get("mode").and_then(|mode| mode.as_str()).map(Into::into),
+        current_profile,
+        system_proxy: verge.enable_system_proxy.unwrap_or(false),
+        tun: verge.enable_tun_mode.unwrap_or(false),
+        ports: ApiPorts {
+            mixed: info.mixed_port,
+            socks: info.socks_port,
+            http: info.port,
+            controller: info.server,
+        },
+    }
+}
+
+async fn list_profiles() -> Vec<ApiProfileItem> {
+    let profiles = Config::profiles().await.latest_arc();
+    let current = profiles.get_current();
+    profiles
+        .get_items()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let uid = item.uid.clone()?;
+            Some(ApiProfileItem {
+                current: current == Some(&uid),
+                uid,
+                name: item.name.clone(),
+                itype: item.itype.clone(),
+                updated: item.updated,
+            })
+        })
+        .collect()
+}
+
+async fn get_logs(params: LogsParams) -> Result<impl Reply, Rejection> {
+    let levels = match params.level.as_deref() {
+        Some(level) => parse_levels(level).ok_or_else(|| bad_request(format!("unknown level in `{level}`")))?,
+        None => Vec::new(),
+    };
+
+    let query = CoreLogQuery {
+        levels,
+        ..CoreLogQuery::default()
+    };
+    let mut entries = CoreManager::global().query_clash_logs(query).await.map_err(failed)?;
+    if let Some(after) = params.after {
+        entries.retain(|entry| entry.seq > after);
+    }
+    if let Some(limit) = params.limit {
+        entries.drain(..entries.len().saturating_sub(limit));
+    }
+    Ok(warp::reply::json(&entries))
+}
+
+fn parse_levels(levels: &str) -> Option<Vec<CoreLogLevel>> {
+    levels
+        .split(',')
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .map(CoreLogLevel::parse)
+        .collect()
+}
+
+async fn set_system_proxy(body: ToggleBody) -> Result<impl Reply, Rejection> {
+    logging!(info, Type::Cmd, "Automation API: system proxy -> {}", body.enable);
+    let patch = IVerge {
+        enable_system_proxy: Some(body.enable),
+        ..IVerge::default()
+    };
+    feat::patch_verge(&patch, false).await.map_err(failed)?;
+    Handle::refresh_verge();
+    Ok(warp::reply::json(&collect_status().await))
+}
+
+async fn set_tun(body: ToggleBody) -> Result<impl Reply, Rejection> {
+    logging!(info, Type::Cmd, "Automation API: tun -> {}", body.enable);
+    let patch = IVerge {
+        enable_tun_mode: Some(body.enable),
+        ..IVerge::default()
+    };
+    feat::patch_verge(&patch, false).await.map_err(failed)?;
+    Handle::refresh_verge();
+    Ok(warp::reply::json(&collect_status().await))
+}
+
+async fn set_mode(body: ModeBody) -> Result<impl Reply, Rejection> {
+    let mode = body.mode.to_lowercase();
+    if !CLASH_MODES.contains(&mode.as_str()) {
+        return Err(bad_request(format!("unknown mode `{}`", body.mode)));
+    }
+
+    logging!(info, Type::Cmd, "Automation API: mode -> {mode}");
+    feat::change_clash_mode(mode.as_str().into()).await;
+    let status = collect_status().await;
+    if status.mode.as_deref() != Some(mode.as_str()) {
+        return Err(failed(format!("failed to switch mode to `{mode}`")));
+    }
+    Ok(warp::reply::json(&status))
+}
+
+async fn switch_profile(body: ProfileBody) -> Result<impl Reply, Rejection> {
+    if Config::profiles().await.latest_arc().get_item(&body.uid).is_err() {
+        return Err(bad_request(format!("profile `{}` not found", body.uid)));
+    }
+
+    logging!(info, Type::Cmd, "Automation API: switch profile -> {}", body.uid);
+    match cmd::patch_profiles_config_by_profile_index(body.uid).await {
+        Ok(true) => Ok(warp::reply::json(&collect_status().await)),
+        Ok(false) => Err(failed("profile is invalid or another switch is in progress")),
+        Err(err) => Err(failed(err)),
+    }
+}
+
+async fn update_profile(body: UpdateBody) -> Result<impl Reply, Rejection> {
+    let uid = match body.uid {
+        Some(uid) => uid,
+        None => Config::profiles()
+            .await
+            .latest_arc()
+            .get_current()
+            .cloned()
+            .ok_or_else(|| bad_request("no current profile"))?,
+    };
+
+    logging!(info, Type::Cmd, "Automation API: update profile {uid}");
+    feat::update_profile(&uid, None, true, true, true)
+        .await
+        .map_err(failed)?;
+    Ok(warp::reply::json(&collect_status().await))
+}
+
+async fn select_node(body: SelectBody) -> Result<impl Reply, Rejection> {
+    let SelectBody { group, name } = body;
+    logging!(info, Type::Cmd, "Automation API: select {group} -> {name}");
+    Handle::mihomo()
+        .await
+        .select_node_for_group(&group, &name)
+        .await
+        .map_err(failed)?;
+    Handle::refresh_clash();
+    logging_error!(Type::Tray, Tray::global().update_menu().await);
+    Ok(warp::reply::json(&collect_status().await))
+}
+
+/// 生成新的访问令牌并保存，令牌名称不可重复
+pub async fn create_token(name: &str) -> Result<IVergeApiToken> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("token name is empty");
+    }
+
+    let mut tokens = Config::verge()
+        .await
+        .latest_arc()
+        .automation_api_tokens
+        .clone()
+        .unwrap_or_default();
+    if tokens.iter().any(|token| token.name == name) {
+        bail!("token `{name}` already exists");
+    }
+
+    let token = IVergeApiToken {
+        name: name.into(),
+        token: generate_token()?,
+        created_at: chrono::Local::now().timestamp(),
+    };
+    tokens.push(token.clone());
+    save_tokens(tokens).await?;
+    logging!(info, Type::Config, "Created automation API token `{name}`");
+    Ok(token)
+}
+
+pub async fn revoke_token(name: &str) -> Result<()> {
+    let mut tokens = Config::verge()
+        .await
+        .latest_arc()
+        .automation_api_tokens
+        .clone()
+        .unwrap_or_default();
+    let count = tokens.len();
+    tokens.retain(|token| token.name != name);
+    if tokens.len() == count {
+        bail!("token `{name}` not found");
+    }
+
+    save_tokens(tokens).await?;
+    logging!(info, Type::Config, "Revoked automation API token `{name}`");
+    Ok(())
+}
+
+async fn save_tokens(tokens: Vec<IVergeApiToken>) -> Result<()> {
+    let patch = IVerge {
+        automation_api_tokens: Some(tokens),
+        ..IVerge::default()
+    };
+    feat::patch_verge(&patch, false).await?;
+    Handle::refresh_verge();
+    Ok(())
+}
+
+pub(crate) fn generate_token() -> Result<String> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    getrandom::fill(&mut bytes).map_err(|err| anyhow!("failed to generate token: {err}"))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes).into())
+}
+
+pub(crate) fn bearer_token(header: &str) -> Option<&str> {
+    let (scheme, token) = header.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+fn verify_token(tokens: &[IVergeApiToken], presented: &str) -> bool {
+    tokens.iter().fold(false, |found, token| {
+        constant_time_eq(token.token.as_bytes(), presented.as_bytes()) | found
+    })
+}
+
+/// 比较耗时与内容无关，避免通过响应时间猜测令牌
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{
+        CoreLogLevel, IVergeApiToken, TOKEN_BYTES, api, authorize, bearer_token, constant_time_eq, generate_token,
+        parse_levels, verify_token,
+    };
+    use tokio::sync::oneshot;
+    use warp::{Filter as _, http::StatusCode};
+
+    fn token(name: &str, token: &str) -> IVergeApiToken {
+        IVergeApiToken {
+            name: name.into(),
+            token: token.into(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn parse_bearer_header() {
+        assert_eq!(bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(bearer_token("bearer  abc "), Some("abc"));
+        assert_eq!(bearer_token("Basic abc"), None);
+        assert_eq!(bearer_token("Bearer "), None);
+        assert_eq!(bearer_token("abc"), None);
+    }
+
+    #[test]
+    fn verify_tokens() {
+        let tokens = [token("ci", "first-token"), token("cron", "second-token")];
+        assert!(verify_token(&tokens, "first-token"));
+        assert!(verify_token(&tokens, "second-token"));
+        assert!(!verify_token(&tokens, "first-toke"));
+        assert!(!verify_token(&tokens, ""));
+        assert!(!verify_token(&[], "first-token"));
+
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+
+    #[test]
+    fn generated_tokens_are_unique() {
+        let first = generate_token().expect("generate token");
+        let second = generate_token().expect("generate token");
+        assert_ne!(first, second);
+        assert_eq!(first.len(), (TOKEN_BYTES * 4).div_ceil(3));
+    }
+
+    #[test]
+    fn parse_log_levels() {
+        assert_eq!(
+            parse_levels("warning, error"),
+            Some(vec![CoreLogLevel::Warning, CoreLogLevel::Error])
+        );
+        assert_eq!(parse_levels(""), Some(Vec::new()));
+        assert_eq!(parse_levels("info,verbose"), None);
+    }
+
+    /// 使用固定令牌启动接口服务，返回端口与关闭信号
+    async fn serve(enabled: bool) -> (u16, oneshot::Sender<()>) {
+        let auth = warp::header::optional::<std::string::String>("authorization")
+            .and_then(move |header: Option<std::string::String>| async move {
+                authorize(enabled, &[token("ci", "test-token")], header.as_deref())
+            })
+            .untuple_one();
+        let port = std::net::TcpListener::bind(("127.0.0.1", 0))
+            .and_then(|listener| listener.local_addr())
+            .expect("pick a free port")
+            .port();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let server = warp::serve(api(auth))
+            .bind(([127, 0, 0, 1], port))
+            .await
+            .graceful(async {
+                shutdown_rx.await.ok();
+            });
+        tokio::spawn(server.run());
+        (port, shutdown_tx)
+    }
+
+    async fn get(port: u16, path: &str, token: Option<&str>) -> (StatusCode, std::string::String) {
+        let mut request = reqwest::Client::new().get(format!("http://127.0.0.1:{port}{path}"));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.expect("send request");
+        let status = StatusCode::from_u16(response.status().as_u16()).expect("status code");
+        (status, response.text().await.expect("response body"))
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_or_invalid_token() {
+        let (port, shutdown) = serve(true).await;
+
+        let (status, body) = get(port, "/api/v1/logs", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body.contains("invalid or missing token"), "{body}");
+        let (status, _) = get(port, "/api/v1/status", Some("wrong-token")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        shutdown.send(()).ok();
+    }
+
+    #[tokio::test]
+    async fn disabled_api_is_not_found() {
+        let (port, shutdown) = serve(false).await;
+        let (status, _) = get(port, "/api/v1/logs", Some("test-token")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        shutdown.send(()).ok();
+    }
+
+    #[tokio::test]
+    async fn serves_logs_with_valid_token() {
+        let (port, shutdown) = serve(true).await;
+
+        let (status, body) = get(port, "/api/v1/logs?limit=10", Some("test-token")).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let logs: serde_json::Value = serde_json::from_str(&body).expect("json body");
+        assert!(logs.is_array());
+
+        let (status, body) = get(port, "/api/v1/logs?level=verbose", Some("test-token")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("unknown level"), "{body}");
+
+        shutdown.send(()).ok();
+    }
+}
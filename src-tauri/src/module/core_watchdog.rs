@@ -0,0 +1,341 @@
+use crate::{
+    config::{Config, IVerge},
+    core::{CoreManager, handle::Handle, manager::RunningMode},
+    process::AsyncHandler,
+    singleton,
+};
+use anyhow::{Result, anyhow, bail};
+use chrono::Local;
+use clash_verge_logging::{Type, logging};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use smartstring::alias::String;
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+const MIN_INTERVAL_SECS: u64 = 5;
+const MAX_INTERVAL_SECS: u64 = 3600;
+const DEFAULT_THRESHOLD: u32 = 3;
+const DEFAULT_MAX_LATENCY_MS: u64 = 3000;
+const MIN_MAX_LATENCY_MS: u64 = 100;
+const HISTORY_LIMIT: usize = 100;
+
+/// 依次尝试的恢复手段
+const RECOVERY_LADDER: &[RecoveryAction] = &[
+    RecoveryAction::Reload,
+    RecoveryAction::RestartCore,
+    RecoveryAction::SwitchMode,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecoveryAction {
+    /// 重新生成并热重载配置
+    Reload,
+    /// 重启核心
+    RestartCore,
+    /// 在 Service 与 Sidecar 之间切换
+    SwitchMode,
+}
+
+/// 一次健康检查的结果
+#[derive(Clone, Debug, Serialize)]
+pub struct HealthRecord {
+    /// 检查时间戳 (ms)
+    pub timestamp: i64,
+    pub ok: bool,
+    pub latency_ms: Option<u64>,
+    pub version: Option<String>,
+    pub error: Option<String>,
+    /// 本次失败后执行的恢复手段
+    pub action: Option<RecoveryAction>,
+    pub running_mode: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct WatchdogSettings {
+    enabled: bool,
+    interval: Duration,
+    threshold: u32,
+    max_latency: Duration,
+}
+
+impl WatchdogSettings {
+    fn from_verge(verge: &IVerge) -> Self {
+        Self {
+            enabled: verge.enable_core_watchdog.unwrap_or(false),
+            interval: Duration::from_secs(
+                verge
+                    .core_watchdog_interval
+                    .unwrap_or(DEFAULT_INTERVAL_SECS)
+                    .clamp(MIN_INTERVAL_SECS, MAX_INTERVAL_SECS),
+            ),
+            threshold: verge.core_watchdog_threshold.unwrap_or(DEFAULT_THRESHOLD).max(1),
+            max_latency: Duration::from_millis(
+                verge
+                    .core_watchdog_max_latency
+                    .unwrap_or(DEFAULT_MAX_LATENCY_MS)
+                    .max(MIN_MAX_LATENCY_MS),
+            ),
+        }
+    }
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(DEFAULT_INTERVAL_SECS),
+            threshold: DEFAULT_THRESHOLD,
+            max_latency: Duration::from_millis(DEFAULT_MAX_LATENCY_MS),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Verdict {
+    /// 尚未达到阈值
+    Wait,
+    Recover(RecoveryAction),
+    /// 所有恢复手段均已尝试，等待核心恢复
+    GiveUp,
+}
+
+/// 连续失败计数与恢复升级，每累计 threshold 次失败升级一级
+#[derive(Debug, Default)]
+struct Escalation {
+    failures: u32,
+    step: usize,
+    gave_up: bool,
+}
+
+impl Escalation {
+    fn on_success(&mut self) {
+        *self = Self::default();
+    }
+
+    fn on_failure(&mut self, threshold: u32) -> Verdict {
+        self.failures += 1;
+        if self.failures < threshold {
+            return Verdict::Wait;
+        }
+        self.failures = 0;
+
+        if let Some(action) = RECOVERY_LADDER.get(self.step) {
+            self.step += 1;
+            return Verdict::Recover(*action);
+        }
+        if self.gave_up {
+            return Verdict::Wait;
+        }
+        self.gave_up = true;
+        Verdict::GiveUp
+    }
+}
+
+pub struct CoreWatchdog {
+    settings_tx: watch::Sender<WatchdogSettings>,
+    runner_started: AtomicBool,
+    escalation: Mutex<Escalation>,
+    history: RwLock<VecDeque<HealthRecord>>,
+}
+
+singleton!(CoreWatchdog, CORE_WATCHDOG);
+
+impl CoreWatchdog {
+    fn new() -> Self {
+        let (tx, _rx) = watch::channel(WatchdogSettings::default());
+        Self {
+            settings_tx: tx,
+            runner_started: AtomicBool::new(false),
+            escalation: Mutex::new(Escalation::default()),
+            history: RwLock::new(VecDeque::with_capacity(HISTORY_LIMIT)),
+        }
+    }
+
+    pub async fn init(&self) -> Result<()> {
+        self.refresh_settings().await
+    }
+
+    pub async fn refresh_settings(&self) -> Result<()> {
+        let settings = WatchdogSettings::from_verge(&Config::verge().await.latest_arc());
+        let _ = self.settings_tx.send(settings);
+        if settings.enabled {
+            self.ensure_runner();
+        }
+        Ok(())
+    }
+
+    /// 最近的健康检查记录，按时间先后排列
+    pub fn history(&self) -> Vec<HealthRecord> {
+        self.history.read().iter().cloned().collect()
+    }
+
+    fn ensure_runner(&self) {
+        if self.runner_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let mut rx = self.settings_tx.subscribe();
+        AsyncHandler::spawn(move || async move {
+            Self::run_loop(&mut rx).await;
+        });
+    }
+
+    async fn run_loop(rx: &mut watch::Receiver<WatchdogSettings>) {
+        let mut current = *rx.borrow();
+        loop {
+            if !current.enabled {
+                Self::global().escalation.lock().on_success();
+                if rx.changed().await.is_err() {
+                    break;
+                }
+                current = *rx.borrow();
+                continue;
+            }
+
+            let sleeper = tokio::time::sleep(current.interval);
+            tokio::pin!(sleeper);
+
+            tokio::select! {
+                _ = &mut sleeper => {
+                    Self::global().check(current).await;
+                }
+                changed = rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    current = *rx.borrow();
+                }
+            }
+        }
+    }
+
+    async fn check(&self, settings: WatchdogSettings) {
+        let manager = CoreManager::global();
+        let mode = manager.get_running_mode();
+        // 未运行或正在退出时不检查，崩溃由 sidecar 监督处理
+        if *mode == RunningMode::NotRunning || Handle::global().is_exiting() {
+            return;
+        }
+
+        let started = Instant::now();
+        let result = probe_controller(settings.max_latency).await;
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).ok();
+
+        let mut record = HealthRecord {
+            timestamp: Local::now().timestamp_millis(),
+            ok: result.is_ok(),
+            latency_ms,
+            version: None,
+            error: None,
+            action: None,
+            running_mode: mode.to_string().into(),
+        };
+
+        let verdict = match result {
+            Ok(version) => {
+                record.version = Some(version);
+                self.escalation.lock().on_success();
+                Verdict::Wait
+            }
+            Err(err) => {
+                logging!(warn, Type::Core, "Core health check failed: {err}");
+                record.error = Some(err.to_string().into());
+                self.escalation.lock().on_failure(settings.threshold)
+            }
+        };
+
+        match verdict {
+            Verdict::Wait => {}
+            Verdict::Recover(action) => {
+                record.action = Some(action);
+                logging!(warn, Type::Core, "Core is unhealthy, trying to recover: {action:?}");
+                Handle::notice_message("core_watchdog::recovering", format!("{action:?}"));
+                if let Err(err) = recover(action).await {
+                    logging!(error, Type::Core, "Core recovery {action:?} failed: {err}");
+                    Handle::notice_message("core_watchdog::recover_failed", format!("{action:?}: {err}"));
+                }
+            }
+            Verdict::GiveUp => {
+                logging!(error, Type::Core, "Core is still unhealthy after all recovery attempts");
+                Handle::notice_message("core_watchdog::unhealthy", record.error.clone().unwrap_or_default());
+            }
+        }
+
+        self.push_record(record);
+    }
+
+    fn push_record(&self, record: HealthRecord) {
+        let mut history = self.history.write();
+        if history.len() >= HISTORY_LIMIT {
+            history.pop_front();
+        }
+        history.push_back(record);
+    }
+}
+
+/// 通过控制器获取版本，超时视为失败
+pub async fn probe_controller(max_latency: Duration) -> Result<String> {
+    let version = tokio::time::timeout(max_latency, async { Handle::mihomo().await.get_version().await })
+        .await
+        .map_err(|_| anyhow!("controller did not respond within {}ms", max_latency.as_millis()))?
+        .map_err(|err| anyhow!("controller request failed: {err}"))?;
+
+    if version.version.is_empty() {
+        bail!("controller returned an empty version");
+    }
+    Ok(version.version.as_str().into())
+}
+
+async fn recover(action: RecoveryAction) -> Result<()> {
+    let manager = CoreManager::global();
+    match action {
+        RecoveryAction::Reload => match manager.update_config().await? {
+            (true, _) => Ok(()),
+            (false, msg) => bail!("{msg}"),
+        },
+        RecoveryAction::RestartCore => manager.restart_core().await,
+        RecoveryAction::SwitchMode => {
+            let mode = manager.switch_running_mode().await?;
+            logging!(info, Type::Core, "Core running mode switched to {mode}");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Escalation, RecoveryAction, Verdict};
+
+    #[test]
+    fn escalates_after_threshold() {
+        let mut escalation = Escalation::default();
+
+        assert_eq!(escalation.on_failure(2), Verdict::Wait);
+        assert_eq!(escalation.on_failure(2), Verdict::Recover(RecoveryAction::Reload));
+        assert_eq!(escalation.on_failure(2), Verdict::Wait);
+        assert_eq!(escalation.on_failure(2), Verdict::Recover(RecoveryAction::RestartCore));
+        assert_eq!(escalation.on_failure(2), Verdict::Wait);
+        assert_eq!(escalation.on_failure(2), Verdict::Recover(RecoveryAction::SwitchMode));
+        assert_eq!(escalation.on_failure(2), Verdict::Wait);
+        assert_eq!(escalation.on_failure(2), Verdict::GiveUp);
+        assert_eq!(escalation.on_failure(2), Verdict::Wait);
+        assert_eq!(escalation.on_failure(2), Verdict::Wait);
+    }
+
+    #[test]
+    fn success_resets_escalation() {
+        let mut escalation = Escalation::default();
+
+        assert_eq!(escalation.on_failure(1), Verdict::Recover(RecoveryAction::Reload));
+        escalation.on_success();
+        assert_eq!(escalation.on_failure(1), Verdict::Recover(RecoveryAction::Reload));
+        assert_eq!(escalation.on_failure(1), Verdict::Recover(RecoveryAction::RestartCore));
+    }
+}
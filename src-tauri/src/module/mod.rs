@@ -1,2 +1,4 @@
 pub mod auto_backup;
+pub mod automation_api;
+pub mod core_watchdog;
 pub mod lightweight;
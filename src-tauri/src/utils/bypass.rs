@@ -0,0 +1,600 @@
+use serde::Serialize;
+use smartstring::alias::String;
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+/// 局域网与保留地址
+const LAN_PRESET: &[&str] = &[
+    "localhost",
+    "<local>",
+    "127.0.0.0/8",
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "169.254.0.0/16",
+    "100.64.0.0/10",
+    "::1",
+    "fc00::/7",
+    "fe80::/10",
+];
+
+/// 常见企业内网后缀
+const INTRANET_PRESET: &[&str] = &[
+    "*.local",
+    "*.lan",
+    "*.localdomain",
+    "*.internal",
+    "*.intranet",
+    "*.corp",
+    "*.home.arpa",
+];
+
+/// 常见国内直连域名
+const DOMESTIC_DIRECT_PRESET: &[&str] = &[
+    "*.cn",
+    "*.baidu.com",
+    "*.bdstatic.com",
+    "*.qq.com",
+    "*.gtimg.com",
+    "*.taobao.com",
+    "*.tmall.com",
+    "*.alicdn.com",
+    "*.alipay.com",
+    "*.aliyun.com",
+    "*.jd.com",
+    "*.163.com",
+    "*.126.net",
+    "*.bilibili.com",
+    "*.hdslb.com",
+    "*.weibo.com",
+    "*.sinaimg.cn",
+    "*.zhihu.com",
+    "*.douyin.com",
+    "*.meituan.com",
+    "*.xiaomi.com",
+    "*.huawei.com",
+    "*.csdn.net",
+    "*.gitee.com",
+];
+
+/// 可一键添加的绕过预设
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BypassPreset {
+    Lan,
+    Intranet,
+    DomesticDirect,
+}
+
+impl BypassPreset {
+    pub const ALL: [Self; 3] = [Self::Lan, Self::Intranet, Self::DomesticDirect];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "lan" => Some(Self::Lan),
+            "intranet" => Some(Self::Intranet),
+            "domestic-direct" => Some(Self::DomesticDirect),
+            _ => None,
+        }
+    }
+
+    pub const fn entries(self) -> &'static [&'static str] {
+        match self {
+            Self::Lan => LAN_PRESET,
+            Self::Intranet => INTRANET_PRESET,
+            Self::DomesticDirect => DOMESTIC_DIRECT_PRESET,
+        }
+    }
+
+    pub fn list(self) -> BypassList {
+        BypassList::parse(&self.entries().join(","))
+    }
+}
+
+/// 预设及其包含的条目
+#[derive(Debug, Serialize)]
+pub struct BypassPresetInfo {
+    pub id: BypassPreset,
+    pub entries: &'static [&'static str],
+}
+
+impl From<BypassPreset> for BypassPresetInfo {
+    fn from(preset: BypassPreset) -> Self {
+        Self {
+            id: preset,
+            entries: preset.entries(),
+        }
+    }
+}
+
+/// 绕过条目匹配的主机
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BypassHost {
+    /// `<local>`，不含点的主机名
+    Local,
+    /// `*`，绕过所有主机
+    Any,
+    /// WinINet 的其他特殊条目，如 `<-loopback>`，保留原文
+    Special(String),
+    Domain(String),
+    /// `*.example.com`，匹配所有子域名
+    Suffix(String),
+    /// 其他通配形式，如 `*example*`
+    Pattern(String),
+    /// IP 或网段，单个地址的前缀长度为地址位数
+    Cidr(IpAddr, u8),
+}
+
+/// 规范化后的绕过条目
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BypassEntry {
+    pub host: BypassHost,
+    pub port: Option<u16>,
+}
+
+impl fmt::Display for BypassEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.host, self.port) {
+            (BypassHost::Cidr(IpAddr::V6(ip), 128), Some(port)) => return write!(f, "[{ip}]:{port}"),
+            (BypassHost::Local, _) => f.write_str("<local>")?,
+            (BypassHost::Any, _) => f.write_str("*")?,
+            (BypassHost::Special(token), _) => f.write_str(token)?,
+            (BypassHost::Domain(domain), _) => f.write_str(domain)?,
+            (BypassHost::Suffix(domain), _) => write!(f, "*.{domain}")?,
+            (BypassHost::Pattern(pattern), _) => f.write_str(pattern)?,
+            (BypassHost::Cidr(ip, prefix), _) if *prefix == max_prefix(ip) => write!(f, "{ip}")?,
+            (BypassHost::Cidr(ip, prefix), _) => write!(f, "{ip}/{prefix}")?,
+        }
+        match self.port {
+            Some(port) => write!(f, ":{port}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 无法解析的条目，位置以字符计
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BypassError {
+    pub entry: String,
+    pub start: usize,
+    pub end: usize,
+    pub reason: &'static str,
+}
+
+/// 绕过列表的检查结果
+#[derive(Debug, Serialize)]
+pub struct BypassReport {
+    /// 按当前平台语法输出的规范化列表
+    pub normalized: String,
+    pub errors: Vec<BypassError>,
+    pub duplicates: Vec<String>,
+}
+
+/// 绕过列表的输出格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BypassFormat {
+    /// WinINet，`;` 分隔，不支持 CIDR
+    Windows,
+    MacOs,
+    /// GNOME/KDE 的 ignore-hosts
+    Linux,
+    /// `NO_PROXY` 环境变量
+    NoProxy,
+}
+
+impl BypassFormat {
+    pub const fn system() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::Windows
+        } else if cfg!(target_os = "macos") {
+            Self::MacOs
+        } else {
+            Self::Linux
+        }
+    }
+
+    const fn separator(self) -> &'static str {
+        match self {
+            Self::Windows => ";",
+            _ => ",",
+        }
+    }
+}
+
+/// 解析并去重后的绕过列表
+#[derive(Clone, Debug, Default)]
+pub struct BypassList {
+    pub entries: Vec<BypassEntry>,
+    pub errors: Vec<BypassError>,
+    /// 规范化后与已有条目重复而被忽略的条目
+    pub duplicates: Vec<String>,
+}
+
+impl BypassList {
+    /// 解析以 `,`、`;` 或换行分隔的绕过列表
+    pub fn parse(input: &str) -> Self {
+        let mut list = Self::default();
+        let mut start = 0;
+        let mut entry = std::string::String::new();
+        for (index, ch) in input.chars().chain(std::iter::once(',')).enumerate() {
+            if matches!(ch, ',' | ';' | '\n' | '\r') {
+                list.push_raw(&entry, start);
+                entry.clear();
+                start = index + 1;
+            } else {
+                entry.push(ch);
+            }
+        }
+        list
+    }
+
+    fn push_raw(&mut self, raw: &str, start: usize) {
+        let trimmed = raw.trim_start();
+        let start = start + (raw.chars().count() - trimmed.chars().count());
+        let trimmed = trimmed.trim_end();
+        if trimmed.is_empty() {
+            return;
+        }
+        match parse_entry(trimmed) {
+            Ok(entry) => self.push(entry),
+            Err(reason) => self.errors.push(BypassError {
+                entry: trimmed.into(),
+                start,
+                end: start + trimmed.chars().count(),
+                reason,
+            }),
+        }
+    }
+
+    pub fn push(&mut self, entry: BypassEntry) {
+        if self.entries.contains(&entry) {
+            self.duplicates.push(entry.to_string().into());
+        } else {
+            self.entries.push(entry);
+        }
+    }
+
+    /// 合并另一个列表，错误与重复记录一并保留
+    pub fn merge(&mut self, other: Self) {
+        for entry in other.entries {
+            self.push(entry);
+        }
+        self.errors.extend(other.errors);
+        self.duplicates.extend(other.duplicates);
+    }
+
+    pub fn report(self, format: BypassFormat) -> BypassReport {
+        BypassReport {
+            normalized: self.render(format),
+            errors: self.errors,
+            duplicates: self.duplicates,
+        }
+    }
+
+    /// 按目标平台的语法输出，无法表达的条目会被省略
+    pub fn render(&self, format: BypassFormat) -> String {
+        let mut items: Vec<std::string::String> = Vec::new();
+        for entry in &self.entries {
+            for item in render_entry(entry, format) {
+                if !items.contains(&item) {
+                    items.push(item);
+                }
+            }
+        }
+        items.join(format.separator()).into()
+    }
+}
+
+impl fmt::Display for BypassList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, entry) in self.entries.iter().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+const fn max_prefix(ip: &IpAddr) -> u8 {
+    match ip {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn parse_entry(raw: &str) -> Result<BypassEntry, &'static str> {
+    if raw.starts_with('<') {
+        return parse_special(raw).map(|host| BypassEntry { host, port: None });
+    }
+
+    let lower = raw.to_ascii_lowercase();
+    let item = lower.as_str();
+
+    if let Some(rest) = item.strip_prefix('[') {
+        let (ip, tail) = rest.split_once(']').ok_or("unclosed IPv6 bracket")?;
+        let ip = ip.parse::<Ipv6Addr>().map_err(|_| "invalid IPv6 address")?;
+        let port = match tail {
+            "" => None,
+            _ => Some(parse_port(
+                tail.strip_prefix(':').ok_or("unexpected text after IPv6 address")?,
+            )?),
+        };
+        return Ok(BypassEntry {
+            host: BypassHost::Cidr(IpAddr::V6(ip), 128),
+            port,
+        });
+    }
+
+    // 多个冒号视为 IPv6，单个冒号视为端口
+    let (host, port) = match item.split_once(':') {
+        Some((host, port)) if !port.contains(':') => (host, Some(parse_port(port)?)),
+        _ => (item, None),
+    };
+    Ok(BypassEntry {
+        host: parse_host(host)?,
+        port,
+    })
+}
+
+fn parse_port(port: &str) -> Result<u16, &'static str> {
+    port.parse::<u16>().ok().filter(|port| *port > 0).ok_or("invalid port")
+}
+
+fn parse_host(host: &str) -> Result<BypassHost, &'static str> {
+    match host {
+        "" => return Err("empty host"),
+        "*" => return Ok(BypassHost::Any),
+        _ => {}
+    }
+
+    if let Some((ip, prefix)) = host.split_once('/') {
+        let ip = ip.parse::<IpAddr>().map_err(|_| "invalid CIDR address")?;
+        let prefix = prefix
+            .parse::<u8>()
+            .ok()
+            .filter(|prefix| *prefix <= max_prefix(&ip))
+            .ok_or("invalid CIDR prefix")?;
+        return Ok(BypassHost::Cidr(network(ip, prefix), prefix));
+    }
+    if host.contains(':') {
+        let ip = host.parse::<Ipv6Addr>().map_err(|_| "invalid IPv6 address")?;
+        return Ok(BypassHost::Cidr(IpAddr::V6(ip), 128));
+    }
+    if host.bytes().all(|byte| byte.is_ascii_digit() || byte == b'.') {
+        let ip = host.parse::<Ipv4Addr>().map_err(|_| "invalid IPv4 address")?;
+        return Ok(BypassHost::Cidr(IpAddr::V4(ip), 32));
+    }
+    if let Some(cidr) = wildcard_cidr(host) {
+        return Ok(cidr);
+    }
+
+    if !host
+        .bytes()
+        .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'-' | b'_' | b'*'))
+    {
+        return Err("invalid character");
+    }
+    let suffix = host.strip_prefix("*.").or_else(|| host.strip_prefix('.'));
+    if let Some(domain) = suffix.filter(|domain| !domain.contains('*')) {
+        return valid_domain(domain).map(|domain| BypassHost::Suffix(domain.into()));
+    }
+    if host.contains('*') {
+        return Ok(BypassHost::Pattern(host.into()));
+    }
+    valid_domain(host).map(|domain| BypassHost::Domain(domain.into()))
+}
+
+/// `<...>` 条目原样保留，只有 `<local>` 有跨平台的含义
+fn parse_special(token: &str) -> Result<BypassHost, &'static str> {
+    let inner = token
+        .strip_prefix('<')
+        .and_then(|token| token.strip_suffix('>'))
+        .ok_or("unclosed special entry")?;
+    if inner.is_empty() || inner.contains(['<', '>']) || inner.contains(char::is_whitespace) {
+        return Err("invalid special entry");
+    }
+    if inner.eq_ignore_ascii_case("local") {
+        return Ok(BypassHost::Local);
+    }
+    Ok(BypassHost::Special(token.into()))
+}
+
+fn valid_domain(domain: &str) -> Result<&str, &'static str> {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    if domain.is_empty() || domain.len() > 253 {
+        return Err("invalid domain length");
+    }
+    if domain.split('.').any(|label| label.is_empty() || label.len() > 63) {
+        return Err("empty or oversized domain label");
+    }
+    Ok(domain)
+}
+
+/// `192.168.*` 形式的 IPv4 通配转为网段
+fn wildcard_cidr(host: &str) -> Option<BypassHost> {
+    let octets = host.split('.').collect::<Vec<_>>();
+    let fixed = octets.iter().take_while(|octet| **octet != "*").count();
+    if octets.len() > 4 || fixed == 0 || octets[fixed..].iter().any(|octet| *octet != "*") {
+        return None;
+    }
+    let mut address = octets[..fixed]
+        .iter()
+        .map(|octet| octet.parse::<u8>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    address.resize(4, 0);
+    let ip = Ipv4Addr::new(address[0], address[1], address[2], address[3]);
+    Some(BypassHost::Cidr(IpAddr::V4(ip), u8::try_from(fixed * 8).ok()?))
+}
+
+fn network(ip: IpAddr, prefix: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+        }
+        IpAddr::V6(ip) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+        }
+    }
+}
+
+fn render_entry(entry: &BypassEntry, format: BypassFormat) -> Vec<std::string::String> {
+    let hosts = match (&entry.host, format) {
+        (BypassHost::Local, BypassFormat::Linux) => vec![],
+        (BypassHost::Local, BypassFormat::NoProxy) => vec!["localhost".into()],
+        (BypassHost::Special(token), BypassFormat::Windows) => vec![token.to_string()],
+        (BypassHost::Special(_), _) => vec![],
+        (BypassHost::Suffix(domain), BypassFormat::NoProxy) => vec![format!(".{domain}")],
+        (BypassHost::Pattern(_), BypassFormat::NoProxy) => vec![],
+        (BypassHost::Cidr(IpAddr::V4(ip), prefix), BypassFormat::Windows) => windows_wildcards(*ip, *prefix),
+        (BypassHost::Cidr(IpAddr::V6(ip), 128), BypassFormat::Windows) => vec![format!("[{ip}]")],
+        (BypassHost::Cidr(IpAddr::V6(_), _), BypassFormat::Windows) => vec![],
+        _ => {
+            return vec![entry.to_string()];
+        }
+    };
+    match entry.port {
+        Some(port) => hosts.into_iter().map(|host| format!("{host}:{port}")).collect(),
+        None => hosts,
+    }
+}
+
+/// WinINet 只支持按字节通配，非整字节的网段展开为多个通配条目
+fn windows_wildcards(ip: Ipv4Addr, prefix: u8) -> Vec<std::string::String> {
+    match prefix {
+        0 => return vec!["*".into()],
+        32 => return vec![ip.to_string()],
+        _ => {}
+    }
+    let bytes = prefix.div_ceil(8);
+    let base = u32::from(ip);
+    let count = 1u32 << (u32::from(bytes) * 8 - u32::from(prefix));
+    (0..count)
+        .map(|index| {
+            let octets = (base + (index << (32 - u32::from(bytes) * 8))).to_be_bytes();
+            let mut parts = octets[..usize::from(bytes)]
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<_>>();
+            parts.push("*".into());
+            parts.join(".")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BypassError, BypassFormat, BypassList, BypassPreset};
+
+    #[test]
+    fn normalize_platform_syntaxes() {
+        let list =
+            BypassList::parse("LOCALHOST; 192.168.*;*.Local, .corp.example,<local>\n10.1.2.3/8,::1,[fe80::1]:8080");
+
+        assert!(list.errors.is_empty());
+        assert_eq!(
+            list.to_string(),
+            "localhost,192.168.0.0/16,*.local,*.corp.example,<local>,10.0.0.0/8,::1,[fe80::1]:8080"
+        );
+    }
+
+    #[test]
+    fn report_invalid_entries_with_positions() {
+        let list = BypassList::parse("example.com, 300.1.1.1;a b,10.0.0.0/33,host:99999");
+
+        assert_eq!(list.entries.len(), 1);
+        assert_eq!(
+            list.errors,
+            vec![
+                BypassError {
+                    entry: "300.1.1.1".into(),
+                    start: 13,
+                    end: 22,
+                    reason: "invalid IPv4 address",
+                },
+                BypassError {
+                    entry: "a b".into(),
+                    start: 23,
+                    end: 26,
+                    reason: "invalid character",
+                },
+                BypassError {
+                    entry: "10.0.0.0/33".into(),
+                    start: 27,
+                    end: 38,
+                    reason: "invalid CIDR prefix",
+                },
+                BypassError {
+                    entry: "host:99999".into(),
+                    start: 39,
+                    end: 49,
+                    reason: "invalid port",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_special_entries_verbatim() {
+        let list = BypassList::parse("<-loopback>;<LOCAL>;<Local>;example.com;<local;<>");
+
+        assert_eq!(list.to_string(), "<-loopback>,<local>,example.com");
+        assert_eq!(list.duplicates, vec!["<local>"]);
+        assert_eq!(
+            list.errors
+                .iter()
+                .map(|error| (error.entry.as_str(), error.reason))
+                .collect::<Vec<_>>(),
+            vec![("<local", "unclosed special entry"), ("<>", "invalid special entry")]
+        );
+        assert_eq!(list.render(BypassFormat::Windows), "<-loopback>;<local>;example.com");
+        assert_eq!(list.render(BypassFormat::MacOs), "<local>,example.com");
+        assert_eq!(list.render(BypassFormat::NoProxy), "localhost,example.com");
+    }
+
+    #[test]
+    fn deduplicate_normalized_entries() {
+        let mut list = BypassList::parse("192.168.*,192.168.0.0/16,Example.com");
+        list.merge(BypassList::parse("example.com.,*.local"));
+
+        assert_eq!(list.to_string(), "192.168.0.0/16,example.com,*.local");
+        assert_eq!(list.duplicates, vec!["192.168.0.0/16", "example.com"]);
+    }
+
+    #[test]
+    fn render_for_each_platform() {
+        let list = BypassList::parse("<local>,*.local,172.16.0.0/12,127.0.0.1,::1,*epic*,example.com:8080");
+
+        assert_eq!(
+            list.render(BypassFormat::NoProxy),
+            "localhost,.local,172.16.0.0/12,127.0.0.1,::1,example.com:8080"
+        );
+        assert_eq!(
+            list.render(BypassFormat::Linux),
+            "*.local,172.16.0.0/12,127.0.0.1,::1,*epic*,example.com:8080"
+        );
+        let windows_default = BypassList::parse("localhost;127.*;192.168.*;172.16.*;<local>");
+        assert_eq!(
+            windows_default.render(BypassFormat::NoProxy),
+            "localhost,127.0.0.0/8,192.168.0.0/16,172.16.0.0/16"
+        );
+
+        let windows = list.render(BypassFormat::Windows);
+        assert!(windows.starts_with("<local>;*.local;172.16.*;172.17.*;"));
+        assert!(windows.ends_with(";172.31.*;127.0.0.1;[::1];*epic*;example.com:8080"));
+    }
+
+    #[test]
+    fn presets_are_valid() {
+        for preset in BypassPreset::ALL {
+            let list = preset.list();
+            assert!(list.errors.is_empty(), "{preset:?}: {:?}", list.errors);
+            assert!(list.duplicates.is_empty(), "{preset:?}: {:?}", list.duplicates);
+        }
+    }
+}
@@ -85,6 +85,16 @@ pub fn app_profiles_dir() -> Result<PathBuf> {
     Ok(app_home_dir()?.join("profiles"))
 }
 
+/// enhance cache dir
+pub fn enhance_cache_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("enhance_cache"))
+}
+
+/// managed cores dir
+pub fn managed_cores_dir() -> Result<PathBuf> {
+    Ok(app_home_dir()?.join("cores"))
+}
+
 /// icons dir
 pub fn app_icons_dir() -> Result<PathBuf> {
     Ok(app_home_dir()?.join("icons"))
@@ -167,7 +177,9 @@ pub fn service_log_dir() -> Result<PathBuf> {
 pub fn clash_latest_log() -> Result<PathBuf> {
     match *CoreManager::global().get_running_mode() {
         RunningMode::Service => Ok(service_log_dir()?.join("service_latest.log")),
-        RunningMode::Sidecar | RunningMode::NotRunning => Ok(sidecar_log_dir()?.join("sidecar_latest.log")),
+        RunningMode::Sidecar | RunningMode::External | RunningMode::NotRunning => {
+            Ok(sidecar_log_dir()?.join("sidecar_latest.log"))
+        }
     }
 }
 
@@ -0,0 +1,184 @@
+use crate::{core::CoreManager, feat, module::automation_api, utils::dirs};
+use anyhow::{Result, bail};
+use clash_verge_logging::{Type, logging};
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use smartstring::alias::String;
+use std::{
+    io::Write as _,
+    ops::Range,
+    path::Path,
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+/// 以无界面模式启动的命令行参数
+pub const HEADLESS_ARG: &str = "--headless";
+/// 以无界面模式启动的环境变量，值为 `1` 或 `true` 时生效
+pub const HEADLESS_ENV: &str = "CLASH_VERGE_HEADLESS";
+/// 控制接口令牌所在文件，位于应用数据目录，仅当前用户可读
+pub const COMMAND_TOKEN_FILE: &str = "headless.token";
+
+/// 虚拟显示的编号范围
+const VIRTUAL_DISPLAYS: Range<u16> = 99..199;
+const VIRTUAL_DISPLAY_TIMEOUT: Duration = Duration::from_secs(5);
+
+static COMMAND_TOKEN: OnceCell<String> = OnceCell::new();
+static VIRTUAL_DISPLAY: Mutex<Option<Child>> = Mutex::new(None);
+/// 日志系统初始化前产生的虚拟显示启动结果，`true` 表示错误
+static STARTUP_MESSAGES: Mutex<Vec<(bool, String)>> = Mutex::new(Vec::new());
+
+static HEADLESS: Lazy<bool> =
+    Lazy::new(|| cfg!(target_os = "linux") && detect(std::env::args(), std::env::var(HEADLESS_ENV).ok().as_deref()));
+
+/// 是否以无界面守护进程模式运行，仅 Linux 支持
+pub fn is_headless() -> bool {
+    *HEADLESS
+}
+
+fn detect<I, S>(args: I, env: Option<&str>) -> bool
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let by_env = env.is_some_and(|value| matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true"));
+    by_env || args.into_iter().skip(1).any(|arg| arg.as_ref() == HEADLESS_ARG)
+}
+
+/// 没有显示环境时由应用自行启动 Xvfb，GTK 初始化需要显示但不会创建任何窗口，须在创建 Tauri 应用前调用
+///
+/// 此时日志系统尚未初始化，错误直接输出到 stderr，并在 [`flush_startup_messages`] 时写入日志
+pub fn ensure_display() {
+    if !is_headless() || std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return;
+    }
+
+    let Some(display) = VIRTUAL_DISPLAYS.into_iter().find(|display| !display_in_use(*display)) else {
+        startup_error("No free display number for the virtual display".into());
+        return;
+    };
+    let child = Command::new("Xvfb")
+        .args([&format!(":{display}"), "-nolisten", "tcp", "-screen", "0", "640x480x24"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => {
+            startup_error(format!("Failed to start Xvfb, install xvfb or set DISPLAY: {err}").into());
+            return;
+        }
+    };
+
+    let started = Instant::now();
+    while !display_in_use(display) {
+        if started.elapsed() > VIRTUAL_DISPLAY_TIMEOUT || child.try_wait().ok().flatten().is_some() {
+            startup_error(format!("Xvfb did not provide display :{display}").into());
+            let _ = child.kill();
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    // 单例检查创建的 tokio 运行时线程此时处于空闲，没有其它代码会并发读取环境变量
+    unsafe {
+        std::env::set_var("DISPLAY", format!(":{display}"));
+    }
+    STARTUP_MESSAGES.lock().push((
+        false,
+        format!("Started virtual display :{display} for headless mode").into(),
+    ));
+    *VIRTUAL_DISPLAY.lock() = Some(child);
+}
+
+fn startup_error(message: String) {
+    eprintln!("{message}");
+    STARTUP_MESSAGES.lock().push((true, message));
+}
+
+/// 日志系统初始化后写入 [`ensure_display`] 的结果
+pub fn flush_startup_messages() {
+    for (error, message) in STARTUP_MESSAGES.lock().drain(..) {
+        if error {
+            logging!(error, Type::Setup, "{message}");
+        } else {
+            logging!(info, Type::Setup, "{message}");
+        }
+    }
+}
+
+fn display_in_use(display: u16) -> bool {
+    Path::new(&format!("/tmp/.X11-unix/X{display}")).exists() || Path::new(&format!("/tmp/.X{display}-lock")).exists()
+}
+
+/// 退出时关闭由应用启动的 Xvfb
+pub fn stop_virtual_display() {
+    if let Some(mut child) = VIRTUAL_DISPLAY.lock().take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// 生成控制接口令牌并写入 [`COMMAND_TOKEN_FILE`]，每次启动都会更换
+pub fn init_command_token() -> Result<()> {
+    let token = automation_api::generate_token()?;
+    let path = dirs::app_home_dir()?.join(COMMAND_TOKEN_FILE);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt as _;
+        options.mode(0o600);
+    }
+    options.open(&path)?.write_all(token.as_bytes())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    let _ = COMMAND_TOKEN.set(token);
+    Ok(())
+}
+
+/// 校验 `Authorization: Bearer <token>`，令牌未生成时一律拒绝
+pub fn verify_command_token(header: Option<&str>) -> bool {
+    let Some(expected) = COMMAND_TOKEN.get() else {
+        return false;
+    };
+    header
+        .and_then(automation_api::bearer_token)
+        .is_some_and(|token| automation_api::constant_time_eq(expected.as_bytes(), token.as_bytes()))
+}
+
+/// 重新生成并应用配置，对应 SIGHUP 与 `/commands/reload`
+pub async fn reload() -> Result<()> {
+    logging!(info, Type::System, "Reloading configuration in headless mode");
+    let (applied, message) = feat::enhance_profiles().await?;
+    if !applied {
+        bail!("failed to apply config: {message}");
+    }
+    Ok(())
+}
+
+/// 重启内核，对应 `/commands/restart`
+pub async fn restart_core() -> Result<()> {
+    logging!(info, Type::System, "Restarting core in headless mode");
+    CoreManager::global().graceful_restart_core().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::detect;
+
+    #[test]
+    fn detect_headless_flag() {
+        assert!(detect(["clash-verge", "--headless"], None));
+        assert!(detect(["clash-verge"], Some("1")));
+        assert!(detect(["clash-verge"], Some(" TRUE ")));
+        assert!(!detect(["clash-verge"], Some("0")));
+        assert!(!detect(["clash-verge"], None));
+        // 程序名本身不参与判断
+        assert!(!detect(["--headless"], None));
+        assert!(!detect(["clash-verge", "clash://install-config?url=--headless"], None));
+    }
+}
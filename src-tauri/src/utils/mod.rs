@@ -1,4 +1,6 @@
+pub mod bypass;
 pub mod dirs;
+pub mod headless;
 pub mod help;
 pub mod init;
 #[cfg(target_os = "linux")]
@@ -9,13 +9,13 @@ use crate::{
         hotkey::Hotkey,
         logger::Logger,
         service::{SERVICE_MANAGER, ServiceManager, is_service_ipc_path_exists},
-        sysopt,
+        service_version, sysopt,
         tray::Tray,
     },
     feat,
-    module::{auto_backup::AutoBackupManager, lightweight::auto_lightweight_boot},
+    module::{auto_backup::AutoBackupManager, core_watchdog::CoreWatchdog, lightweight::auto_lightweight_boot},
     process::AsyncHandler,
-    utils::{init, server, window_manager::WindowManager},
+    utils::{headless, init, server, window_manager::WindowManager},
 };
 use clash_verge_logging::{Type, logging, logging_error};
 use clash_verge_signal;
@@ -53,14 +53,18 @@ pub fn resolve_setup_async() {
         init_startup_script().await;
         init_verge_config().await;
         Config::verify_config_initialization().await;
+
+        // 无界面模式只负责内核、定时任务与自动备份
+        if headless::is_headless() {
+            let core_init = AsyncHandler::spawn(init_core);
+            let _ = futures::join!(core_init, init_timer(), init_auto_backup());
+            logging!(info, Type::Setup, "Headless daemon is ready");
+            return;
+        }
+
         init_window().await;
 
-        let core_init = AsyncHandler::spawn(|| async {
-            init_service_manager().await;
-            init_core_manager().await;
-            init_system_proxy().await;
-            init_system_proxy_guard().await;
-        });
+        let core_init = AsyncHandler::spawn(init_core);
 
         let tray_init = async {
             init_tray().await;
@@ -78,6 +82,16 @@ pub fn resolve_setup_async() {
     });
 }
 
+async fn init_core() {
+    init_service_manager().await;
+    init_core_manager().await;
+    init_system_proxy().await;
+    init_system_proxy_guard().await;
+    init_lan_pac_server().await;
+    feat::check_controller_secret().await;
+    init_core_watchdog().await;
+}
+
 pub async fn resolve_reset_async() -> Result<(), anyhow::Error> {
     sysopt::Sysopt::global().reset_sysproxy().await?;
     CoreManager::global().stop_core().await?;
@@ -133,6 +147,12 @@ pub(super) async fn init_auto_backup() {
 pub fn init_signal() {
     logging!(info, Type::Setup, "Initializing signal handlers...");
     clash_verge_signal::register(feat::quit);
+    #[cfg(unix)]
+    if headless::is_headless() {
+        clash_verge_signal::register_reload(|| async {
+            logging_error!(Type::SystemSignal, headless::reload().await);
+        });
+    }
 }
 
 pub async fn init_work_config() {
@@ -154,6 +174,9 @@ pub(super) async fn init_service_manager() {
     }
     if SERVICE_MANAGER.lock().await.init().await.is_ok() {
         logging_error!(Type::Setup, SERVICE_MANAGER.lock().await.refresh().await);
+        if let Some(negotiation) = service_version::last_negotiation() {
+            service_version::handle_negotiation(&negotiation).await;
+        }
     }
 }
 
@@ -165,10 +188,18 @@ pub(super) async fn init_system_proxy() {
     logging_error!(Type::Setup, sysopt::Sysopt::global().update_sysproxy().await);
 }
 
+pub(super) async fn init_core_watchdog() {
+    logging_error!(Type::Core, CoreWatchdog::global().init().await);
+}
+
 pub(super) async fn init_system_proxy_guard() {
     sysopt::Sysopt::global().refresh_guard().await;
 }
 
+pub(super) async fn init_lan_pac_server() {
+    server::refresh_lan_pac_server().await;
+}
+
 pub(super) async fn refresh_tray_menu() {
     logging_error!(Type::Setup, Tray::global().update_part().await);
 }
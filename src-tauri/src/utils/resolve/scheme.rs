@@ -1,16 +1,108 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
+use parking_lot::Mutex;
 use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use serde_yaml_ng::Mapping;
 use smartstring::alias::String;
 use tauri::Url;
 
 use crate::{
-    config::{Config, PrfItem, profiles},
-    core::{CoreManager, handle},
+    cmd,
+    config::{Config, IProfiles, PrfItem, PrfOption, profiles},
+    core::{CoreManager, Timer, handle},
+    feat,
+    utils::{
+        headless, help,
+        network::{NetworkManager, ProxyType},
+        window_manager::WindowManager,
+    },
 };
 use clash_verge_logging::{Type, logging, logging_error};
 
+/// 支持的最高深链接版本，链接通过 `v` 参数声明，缺省为 1
+const DEEP_LINK_VERSION: u32 = 1;
+/// 超时未处理的确认请求直接丢弃
+const PENDING_TTL: Duration = Duration::from_secs(10 * 60);
+/// 同时等待确认的链接上限，超出时丢弃最早的请求
+const MAX_PENDING_LINKS: usize = 8;
+const FETCH_TIMEOUT_SECS: u64 = 20;
+
+static PENDING_LINKS: Mutex<Vec<PendingLink>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChainKind {
+    Merge,
+    Script,
+}
+
+impl ChainKind {
+    const fn global_uid(self) -> &'static str {
+        match self {
+            Self::Merge => "Merge",
+            Self::Script => "Script",
+        }
+    }
+
+    const fn action(self) -> &'static str {
+        match self {
+            Self::Merge => "import-merge",
+            Self::Script => "import-script",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum DeepLinkAction {
+    /// `install-config`，导入订阅
+    ImportProfile {
+        url: std::string::String,
+        name: Option<String>,
+        option: PrfOption,
+    },
+    /// `import-chain`，导入 Merge 或 Script，未指定订阅时写入全局扩展
+    ImportChain {
+        kind: ChainKind,
+        url: std::string::String,
+        profile: Option<String>,
+    },
+    /// `switch-profile`，按 uid 或名称切换订阅
+    SwitchProfile { profile: String },
+    /// `set-mode`，切换代理模式
+    SetMode { mode: String },
+}
+
+/// 确认对话框中展示的链接摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepLinkPreview {
+    pub id: String,
+    pub action: &'static str,
+    /// 远程内容的来源主机
+    pub host: Option<String>,
+    pub name: Option<String>,
+    /// 下载内容的字节数
+    pub size: Option<usize>,
+}
+
+/// 已完成下载与校验，只待执行的链接
+enum PreparedLink {
+    Profile(Box<PrfItem>),
+    Chain {
+        target: Box<PrfItem>,
+        data: String,
+        applies_to_current: bool,
+    },
+    SwitchProfile(String),
+    SetMode(String),
+}
+
+struct PendingLink {
+    preview: DeepLinkPreview,
+    prepared: PreparedLink,
+    created_at: Instant,
+}
+
 pub(super) async fn resolve_scheme(param: &str) -> Result<()> {
     logging!(info, Type::Config, "received deep link: {param}");
 
@@ -25,32 +117,209 @@ pub(super) async fn resolve_scheme(param: &str) -> Result<()> {
     let link_parsed =
         Url::parse(param_str).map_err(|e| anyhow::anyhow!("failed to parse deep link: {:?}, param: {:?}", e, param))?;
 
-    let Some((url, name)) = extract_subscription_info(&link_parsed) else {
-        logging!(error, Type::Config, "missing url parameter in deep link: {}", param_str);
-        return Ok(());
+    let result = handle_deep_link(&link_parsed).await;
+    if let Err(err) = &result {
+        handle::Handle::notice_message("deep_link::error", err.to_string());
+    }
+    result
+}
+
+async fn handle_deep_link(link: &Url) -> Result<()> {
+    let action = parse_deep_link(link)?;
+    // 只有新增订阅可以免确认，导入 Merge / Script 会覆盖已有内容，始终需要确认
+    let trusted = match &action {
+        DeepLinkAction::ImportProfile { url, .. } => {
+            let verge = Config::verge().await.latest_arc();
+            is_trusted_source(url, verge.deep_link_trusted_domains.as_deref().unwrap_or_default())
+        }
+        DeepLinkAction::ImportChain { .. } | DeepLinkAction::SwitchProfile { .. } | DeepLinkAction::SetMode { .. } => {
+            false
+        }
     };
 
-    import_subscription(&url, name.as_ref()).await;
+    let (preview, prepared) = prepare(action).await?;
+    if trusted {
+        logging!(info, Type::Config, "deep link from trusted source, skip confirmation");
+        return execute(prepared).await;
+    }
+    if headless::is_headless() {
+        bail!("deep link requires confirmation, which is not available in headless mode");
+    }
+
+    push_pending(
+        &mut PENDING_LINKS.lock(),
+        PendingLink {
+            preview: preview.clone(),
+            prepared,
+            created_at: Instant::now(),
+        },
+    );
+    // 轻量模式下窗口已销毁，前端加载后会通过 get_pending_deep_links 补取
+    WindowManager::show_main_window().await;
+    handle::Handle::notify_deep_link_confirm(&preview);
     Ok(())
 }
 
-fn extract_subscription_info(link_parsed: &Url) -> Option<(std::string::String, Option<String>)> {
-    if !matches!(link_parsed.scheme(), "clash" | "clash-verge") {
-        return None;
+/// 丢弃过期的请求，超出上限时移除最早的请求
+fn prune_pending(pending: &mut Vec<PendingLink>) {
+    pending.retain(|link| link.created_at.elapsed() < PENDING_TTL);
+    let overflow = pending.len().saturating_sub(MAX_PENDING_LINKS);
+    if overflow > 0 {
+        logging!(warn, Type::Config, "too many pending deep links, dropping {overflow}");
+        pending.drain(..overflow);
+    }
+}
+
+fn push_pending(pending: &mut Vec<PendingLink>, link: PendingLink) {
+    pending.push(link);
+    prune_pending(pending);
+}
+
+/// 等待用户确认的深链接
+pub fn pending_deep_links() -> Vec<DeepLinkPreview> {
+    let mut pending = PENDING_LINKS.lock();
+    prune_pending(&mut pending);
+    pending.iter().map(|link| link.preview.clone()).collect()
+}
+
+/// 确认后执行深链接，拒绝则直接丢弃
+pub async fn respond_deep_link(id: &str, accept: bool) -> Result<()> {
+    let link = {
+        let mut pending = PENDING_LINKS.lock();
+        prune_pending(&mut pending);
+        let index = pending
+            .iter()
+            .position(|link| link.preview.id == id)
+            .ok_or_else(|| anyhow!("deep link request has expired"))?;
+        pending.remove(index)
+    };
+
+    if !accept {
+        logging!(info, Type::Config, "deep link {} rejected", link.preview.action);
+        return Ok(());
+    }
+    logging!(info, Type::Config, "deep link {} confirmed", link.preview.action);
+    let result = execute(link.prepared).await;
+    if let Err(err) = &result {
+        handle::Handle::notice_message("deep_link::error", err.to_string());
+    }
+    result
+}
+
+/// 解析深链接，`url` 参数需放在最后，其后的内容都视为远程地址
+fn parse_deep_link(link: &Url) -> Result<DeepLinkAction> {
+    if !matches!(link.scheme(), "clash" | "clash-verge") {
+        bail!("unsupported deep link scheme \"{}\"", link.scheme());
+    }
+
+    let params = LinkParams::new(link);
+    let version = match params.get("v") {
+        Some(version) => version
+            .parse::<u32>()
+            .map_err(|_| anyhow!("invalid deep link version \"{version}\""))?,
+        None => 1,
+    };
+    if version == 0 || version > DEEP_LINK_VERSION {
+        bail!("deep link version {version} is not supported, please upgrade Clash Verge");
+    }
+
+    let action = match link.host_str().unwrap_or_default() {
+        "install-config" => parse_install_config(link, &params)?,
+        "import-chain" => {
+            let kind = match params.get("type").as_deref() {
+                Some("merge") => ChainKind::Merge,
+                Some("script") => ChainKind::Script,
+                Some(kind) => bail!("unsupported chain type \"{kind}\""),
+                None => bail!("missing type parameter in deep link"),
+            };
+            DeepLinkAction::ImportChain {
+                kind,
+                url: extract_subscription_url(link).ok_or_else(|| anyhow!("missing url parameter in deep link"))?,
+                profile: params.get("profile"),
+            }
+        }
+        "switch-profile" => DeepLinkAction::SwitchProfile {
+            profile: params
+                .get("profile")
+                .ok_or_else(|| anyhow!("missing profile parameter in deep link"))?,
+        },
+        "set-mode" => match params.get("mode").as_deref() {
+            Some(mode @ ("rule" | "global" | "direct")) => DeepLinkAction::SetMode { mode: mode.into() },
+            _ => bail!("mode must be one of rule, global or direct"),
+        },
+        action => bail!("unsupported deep link action \"{action}\""),
+    };
+    Ok(action)
+}
+
+fn parse_install_config(link: &Url, params: &LinkParams) -> Result<DeepLinkAction> {
+    let url = extract_subscription_url(link).ok_or_else(|| anyhow!("missing url parameter in deep link"))?;
+    // 兼容旧链接中位于 url 之后的 name
+    let name = params.get("name").or_else(|| {
+        link.query_pairs()
+            .find(|(key, _)| key == "name")
+            .map(|(_, value)| value.into_owned().into())
+    });
+    let update_interval = match params.get("update_interval") {
+        Some(interval) => Some(
+            interval
+                .parse::<u64>()
+                .map_err(|_| anyhow!("invalid update_interval \"{interval}\""))?,
+        ),
+        None => None,
+    };
+    let self_proxy = match params.get("self_proxy").as_deref() {
+        Some("1" | "true") => Some(true),
+        Some("0" | "false") => Some(false),
+        Some(flag) => bail!("invalid self_proxy \"{flag}\""),
+        None => None,
+    };
+
+    Ok(DeepLinkAction::ImportProfile {
+        url,
+        name,
+        option: PrfOption {
+            user_agent: params.get("user_agent"),
+            update_interval,
+            self_proxy,
+            ..PrfOption::default()
+        },
+    })
+}
+
+/// `url` 之前的查询参数
+struct LinkParams(Vec<(std::string::String, std::string::String)>);
+
+impl LinkParams {
+    fn new(link: &Url) -> Self {
+        let query = link.query().unwrap_or_default();
+        let head = url_param_start(query).map_or(query, |pos| &query[..pos]);
+        let mut head_link = link.clone();
+        head_link.set_query(Some(head));
+        Self(head_link.query_pairs().into_owned().collect())
     }
 
-    let name = link_parsed
-        .query_pairs()
-        .find(|(key, _)| key == "name")
-        .map(|(_, value)| value.into_owned().into());
-    let url = extract_subscription_url(link_parsed)?;
-    Some((url, name))
+    fn get(&self, key: &str) -> Option<String> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(_, value)| value.as_str().into())
+    }
+}
+
+fn url_param_start(query: &str) -> Option<usize> {
+    if query.starts_with("url=") {
+        Some(0)
+    } else {
+        query.find("&url=").map(|pos| pos + 1)
+    }
 }
 
 fn extract_subscription_url(link_parsed: &Url) -> Option<std::string::String> {
     let query = link_parsed.query()?;
     let prefix = "url=";
-    let pos = query.find(prefix)?;
+    let pos = url_param_start(query)?;
     let raw_url = query[pos + prefix.len()..].trim();
     Some(decode_subscription_url(raw_url))
 }
@@ -75,22 +344,212 @@ fn decode_subscription_url(raw_url: &str) -> std::string::String {
     candidate
 }
 
-async fn import_subscription(url: &str, name: Option<&String>) {
+fn source_host(url: &str) -> Option<String> {
+    Url::parse(url).ok()?.host_str().map(Into::into)
+}
+
+/// 仅信任 https 来源，域名匹配自身及其子域名
+fn is_trusted_source(url: &str, trusted_domains: &[String]) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    let Some(host) = url.host_str().filter(|_| url.scheme() == "https") else {
+        return false;
+    };
+    let host = host.to_ascii_lowercase();
+
+    trusted_domains.iter().any(|domain| {
+        let domain = domain
+            .trim()
+            .trim_start_matches("*.")
+            .trim_start_matches('.')
+            .to_ascii_lowercase();
+        !domain.is_empty()
+            && (host == domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|rest| rest.ends_with('.')))
+    })
+}
+
+/// 下载并校验链接内容，生成确认时展示的摘要
+async fn prepare(action: DeepLinkAction) -> Result<(DeepLinkPreview, PreparedLink)> {
+    let id: String = help::get_uid("d").into();
+    match action {
+        DeepLinkAction::ImportProfile { url, name, option } => {
+            let mut item = PrfItem::from_url(&url, name.as_ref(), None, Some(&option)).await?;
+            // from_url 不保存 UA 与代理选项，补回后续更新时沿用
+            item.option = PrfOption::merge(item.option.as_ref(), Some(&option));
+            let preview = DeepLinkPreview {
+                id,
+                action: "import-profile",
+                host: source_host(&url),
+                name: item.name.clone(),
+                size: item.file_data.as_ref().map(|data| data.len()),
+            };
+            Ok((preview, PreparedLink::Profile(Box::new(item))))
+        }
+        DeepLinkAction::ImportChain { kind, url, profile } => {
+            let (target, name, applies_to_current) = chain_target(kind, profile.as_deref()).await?;
+            let data = fetch_text(&url).await?;
+            validate_chain(kind, &data)?;
+            let preview = DeepLinkPreview {
+                id,
+                action: kind.action(),
+                host: source_host(&url),
+                name: Some(name),
+                size: Some(data.len()),
+            };
+            let prepared = PreparedLink::Chain {
+                target: Box::new(target),
+                data,
+                applies_to_current,
+            };
+            Ok((preview, prepared))
+        }
+        DeepLinkAction::SwitchProfile { profile } => {
+            let profiles = Config::profiles().await.latest_arc();
+            let item = find_profile(&profiles, &profile)?;
+            let preview = DeepLinkPreview {
+                id,
+                action: "switch-profile",
+                host: None,
+                name: item.name.clone().or_else(|| item.uid.clone()),
+                size: None,
+            };
+            Ok((
+                preview,
+                PreparedLink::SwitchProfile(item.uid.clone().unwrap_or_default()),
+            ))
+        }
+        DeepLinkAction::SetMode { mode } => {
+            let preview = DeepLinkPreview {
+                id,
+                action: "set-mode",
+                host: None,
+                name: Some(mode.clone()),
+                size: None,
+            };
+            Ok((preview, PreparedLink::SetMode(mode)))
+        }
+    }
+}
+
+async fn execute(prepared: PreparedLink) -> Result<()> {
+    match prepared {
+        PreparedLink::Profile(item) => import_profile_item(*item).await,
+        PreparedLink::Chain {
+            target,
+            data,
+            applies_to_current,
+        } => {
+            target.save_file(data).await?;
+            logging!(info, Type::Config, "deep link imported {:?}", target.uid);
+            if applies_to_current {
+                refresh_core_config().await;
+            }
+            handle::Handle::notice_message("deep_link::ok", "");
+            Ok(())
+        }
+        PreparedLink::SwitchProfile(uid) => match cmd::patch_profiles_config_by_profile_index(uid).await {
+            Ok(true) => Ok(()),
+            Ok(false) => bail!("profile is invalid or another switch is in progress"),
+            Err(err) => bail!("{err}"),
+        },
+        PreparedLink::SetMode(mode) => {
+            feat::change_clash_mode(mode).await;
+            Ok(())
+        }
+    }
+}
+
+/// 按 uid 或名称查找订阅，uid 优先
+fn find_profile<'a>(profiles: &'a IProfiles, profile: &str) -> Result<&'a PrfItem> {
+    let items = profiles
+        .get_items()
+        .into_iter()
+        .flatten()
+        .filter(|item| matches!(item.itype.as_deref(), Some("remote" | "local")))
+        .collect::<Vec<_>>();
+    if let Some(item) = items.iter().copied().find(|item| item.uid.as_deref() == Some(profile)) {
+        return Ok(item);
+    }
+
+    match items
+        .into_iter()
+        .filter(|item| item.name.as_deref() == Some(profile))
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [item] => Ok(*item),
+        [] => bail!("profile \"{profile}\" not found"),
+        _ => bail!("profile name \"{profile}\" is ambiguous, use its uid instead"),
+    }
+}
+
+/// 返回要覆盖的扩展项、展示名称以及是否作用于当前订阅
+async fn chain_target(kind: ChainKind, profile: Option<&str>) -> Result<(PrfItem, String, bool)> {
+    let profiles = Config::profiles().await.latest_arc();
+    let (uid, name, applies_to_current) = match profile {
+        Some(profile) => {
+            let item = find_profile(&profiles, profile)?;
+            let uid = match kind {
+                ChainKind::Merge => item.current_merge(),
+                ChainKind::Script => item.current_script(),
+            }
+            .ok_or_else(|| anyhow!("profile \"{profile}\" has no {} item", kind.global_uid()))?;
+            let is_current = item
+                .uid
+                .as_ref()
+                .is_some_and(|uid| profiles.is_current_profile_index(uid));
+            (
+                uid.clone(),
+                item.name.clone().unwrap_or_else(|| profile.into()),
+                is_current,
+            )
+        }
+        None => (kind.global_uid().into(), kind.global_uid().into(), true),
+    };
+    let target = profiles.get_item(&uid)?.clone();
+    Ok((target, name, applies_to_current))
+}
+
+async fn fetch_text(url: &str) -> Result<String> {
+    let resp = NetworkManager::new()
+        .get_with_interrupt(url, ProxyType::None, Some(FETCH_TIMEOUT_SECS), None, false)
+        .await?;
+    let status = resp.status();
+    if !status.is_success() {
+        bail!("failed to fetch {} with status {status}", help::mask_url(url));
+    }
+    Ok(resp.text_with_charset()?.into())
+}
+
+fn validate_chain(kind: ChainKind, data: &str) -> Result<()> {
+    match kind {
+        ChainKind::Merge => {
+            serde_yaml_ng::from_str::<Mapping>(data).map_err(|e| anyhow!("invalid Merge content: {e}"))?;
+        }
+        ChainKind::Script => {
+            if !data.contains("function main") {
+                bail!("invalid Script content: missing function main");
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn import_profile_item(mut item: PrfItem) -> Result<()> {
     let had_current_profile = {
         let profiles = Config::profiles().await;
         profiles.latest_arc().current.is_some()
     };
 
-    let Some(mut item) = fetch_profile_item(url, name).await else {
-        return;
-    };
-
     let uid = item.uid.clone().unwrap_or_default();
     if let Err(e) = profiles::profiles_append_item_safe(&mut item).await {
         logging!(error, Type::Config, "failed to import subscription url: {:?}", e);
         Config::profiles().await.discard();
-        handle::Handle::notice_message("import_sub_url::error", e.to_string());
-        return;
+        return Err(e);
     }
 
     Config::profiles().await.apply();
@@ -100,18 +559,11 @@ async fn import_subscription(url: &str, name: Option<&String>) {
         "", // 空 msg 传入，我们不希望导致 后端-前端-后端 死循环，这里只做提醒。
     );
 
-    post_import_updates(&uid, had_current_profile).await;
-}
-
-async fn fetch_profile_item(url: &str, name: Option<&String>) -> Option<PrfItem> {
-    match PrfItem::from_url(url, name, None, None).await {
-        Ok(item) => Some(item),
-        Err(e) => {
-            logging!(error, Type::Config, "failed to parse profile from url: {:?}", e);
-            handle::Handle::notice_message("import_sub_url::error", e.to_string());
-            None
-        }
+    if item.option.as_ref().and_then(|option| option.update_interval).is_some() {
+        logging_error!(Type::Timer, Timer::global().refresh().await);
     }
+    post_import_updates(&uid, had_current_profile).await;
+    Ok(())
 }
 
 async fn post_import_updates(uid: &String, had_current_profile: bool) {
@@ -155,3 +607,129 @@ async fn refresh_core_config() {
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{
+        ChainKind, DeepLinkAction, DeepLinkPreview, MAX_PENDING_LINKS, PENDING_TTL, PendingLink, PreparedLink,
+        is_trusted_source, parse_deep_link, push_pending,
+    };
+    use crate::config::PrfOption;
+    use smartstring::alias::String;
+    use std::time::{Duration, Instant};
+    use tauri::Url;
+
+    fn parse(link: &str) -> anyhow::Result<DeepLinkAction> {
+        parse_deep_link(&Url::parse(link).expect("valid link"))
+    }
+
+    #[test]
+    fn parse_legacy_install_config() {
+        let action = parse("clash://install-config?url=https%3A%2F%2Fexample.com%2Fsub%3Ftoken%3D1&name=Work")
+            .expect("legacy link");
+        assert_eq!(
+            action,
+            DeepLinkAction::ImportProfile {
+                url: "https://example.com/sub?token=1&name=Work".into(),
+                name: Some("Work".into()),
+                option: PrfOption::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_install_options() {
+        let action = parse(
+            "clash://install-config?v=1&name=Work&user_agent=clash.meta&update_interval=60&self_proxy=1&url=https://example.com/sub?v=2&a=b",
+        )
+        .expect("install link");
+        assert_eq!(
+            action,
+            DeepLinkAction::ImportProfile {
+                url: "https://example.com/sub?v=2&a=b".into(),
+                name: Some("Work".into()),
+                option: PrfOption {
+                    user_agent: Some("clash.meta".into()),
+                    update_interval: Some(60),
+                    self_proxy: Some(true),
+                    ..PrfOption::default()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn parse_other_actions() {
+        assert_eq!(
+            parse("clash://import-chain?type=script&profile=Work&url=https://example.com/a.js").expect("chain link"),
+            DeepLinkAction::ImportChain {
+                kind: ChainKind::Script,
+                url: "https://example.com/a.js".into(),
+                profile: Some("Work".into()),
+            }
+        );
+        assert_eq!(
+            parse("clash-verge://switch-profile?profile=R1a2b3").expect("switch link"),
+            DeepLinkAction::SwitchProfile {
+                profile: "R1a2b3".into()
+            }
+        );
+        assert_eq!(
+            parse("clash://set-mode?mode=global").expect("mode link"),
+            DeepLinkAction::SetMode { mode: "global".into() }
+        );
+    }
+
+    #[test]
+    fn reject_invalid_links() {
+        assert!(parse("clash://install-config?name=Work").is_err());
+        assert!(parse("clash://install-config?v=2&url=https://example.com/sub").is_err());
+        assert!(parse("clash://import-chain?type=rules&url=https://example.com/a.yaml").is_err());
+        assert!(parse("clash://set-mode?mode=script").is_err());
+        assert!(parse("clash://uninstall").is_err());
+        assert!(parse("https://install-config?url=https://example.com/sub").is_err());
+    }
+
+    #[test]
+    fn match_trusted_domains() {
+        let trusted: Vec<String> = vec!["example.com".into(), "*.sub.example.org".into()];
+        assert!(is_trusted_source("https://example.com/sub", &trusted));
+        assert!(is_trusted_source("https://cdn.Example.com/sub", &trusted));
+        assert!(is_trusted_source("https://a.sub.example.org/sub", &trusted));
+        assert!(!is_trusted_source("https://badexample.com/sub", &trusted));
+        assert!(!is_trusted_source("https://example.org/sub", &trusted));
+        assert!(!is_trusted_source("http://example.com/sub", &trusted));
+        assert!(!is_trusted_source("https://example.com/sub", &[]));
+    }
+
+    fn pending(id: usize, created_at: Instant) -> PendingLink {
+        PendingLink {
+            preview: DeepLinkPreview {
+                id: id.to_string().into(),
+                action: "set-mode",
+                host: None,
+                name: None,
+                size: None,
+            },
+            prepared: PreparedLink::SetMode("rule".into()),
+            created_at,
+        }
+    }
+
+    #[test]
+    fn pending_links_are_capped_and_expire() {
+        let mut links = Vec::new();
+        let expired = Instant::now()
+            .checked_sub(PENDING_TTL + Duration::from_secs(1))
+            .expect("expired instant");
+        push_pending(&mut links, pending(0, expired));
+        for id in 1..=MAX_PENDING_LINKS + 2 {
+            push_pending(&mut links, pending(id, Instant::now()));
+        }
+
+        let ids = links.iter().map(|link| link.preview.id.as_str()).collect::<Vec<_>>();
+        let expected = (3..=MAX_PENDING_LINKS + 2).map(|id| id.to_string()).collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+    }
+}
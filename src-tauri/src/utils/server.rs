@@ -1,8 +1,10 @@
-use super::resolve;
+use super::{headless, resolve};
 use crate::{
     cmd::is_port_in_use,
     config::{Config, DEFAULT_PAC, IVerge},
-    module::lightweight,
+    core::{CoreManager, pac, sysopt},
+    feat,
+    module::{automation_api, lightweight},
     process::AsyncHandler,
     utils::window_manager::WindowManager,
 };
@@ -12,7 +14,10 @@ use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
 use reqwest::ClientBuilder;
 use smartstring::alias::String;
-use std::time::Duration;
+use std::{
+    net::{Ipv4Addr, TcpListener},
+    time::Duration,
+};
 use tokio::sync::oneshot;
 use warp::Filter as _;
 
@@ -23,6 +28,8 @@ struct QueryParam {
 
 // 关闭 embedded server 的信号发送端
 static SHUTDOWN_SENDER: OnceCell<Mutex<Option<oneshot::Sender<()>>>> = OnceCell::new();
+// 局域网 PAC 服务的监听地址与关闭信号
+static LAN_PAC_SERVER: Mutex<Option<(Ipv4Addr, oneshot::Sender<()>)>> = Mutex::new(None);
 
 /// check whether there is already exists
 pub async fn check_singleton() -> Result<()> {
@@ -36,9 +43,11 @@ pub async fn check_singleton() -> Result<()> {
             #[cfg(not(target_os = "macos"))]
             {
                 let param = argvs[1].as_str();
-                if param.starts_with("clash:") {
+                if param.starts_with("clash:") || param.starts_with("clash-verge:") {
+                    // 链接本身带有 `&` 分隔的参数，需要整体编码
                     client
-                        .get(format!("http://127.0.0.1:{port}/commands/scheme?param={param}"))
+                        .get(format!("http://127.0.0.1:{port}/commands/scheme"))
+                        .query(&[("param", param)])
                         .send()
                         .await?;
                 }
@@ -64,8 +73,17 @@ pub fn embed_server() {
         .set(Mutex::new(Some(shutdown_tx)))
         .expect("failed to set shutdown signal for embedded server");
     let port = IVerge::get_singleton_port();
+    if headless::is_headless()
+        && let Err(err) = headless::init_command_token()
+    {
+        logging!(error, Type::Setup, "Failed to write headless command token: {err}");
+    }
 
     let visible = warp::path!("commands" / "visible").and_then(|| async {
+        if headless::is_headless() {
+            logging!(info, Type::Window, "Ignoring window restore request in headless mode");
+            return Ok(warp::reply::with_status("ok".to_string(), warp::http::StatusCode::OK));
+        }
         logging!(info, Type::Window, "检测到从单例模式恢复应用窗口");
         if !lightweight::exit_lightweight_mode().await {
             WindowManager::show_main_window().await;
@@ -78,28 +96,8 @@ pub fn embed_server() {
         ))
     });
 
-    let pac = warp::path!("commands" / "pac").and_then(|| async move {
-        let verge_config = Config::verge().await;
-        let clash_config = Config::clash().await;
-
-        let pac_content = verge_config
-            .data_arc()
-            .pac_file_content
-            .clone()
-            .unwrap_or_else(|| DEFAULT_PAC.into());
-
-        let pac_port = verge_config
-            .data_arc()
-            .verge_mixed_port
-            .unwrap_or_else(|| clash_config.data_arc().get_mixed_port());
-        let processed_content = pac_content.replace("%mixed-port%", &format!("{pac_port}"));
-        Ok::<_, warp::Rejection>(
-            warp::http::Response::builder()
-                .header("Content-Type", "application/x-ns-proxy-autoconfig")
-                .body(processed_content)
-                .unwrap_or_default(),
-        )
-    });
+    let pac = warp::path!("commands" / "pac")
+        .and_then(|| async move { Ok::<_, warp::Rejection>(pac_response(pac_content().await)) });
 
     // Use map instead of and_then to avoid Send issues
     let scheme = warp::path!("commands" / "scheme")
@@ -114,7 +112,38 @@ pub fn embed_server() {
             ))
         });
 
-    let commands = visible.or(scheme).or(pac);
+    // 无界面模式下的控制接口，需要 POST 并携带令牌，防止网页跨站请求
+    let reload = warp::post()
+        .and(warp::path!("commands" / "reload"))
+        .and(warp::header::optional::<std::string::String>("authorization"))
+        .and_then(|auth: Option<std::string::String>| async move {
+            Ok::<_, warp::Rejection>(headless_command(auth.as_deref(), headless::reload()).await)
+        });
+    let restart = warp::post()
+        .and(warp::path!("commands" / "restart"))
+        .and(warp::header::optional::<std::string::String>("authorization"))
+        .and_then(|auth: Option<std::string::String>| async move {
+            Ok::<_, warp::Rejection>(headless_command(auth.as_deref(), headless::restart_core()).await)
+        });
+    let quit = warp::post()
+        .and(warp::path!("commands" / "quit"))
+        .and(warp::header::optional::<std::string::String>("authorization"))
+        .and_then(|auth: Option<std::string::String>| async move {
+            // 先返回响应再退出
+            let quit = async {
+                AsyncHandler::spawn(feat::quit);
+                Ok(())
+            };
+            Ok::<_, warp::Rejection>(headless_command(auth.as_deref(), quit).await)
+        });
+
+    let commands = visible
+        .or(scheme)
+        .or(pac)
+        .or(reload)
+        .or(restart)
+        .or(quit)
+        .or(automation_api::routes());
 
     AsyncHandler::spawn(move || async move {
         warp::serve(commands)
@@ -128,6 +157,117 @@ pub fn embed_server() {
     });
 }
 
+async fn pac_content() -> std::string::String {
+    let verge_config = Config::verge().await.data_arc();
+
+    let generate_from_rules = verge_config.pac_generate_from_rules.unwrap_or(false);
+    if generate_from_rules {
+        pac::generated_pac().await.into()
+    } else {
+        let pac_content = verge_config
+            .pac_file_content
+            .clone()
+            .unwrap_or_else(|| DEFAULT_PAC.into());
+
+        let (host, port) = sysopt::proxy_endpoint().await;
+        let content = pac_content.replace("%mixed-port%", &format!("{port}"));
+        // 连接外部内核时代理位于远程主机
+        if CoreManager::global().external_proxy().is_some() {
+            content.replace(&format!("127.0.0.1:{port}"), &format!("{host}:{port}"))
+        } else {
+            content
+        }
+    }
+}
+
+fn pac_response(content: std::string::String) -> warp::http::Response<std::string::String> {
+    warp::http::Response::builder()
+        .header("Content-Type", "application/x-ns-proxy-autoconfig")
+        .body(content)
+        .unwrap_or_default()
+}
+
+/// 局域网设备拿到的 PAC 需要把本机回环地址换成局域网地址
+fn lan_pac(content: &str, proxy_host: &str, port: u16, lan_ip: Ipv4Addr) -> std::string::String {
+    let target = format!("{lan_ip}:{port}");
+    let mut content = content.to_owned();
+    for host in [proxy_host, "127.0.0.1", "localhost"] {
+        content = content.replace(&format!("{host}:{port}"), &target);
+    }
+    content
+}
+
+/// 根据 allow-lan 与所选网卡启停局域网 PAC 服务，仅对外提供 PAC 路由
+pub async fn refresh_lan_pac_server() {
+    let target = feat::lan_sharing_host().await;
+    let mut server = LAN_PAC_SERVER.lock();
+    if server.as_ref().map(|(ip, _)| *ip) == target {
+        return;
+    }
+    if let Some((ip, sender)) = server.take() {
+        logging!(info, Type::Network, "Stopping LAN PAC server on {ip}");
+        sender.send(()).ok();
+    }
+
+    let Some(lan_ip) = target else {
+        return;
+    };
+    let port = IVerge::get_singleton_port();
+    if TcpListener::bind((lan_ip, port)).is_err() {
+        logging!(warn, Type::Network, "LAN PAC server cannot bind {lan_ip}:{port}");
+        return;
+    }
+
+    let pac = warp::path!("commands" / "pac").and_then(move || async move {
+        let verge = Config::verge().await.data_arc();
+        let proxy_host = verge.proxy_host.clone().unwrap_or_else(|| "127.0.0.1".into());
+        let mixed_port = match verge.verge_mixed_port {
+            Some(port) => port,
+            None => Config::clash().await.data_arc().get_mixed_port(),
+        };
+        let content = lan_pac(&pac_content().await, &proxy_host, mixed_port, lan_ip);
+        Ok::<_, warp::Rejection>(pac_response(content))
+    });
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    *server = Some((lan_ip, shutdown_tx));
+    drop(server);
+    logging!(info, Type::Network, "Serving LAN PAC on {lan_ip}:{port}");
+    AsyncHandler::spawn(move || async move {
+        warp::serve(pac)
+            .bind((lan_ip, port))
+            .await
+            .graceful(async {
+                shutdown_rx.await.ok();
+            })
+            .run()
+            .await;
+    });
+}
+
+/// 仅在无界面模式下执行控制命令
+async fn headless_command(
+    auth: Option<&str>,
+    command: impl Future<Output = Result<()>>,
+) -> warp::reply::WithStatus<std::string::String> {
+    if !headless::verify_command_token(auth) {
+        return warp::reply::with_status(
+            "invalid or missing token".to_string(),
+            warp::http::StatusCode::UNAUTHORIZED,
+        );
+    }
+    if !headless::is_headless() {
+        return warp::reply::with_status("headless mode only".to_string(), warp::http::StatusCode::FORBIDDEN);
+    }
+    match command.await {
+        Ok(()) => warp::reply::with_status("ok".to_string(), warp::http::StatusCode::OK),
+        Err(err) => {
+            logging!(error, Type::System, "Headless command failed: {err}");
+            warp::reply::with_status(err.to_string(), warp::http::StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 pub fn shutdown_embedded_server() {
     logging!(info, Type::Window, "shutting down embedded server");
     if let Some(sender) = SHUTDOWN_SENDER.get()
@@ -135,4 +275,7 @@ pub fn shutdown_embedded_server() {
     {
         sender.send(()).ok();
     }
+    if let Some((_, sender)) = LAN_PAC_SERVER.lock().take() {
+        sender.send(()).ok();
+    }
 }
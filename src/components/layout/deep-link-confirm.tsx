@@ -0,0 +1,129 @@
+import {
+  Checkbox,
+  FormControlLabel,
+  Table,
+  TableBody,
+  TableCell,
+  TableRow,
+  Typography,
+} from '@mui/material'
+import { useEffect, useState } from 'react'
+import { useTranslation } from 'react-i18next'
+
+import { BaseDialog } from '@/components/base'
+import { useListen } from '@/hooks/use-listen'
+import { useVerge } from '@/hooks/use-verge'
+import { getPendingDeepLinks, respondDeepLink } from '@/services/cmds'
+import { showNotice } from '@/services/notice-service'
+import parseTraffic from '@/utils/parse-traffic'
+
+export const DeepLinkConfirm = () => {
+  const { t } = useTranslation()
+  const { addListener } = useListen()
+  const { verge, patchVerge } = useVerge()
+  const [queue, setQueue] = useState<IDeepLinkPreview[]>([])
+  const [trustHost, setTrustHost] = useState(false)
+  const [loading, setLoading] = useState(false)
+
+  useEffect(() => {
+    const enqueue = (previews: IDeepLinkPreview[]) =>
+      setQueue((prev) => [
+        ...prev,
+        ...previews.filter((item) => !prev.some((p) => p.id === item.id)),
+      ])
+
+    // 窗口创建前到达的链接需要主动拉取
+    getPendingDeepLinks().then(enqueue).catch(console.error)
+    const unlisten = addListener<IDeepLinkPreview>(
+      'verge://deep-link-confirm',
+      ({ payload }) => enqueue([payload]),
+    )
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [addListener])
+
+  const current = queue[0]
+  if (!current) return null
+
+  const respond = async (accept: boolean) => {
+    if (loading) return
+    setLoading(true)
+    try {
+      if (accept && trustHost && current.host) {
+        const domains = verge?.deep_link_trusted_domains ?? []
+        if (!domains.includes(current.host)) {
+          await patchVerge({
+            deep_link_trusted_domains: [...domains, current.host],
+          })
+        }
+      }
+      await respondDeepLink(current.id, accept)
+    } catch (err) {
+      showNotice.error(err)
+    } finally {
+      setLoading(false)
+      setTrustHost(false)
+      setQueue((prev) => prev.slice(1))
+    }
+  }
+
+  const rows: Array<[string, string | undefined]> = [
+    [
+      t('layout.components.deepLink.fields.action'),
+      t(`layout.components.deepLink.actions.${current.action}`),
+    ],
+    [t('layout.components.deepLink.fields.host'), current.host],
+    [t('layout.components.deepLink.fields.name'), current.name],
+    [
+      t('layout.components.deepLink.fields.size'),
+      current.size == null ? undefined : parseTraffic(current.size).join(' '),
+    ],
+  ]
+
+  return (
+    <BaseDialog
+      open={true}
+      title={t('layout.components.deepLink.title')}
+      okBtn={t('shared.actions.confirm')}
+      cancelBtn={t('shared.actions.cancel')}
+      loading={loading}
+      contentSx={{ width: 400 }}
+      onOk={() => respond(true)}
+      onCancel={() => respond(false)}
+      onClose={() => respond(false)}
+    >
+      <Typography variant="body2" sx={{ mb: 1 }}>
+        {t('layout.components.deepLink.description')}
+      </Typography>
+      <Table size="small">
+        <TableBody>
+          {rows
+            .filter(([, value]) => value)
+            .map(([label, value]) => (
+              <TableRow key={label}>
+                <TableCell sx={{ whiteSpace: 'nowrap' }}>{label}</TableCell>
+                <TableCell sx={{ wordBreak: 'break-all' }}>{value}</TableCell>
+              </TableRow>
+            ))}
+        </TableBody>
+      </Table>
+      {/* 信任来源只对导入订阅生效，Merge / Script 始终需要确认 */}
+      {current.host && current.action === 'import-profile' && (
+        <FormControlLabel
+          sx={{ mt: 1 }}
+          control={
+            <Checkbox
+              size="small"
+              checked={trustHost}
+              onChange={(e) => setTrustHost(e.target.checked)}
+            />
+          }
+          label={t('layout.components.deepLink.trustHost', {
+            host: current.host,
+          })}
+        />
+      )}
+    </BaseDialog>
+  )
+}
@@ -1,4 +1,4 @@
-import { ContentCopy } from '@mui/icons-material'
+import { AutorenewRounded, ContentCopy } from '@mui/icons-material'
 import {
   Alert,
   Box,
@@ -12,12 +12,13 @@ import {
   Tooltip,
 } from '@mui/material'
 import { useLockFn } from 'ahooks'
-import { useImperativeHandle, useState, type Ref } from 'react'
+import { useEffect, useImperativeHandle, useState, type Ref } from 'react'
 import { useTranslation } from 'react-i18next'
 
 import { BaseDialog, DialogRef, Switch } from '@/components/base'
 import { useClashInfo } from '@/hooks/use-clash'
 import { useVerge } from '@/hooks/use-verge'
+import { checkSecretStrength, rotateClashSecret } from '@/services/cmds'
 import { showNotice } from '@/services/notice-service'
 
 export function ControllerViewer({ ref }: { ref?: Ref<DialogRef> }) {
@@ -26,7 +27,7 @@ export function ControllerViewer({ ref }: { ref?: Ref<DialogRef> }) {
   const [copySuccess, setCopySuccess] = useState<null | string>(null)
   const [isSaving, setIsSaving] = useState(false)
 
-  const { clashInfo, patchInfo } = useClashInfo()
+  const { clashInfo, patchInfo, mutateInfo } = useClashInfo()
   const { verge, patchVerge } = useVerge()
   const [controller, setController] = useState(clashInfo?.server || '')
   const [secret, setSecret] = useState(clashInfo?.secret || '')
@@ -34,6 +35,16 @@ export function ControllerViewer({ ref }: { ref?: Ref<DialogRef> }) {
     verge?.enable_external_controller ?? false,
   )
 
+  const [strength, setStrength] = useState<ISecretStrength>('strong')
+
+  useEffect(() => {
+    if (!open) return
+    const timer = setTimeout(() => {
+      checkSecretStrength(secret).then(setStrength).catch(console.error)
+    }, 300)
+    return () => clearTimeout(timer)
+  }, [open, secret])
+
   // 对话框打开时初始化配置
   useImperativeHandle(ref, () => ({
     open: async () => {
@@ -88,6 +99,19 @@ export function ControllerViewer({ ref }: { ref?: Ref<DialogRef> }) {
     }
   })
 
+  // 生成随机密钥并立即应用到内核
+  const onRotateSecret = useLockFn(async () => {
+    try {
+      setSecret(await rotateClashSecret())
+      mutateInfo()
+      showNotice.success(
+        'settings.sections.externalController.messages.secretRotated',
+      )
+    } catch (err) {
+      showNotice.error(err)
+    }
+  })
+
   // 复制到剪贴板
   const handleCopyToClipboard = useLockFn(
     async (text: string, type: string) => {
@@ -197,7 +221,7 @@ export function ControllerViewer({ ref }: { ref?: Ref<DialogRef> }) {
             <TextField
               size="small"
               sx={{
-                width: 175,
+                width: 140,
                 opacity: enableController ? 1 : 0.5,
                 pointerEvents: enableController ? 'auto' : 'none',
               }}
@@ -220,10 +244,28 @@ export function ControllerViewer({ ref }: { ref?: Ref<DialogRef> }) {
                 <ContentCopy fontSize="small" />
               </IconButton>
             </Tooltip>
+            <Tooltip
+              title={t('settings.sections.externalController.tooltips.rotate')}
+            >
+              <IconButton
+                size="small"
+                onClick={onRotateSecret}
+                color="primary"
+                disabled={isSaving}
+              >
+                <AutorenewRounded fontSize="small" />
+              </IconButton>
+            </Tooltip>
           </Box>
         </ListItem>
       </List>
 
+      {enableController && strength !== 'strong' && (
+        <Alert severity="warning">
+          {t(`settings.sections.externalController.messages.${strength}Secret`)}
+        </Alert>
+      )}
+
       <Snackbar
         open={copySuccess !== null}
         autoHideDuration={2000}
@@ -0,0 +1,283 @@
+import {
+  ContentCopyRounded,
+  Delete,
+  VisibilityRounded,
+} from '@mui/icons-material'
+import {
+  Box,
+  Button,
+  IconButton,
+  List,
+  ListItem,
+  ListItemText,
+  MenuItem,
+  Select,
+  TextField,
+  Typography,
+} from '@mui/material'
+import { writeText } from '@tauri-apps/plugin-clipboard-manager'
+import { useLockFn } from 'ahooks'
+import type { Ref } from 'react'
+import { useImperativeHandle, useState } from 'react'
+import { useTranslation } from 'react-i18next'
+
+import { BaseDialog, DialogRef } from '@/components/base'
+import { useClash } from '@/hooks/use-clash'
+import { useNetworkInterfaces } from '@/hooks/use-network'
+import {
+  addLanUser,
+  getLanOnboarding,
+  getLanSharing,
+  patchLanSharing,
+  removeLanUser,
+} from '@/services/cmds'
+import { showNotice } from '@/services/notice-service'
+
+const splitLines = (value: string) =>
+  value
+    .split('\n')
+    .map((line) => line.trim())
+    .filter(Boolean)
+
+export function LanSharingViewer({ ref }: { ref?: Ref<DialogRef> }) {
+  const { t } = useTranslation()
+  const { mutateClash } = useClash()
+  const { networkInterfaces } = useNetworkInterfaces()
+
+  const [open, setOpen] = useState(false)
+  const [info, setInfo] = useState<ILanSharingInfo>()
+  const [values, setValues] = useState({
+    interface: '',
+    skipAuth: '',
+    allowed: '',
+    disallowed: '',
+  })
+  const [deviceName, setDeviceName] = useState('')
+  const [onboarding, setOnboarding] = useState<ILanOnboarding>()
+
+  const refresh = async () => {
+    const data = await getLanSharing()
+    setInfo(data)
+    return data
+  }
+
+  useImperativeHandle(ref, () => ({
+    open: async () => {
+      setOnboarding(undefined)
+      setDeviceName('')
+      const data = await refresh().catch((err) => {
+        showNotice.error(err)
+        return undefined
+      })
+      if (!data) return
+      setValues({
+        interface: data.interface ?? '',
+        skipAuth: data.skip_auth_prefixes.join('\n'),
+        allowed: data.lan_allowed_ips.join('\n'),
+        disallowed: data.lan_disallowed_ips.join('\n'),
+      })
+      setOpen(true)
+    },
+    close: () => setOpen(false),
+  }))
+
+  const onSave = useLockFn(async () => {
+    try {
+      await patchLanSharing({
+        interface: values.interface,
+        skip_auth_prefixes: splitLines(values.skipAuth),
+        lan_allowed_ips: splitLines(values.allowed),
+        lan_disallowed_ips: splitLines(values.disallowed),
+      })
+      await mutateClash()
+      showNotice.success('shared.feedback.notifications.common.saveSuccess')
+      setOpen(false)
+    } catch (err) {
+      showNotice.error(err)
+    }
+  })
+
+  const onAdd = useLockFn(async () => {
+    if (!deviceName.trim()) return
+    try {
+      setOnboarding(await addLanUser(deviceName))
+      setDeviceName('')
+      await refresh()
+    } catch (err) {
+      showNotice.error(err)
+    }
+  })
+
+  const onRemove = useLockFn(async (username: string) => {
+    try {
+      await removeLanUser(username)
+      if (onboarding?.username === username) setOnboarding(undefined)
+      await refresh()
+    } catch (err) {
+      showNotice.error(err)
+    }
+  })
+
+  const onShow = useLockFn(async (username: string) => {
+    try {
+      setOnboarding(await getLanOnboarding(username))
+    } catch (err) {
+      showNotice.error(err)
+    }
+  })
+
+  const prefixFields = [
+    ['skipAuth', 'skipAuthPrefixes'],
+    ['allowed', 'lanAllowedIps'],
+    ['disallowed', 'lanDisallowedIps'],
+  ] as const
+
+  return (
+    <BaseDialog
+      open={open}
+      title={t('settings.modals.lanSharing.title')}
+      contentSx={{ width: 480 }}
+      okBtn={t('shared.actions.save')}
+      cancelBtn={t('shared.actions.cancel')}
+      onClose={() => setOpen(false)}
+      onCancel={() => setOpen(false)}
+      onOk={onSave}
+    >
+      {!info?.allow_lan && (
+        <Typography variant="body2" color="warning.main" sx={{ mb: 1 }}>
+          {t('settings.modals.lanSharing.messages.allowLanDisabled')}
+        </Typography>
+      )}
+
+      <ListItem sx={{ padding: '5px 2px' }}>
+        <ListItemText primary={t('settings.modals.lanSharing.interface')} />
+        <Select
+          size="small"
+          sx={{ width: 200 }}
+          value={values.interface}
+          displayEmpty
+          onChange={(e) =>
+            setValues((v) => ({ ...v, interface: e.target.value }))
+          }
+        >
+          <MenuItem value="">{t('settings.modals.lanSharing.auto')}</MenuItem>
+          {networkInterfaces.map((item) => (
+            <MenuItem key={item.name} value={item.name}>
+              {item.name}
+            </MenuItem>
+          ))}
+        </Select>
+      </ListItem>
+
+      {prefixFields.map(([key, label]) => (
+        <TextField
+          key={key}
+          fullWidth
+          multiline
+          minRows={2}
+          size="small"
+          sx={{ mt: 1 }}
+          label={t(`settings.modals.lanSharing.${label}`)}
+          placeholder="192.168.1.0/24"
+          value={values[key]}
+          onChange={(e) =>
+            setValues((v) => ({ ...v, [key]: e.target.value }))
+          }
+        />
+      ))}
+
+      <Typography variant="subtitle2" sx={{ mt: 2 }}>
+        {t('settings.modals.lanSharing.devices')}
+      </Typography>
+      <List dense>
+        {info?.users.map((user) => (
+          <ListItem
+            key={user.username}
+            secondaryAction={
+              <>
+                <IconButton
+                  size="small"
+                  onClick={() => onShow(user.username)}
+                >
+                  <VisibilityRounded fontSize="inherit" />
+                </IconButton>
+                <IconButton
+                  size="small"
+                  color="error"
+                  onClick={() => onRemove(user.username)}
+                >
+                  <Delete fontSize="inherit" />
+                </IconButton>
+              </>
+            }
+          >
+            <ListItemText primary={user.name} secondary={user.username} />
+          </ListItem>
+        ))}
+      </List>
+      <Box display="flex" gap={1}>
+        <TextField
+          fullWidth
+          size="small"
+          placeholder={t('settings.modals.lanSharing.deviceName')}
+          value={deviceName}
+          onChange={(e) => setDeviceName(e.target.value)}
+        />
+        <Button variant="contained" size="small" onClick={onAdd}>
+          {t('settings.modals.lanSharing.actions.addDevice')}
+        </Button>
+      </Box>
+
+      {onboarding && (
+        <Box sx={{ mt: 2 }}>
+          <Typography variant="subtitle2">
+            {t('settings.modals.lanSharing.onboarding', {
+              name: onboarding.name,
+            })}
+          </Typography>
+          {(
+            [
+              ['host', `${onboarding.host}:${onboarding.port}`],
+              ['username', onboarding.username],
+              ['password', onboarding.password],
+              ['httpProxy', onboarding.http_proxy],
+              ['socksProxy', onboarding.socks_proxy],
+              ['pacUrl', onboarding.pac_url],
+            ] as const
+          ).map(([label, content]) => (
+            <Box
+              key={label}
+              display="flex"
+              alignItems="center"
+              justifyContent="space-between"
+              gap={1}
+            >
+              <Typography variant="body2" sx={{ whiteSpace: 'nowrap' }}>
+                {t(`settings.modals.lanSharing.fields.${label}`)}
+              </Typography>
+              <Box display="flex" alignItems="center" minWidth={0}>
+                <Typography
+                  variant="body2"
+                  sx={{ wordBreak: 'break-all', userSelect: 'text' }}
+                >
+                  {content}
+                </Typography>
+                <IconButton
+                  size="small"
+                  onClick={async () => {
+                    await writeText(content)
+                    showNotice.success(
+                      'shared.feedback.notifications.common.copySuccess',
+                    )
+                  }}
+                >
+                  <ContentCopyRounded sx={{ fontSize: '18px' }} />
+                </IconButton>
+              </Box>
+            </Box>
+          ))}
+        </Box>
+      )}
+    </BaseDialog>
+  )
+}
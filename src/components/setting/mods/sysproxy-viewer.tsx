@@ -13,6 +13,7 @@ import {
   Typography,
 } from '@mui/material'
 import { useLockFn } from 'ahooks'
+import dayjs from 'dayjs'
 import {
   forwardRef,
   useEffect,
@@ -37,8 +38,12 @@ import { useSystemProxyState } from '@/hooks/use-system-proxy-state'
 import { useVerge } from '@/hooks/use-verge'
 import { useAppData } from '@/providers/app-data-context'
 import {
+  checkBypass,
+  clearProxyGuardHistory,
   getAutotemProxy,
+  getBypassPresets,
   getNetworkInterfacesInfo,
+  getProxyGuardHistory,
   getSystemHostname,
   getSystemProxy,
   patchVergeConfig,
@@ -56,39 +61,6 @@ const DEFAULT_PAC = `function FindProxyForURL(url, host) {
   return "PROXY %proxy_host%:%mixed-port%; SOCKS5 %proxy_host%:%mixed-port%; DIRECT;";
 }`
 
-/** NO_PROXY validation */
-
-// *., cdn*., *, etc.
-const domain_subdomain_part = String.raw`(?:[a-z0-9\-\*]+\.|\*)*`
-// .*, .cn, .moe, .co*, *
-const domain_tld_part = String.raw`(?:\w{2,64}\*?|\*)`
-// *epicgames*, *skk.moe, *.skk.moe, skk.*, sponsor.cdn.skk.moe, *.*, etc.
-// also matches 192.168.*, 10.*, 127.0.0.*, etc. (partial ipv4)
-const rDomainSimple = domain_subdomain_part + domain_tld_part
-
-const ipv4_part = String.raw`\d{1,3}`
-
-const ipv6_part = '(?:[a-fA-F0-9:])+'
-
-const rLocal = `localhost|<local>|localdomain`
-
-const getValidReg = (isWindows: boolean) => {
-  // 127.0.0.1 (full ipv4)
-  const rIPv4Unix = String.raw`(?:${ipv4_part}\.){3}${ipv4_part}(?:\/\d{1,2})?`
-  const rIPv4Windows = String.raw`(?:${ipv4_part}\.){3}${ipv4_part}`
-
-  const rIPv6Unix = String.raw`(?:${ipv6_part}:+)+${ipv6_part}(?:\/\d{1,3})?`
-  const rIPv6Windows = String.raw`(?:${ipv6_part}:+)+${ipv6_part}`
-
-  const rValidPart = `${rDomainSimple}|${
-    isWindows ? rIPv4Windows : rIPv4Unix
-  }|${isWindows ? rIPv6Windows : rIPv6Unix}|${rLocal}`
-  const separator = isWindows ? ';' : ','
-  const rValid = String.raw`^(${rValidPart})(?:${separator}\s?(${rValidPart}))*${separator}?$`
-
-  return new RegExp(rValid)
-}
-
 const splitBypass = (value?: string) =>
   (value ?? '')
     .split(/[,\n;\r]+/)
@@ -99,7 +71,6 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
   const { t } = useTranslation()
   const systemName = getSystem()
   const isWindows = systemName === 'windows'
-  const validReg = useMemo(() => getValidReg(isWindows), [isWindows])
 
   const [open, setOpen] = useState(false)
   const [editorOpen, setEditorOpen] = useState(false)
@@ -108,6 +79,9 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
   const [saving, setSaving] = useState(false)
   const { verge, patchVerge, mutateVerge } = useVerge()
   const [hostOptions, setHostOptions] = useState<string[]>([])
+  const [guardHistory, setGuardHistory] = useState<IProxyDeviation[]>([])
+  const [bypassPresets, setBypassPresets] = useState<IBypassPresetInfo[]>([])
+  const [bypassReport, setBypassReport] = useState<IBypassReport | null>(null)
 
   const { clashConfig } = useAppData()
   const { indicator: isProxyReallyEnabled } = useSystemProxyState()
@@ -120,6 +94,7 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
     enable_bypass_check,
     use_default_bypass,
     system_proxy_bypass,
+    system_proxy_bypass_presets,
     proxy_guard_duration,
     proxy_host,
   } = verge ?? {}
@@ -128,6 +103,7 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
     guard: enable_proxy_guard,
     enable_bypass_check: enable_bypass_check ?? true,
     bypass: system_proxy_bypass,
+    bypass_presets: system_proxy_bypass_presets ?? [],
     duration: proxy_guard_duration ?? 10,
     use_default: use_default_bypass ?? true,
     pac: proxy_auto_config,
@@ -208,13 +184,34 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
     return `http://${host}:${port}/commands/pac`
   }, [value.proxy_host])
 
+  useEffect(() => {
+    if (!open || !value.enable_bypass_check || !value.bypass) {
+      setBypassReport(null)
+      return
+    }
+    let cancelled = false
+    checkBypass(value.bypass)
+      .then((report) => {
+        if (!cancelled) setBypassReport(report)
+      })
+      .catch(console.error)
+    return () => {
+      cancelled = true
+    }
+  }, [open, value.enable_bypass_check, value.bypass])
+
+  const firstBypassError = bypassReport?.errors[0]
   const bypassError =
-    value.enable_bypass_check &&
-    !value.pac &&
-    !value.use_default &&
-    value.bypass
-      ? !validReg.test(value.bypass)
-      : false
+    !value.pac && !value.use_default && firstBypassError !== undefined
+
+  const toggleBypassPreset = (id: IBypassPresetInfo['id']) => {
+    setValue((v) => ({
+      ...v,
+      bypass_presets: v.bypass_presets.includes(id)
+        ? v.bypass_presets.filter((item) => item !== id)
+        : [...v.bypass_presets, id],
+    }))
+  }
 
   const openPacEditor = () => {
     const nextPac = value.pac_content ?? DEFAULT_PAC
@@ -238,6 +235,7 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
         guard: enable_proxy_guard,
         enable_bypass_check: enable_bypass_check ?? true,
         bypass: system_proxy_bypass,
+        bypass_presets: system_proxy_bypass_presets ?? [],
         duration: proxy_guard_duration ?? 10,
         use_default: use_default_bypass ?? true,
         pac: proxy_auto_config,
@@ -245,10 +243,23 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
         proxy_host: proxy_host ?? '127.0.0.1',
       })
       fetchNetworkInterfaces()
+      getBypassPresets().then(setBypassPresets).catch(console.error)
+      getProxyGuardHistory().then(setGuardHistory).catch(console.error)
     },
     close: () => setOpen(false),
   }))
 
+  const lastDeviation = guardHistory.at(-1)
+
+  const onClearGuardHistory = useLockFn(async () => {
+    try {
+      await clearProxyGuardHistory()
+      setGuardHistory([])
+    } catch (err) {
+      showNotice.error(err)
+    }
+  })
+
   // 获取网络接口和主机名
   const fetchNetworkInterfaces = async () => {
     try {
@@ -317,11 +328,16 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
       value.enable_bypass_check &&
       !value.pac &&
       !value.use_default &&
-      value.bypass &&
-      !validReg.test(value.bypass)
+      value.bypass
     ) {
-      showNotice.error('settings.modals.sysproxy.messages.invalidBypass')
-      return
+      const report = await checkBypass(value.bypass)
+      if (report.errors.length > 0) {
+        showNotice.error(
+          'settings.modals.sysproxy.messages.invalidBypass',
+          report.errors.map((error) => error.entry).join(', '),
+        )
+        return
+      }
     }
 
     // 修改验证规则，允许IP和主机名
@@ -358,6 +374,12 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
     if (value.bypass !== system_proxy_bypass) {
       patch.system_proxy_bypass = value.bypass
     }
+    if (
+      value.bypass_presets.join(',') !==
+      (system_proxy_bypass_presets ?? []).join(',')
+    ) {
+      patch.system_proxy_bypass_presets = value.bypass_presets
+    }
     if (value.pac !== proxy_auto_config) {
       patch.proxy_auto_config = value.pac
     }
@@ -495,6 +517,23 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
               </Typography>
             </FlexBox>
           )}
+          {lastDeviation && (
+            <FlexBox sx={{ alignItems: 'center' }}>
+              <Typography className="label">
+                {t('settings.modals.sysproxy.fields.guardDeviations')}
+              </Typography>
+              <Typography
+                className="value"
+                title={lastDeviation.error ?? undefined}
+                sx={{ flex: 1 }}
+              >
+                {`${guardHistory.length} · ${dayjs(lastDeviation.timestamp).format('HH:mm:ss')} ${lastDeviation.changed.join(', ')}`}
+              </Typography>
+              <Button size="small" onClick={onClearGuardHistory}>
+                {t('settings.modals.sysproxy.actions.clearGuardHistory')}
+              </Button>
+            </FlexBox>
+          )}
         </BaseFieldset>
         <ListItem sx={{ padding: '5px 2px' }}>
           <ListItemText
@@ -575,6 +614,30 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
             }}
           />
         </ListItem>
+        <ListItem sx={{ padding: '5px 2px', alignItems: 'start' }}>
+          <ListItemText
+            primary={t('settings.modals.sysproxy.fields.bypassPresets')}
+            sx={{ padding: '3px 0' }}
+          />
+          <Box sx={{ display: 'flex', flexWrap: 'wrap', gap: 1 }}>
+            {bypassPresets.map((preset) => {
+              const selected = value.bypass_presets.includes(preset.id)
+              return (
+                <Chip
+                  key={preset.id}
+                  size="small"
+                  disabled={!enabled}
+                  title={preset.entries.join(separator)}
+                  label={t(`settings.modals.sysproxy.presets.${preset.id}`)}
+                  color={selected ? 'primary' : 'default'}
+                  variant={selected ? 'filled' : 'outlined'}
+                  onClick={() => toggleBypassPreset(preset.id)}
+                />
+              )
+            })}
+          </Box>
+        </ListItem>
+
         {!value.pac && (
           <ListItem sx={{ padding: '5px 2px' }}>
             <ListItemText
@@ -626,8 +689,12 @@ export const SysproxyViewer = forwardRef<DialogRef>((props, ref) => {
             disabled={!enabled}
             error={bypassError}
             helperText={
-              bypassError
-                ? t('settings.modals.sysproxy.messages.invalidBypass')
+              bypassError && firstBypassError
+                ? t('settings.modals.sysproxy.messages.invalidBypassEntry', {
+                    entry: firstBypassError.entry,
+                    position: firstBypassError.start + 1,
+                    reason: firstBypassError.reason,
+                  })
                 : undefined
             }
             placeholder="localhost"
@@ -1,4 +1,4 @@
-import { LanRounded, SettingsRounded } from '@mui/icons-material'
+import { LanRounded, SettingsRounded, ShareRounded } from '@mui/icons-material'
 import { MenuItem, Select, TextField, Typography } from '@mui/material'
 import { invoke } from '@tauri-apps/api/core'
 import { useLockFn } from 'ahooks'
@@ -20,6 +20,7 @@ import { ControllerViewer } from './mods/controller-viewer'
 import { DnsViewer } from './mods/dns-viewer'
 import { HeaderConfiguration } from './mods/external-controller-cors'
 import { GuardState } from './mods/guard-state'
+import { LanSharingViewer } from './mods/lan-sharing-viewer'
 import { NetworkInterfaceViewer } from './mods/network-interface-viewer'
 import { SettingItem, SettingList } from './mods/setting-comp'
 import { TunnelsViewer } from './mods/tunnels-viewer'
@@ -57,6 +58,7 @@ const SettingClash = ({ onError }: Props) => {
   const ctrlRef = useRef<DialogRef>(null)
   const coreRef = useRef<DialogRef>(null)
   const networkRef = useRef<DialogRef>(null)
+  const lanSharingRef = useRef<DialogRef>(null)
   const dnsRef = useRef<DialogRef>(null)
   const corsRef = useRef<DialogRef>(null)
   const tunnelRef = useRef<DialogRef>(null)
@@ -100,20 +102,31 @@ const SettingClash = ({ onError }: Props) => {
       <ControllerViewer ref={ctrlRef} />
       <ClashCoreViewer ref={coreRef} />
       <NetworkInterfaceViewer ref={networkRef} />
+      <LanSharingViewer ref={lanSharingRef} />
       <DnsViewer ref={dnsRef} />
       <HeaderConfiguration ref={corsRef} />
       <TunnelsViewer ref={tunnelRef} />
       <SettingItem
         label={t('settings.sections.clash.form.fields.allowLan')}
         extra={
-          <TooltipIcon
-            title={t('settings.sections.clash.form.tooltips.networkInterface')}
-            color={'inherit'}
-            icon={LanRounded}
-            onClick={() => {
-              networkRef.current?.open()
-            }}
-          />
+          <>
+            <TooltipIcon
+              title={t(
+                'settings.sections.clash.form.tooltips.networkInterface',
+              )}
+              color={'inherit'}
+              icon={LanRounded}
+              onClick={() => {
+                networkRef.current?.open()
+              }}
+            />
+            <TooltipIcon
+              title={t('settings.sections.clash.form.tooltips.lanSharing')}
+              color={'inherit'}
+              icon={ShareRounded}
+              onClick={() => lanSharingRef.current?.open()}
+            />
+          </>
         }
       >
         <GuardState
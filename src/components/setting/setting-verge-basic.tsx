@@ -168,10 +168,18 @@ const SettingVergeBasic = ({ onError }: Props) => {
         >
           <Select size="small" sx={{ width: 140, '> div': { py: '7.5px' } }}>
             <MenuItem value="bash">Bash</MenuItem>
+            <MenuItem value="zsh">Zsh</MenuItem>
             <MenuItem value="fish">Fish</MenuItem>
             <MenuItem value="nushell">Nushell</MenuItem>
             <MenuItem value="cmd">CMD</MenuItem>
             <MenuItem value="powershell">PowerShell</MenuItem>
+            <MenuItem value="docker-daemon">Docker Daemon</MenuItem>
+            <MenuItem value="docker-client">Docker Client</MenuItem>
+            <MenuItem value="git">Git</MenuItem>
+            <MenuItem value="npm">npm</MenuItem>
+            <MenuItem value="pip">pip</MenuItem>
+            <MenuItem value="cargo">Cargo</MenuItem>
+            <MenuItem value="systemd">systemd</MenuItem>
           </Select>
         </GuardState>
       </SettingItem>
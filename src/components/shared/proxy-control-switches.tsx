@@ -19,6 +19,7 @@ import { useServiceUninstaller } from '@/hooks/use-service-uninstaller'
 import { useSystemProxyState } from '@/hooks/use-system-proxy-state'
 import { useSystemState } from '@/hooks/use-system-state'
 import { useVerge } from '@/hooks/use-verge'
+import { checkTunPreflight } from '@/services/cmds'
 import { showNotice } from '@/services/notice-service'
 
 interface ProxySwitchProps {
@@ -150,6 +151,26 @@ const ProxyControlSwitches = ({
       showErrorNotice(msgKey)
       throw new Error(t(msgKey))
     }
+    if (value) {
+      const report = await checkTunPreflight()
+      const describe = (status: ITunPreflightCheck['status']) =>
+        report?.checks
+          .filter((check) => check.status === status)
+          .map((check) => `${check.detail} (${check.hint})`)
+          .join('; ')
+      if (report && !report.ready) {
+        const msgKey = 'settings.sections.proxyControl.messages.preflightFailed'
+        showNotice.error(msgKey, describe('fail'))
+        throw new Error(t(msgKey))
+      }
+      const warnings = describe('warn')
+      if (warnings) {
+        showNotice.info(
+          'settings.sections.proxyControl.messages.preflightWarning',
+          warnings,
+        )
+      }
+    }
     mutateVerge({ ...verge, enable_tun_mode: value }, false)
     await patchVerge({ enable_tun_mode: value })
   }
@@ -7,7 +7,7 @@ import { showNotice } from '@/services/notice-service'
 import { useVerge } from './use-verge'
 
 export interface SystemState {
-  runningMode: 'Sidecar' | 'Service'
+  runningMode: 'Sidecar' | 'Service' | 'External'
   isAdminMode: boolean
   isServiceOk: boolean
 }
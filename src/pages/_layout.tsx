@@ -33,6 +33,7 @@ import iconDark from '@/assets/image/icon_dark.svg?react'
 import iconLight from '@/assets/image/icon_light.svg?react'
 import LogoSvg from '@/assets/image/logo.svg?react'
 import { BaseErrorBoundary } from '@/components/base'
+import { DeepLinkConfirm } from '@/components/layout/deep-link-confirm'
 import { LayoutItem } from '@/components/layout/layout-item'
 import { LayoutTraffic } from '@/components/layout/layout-traffic'
 import { NoticeManager } from '@/components/layout/notice-manager'
@@ -278,6 +279,7 @@ const Layout = () => {
       <ThemeProvider theme={theme}>
         {/* 左侧底部窗口控制按钮 */}
         <NoticeManager position={verge?.notice_position} />
+        <DeepLinkConfirm />
         <div
           style={{
             animation: 'fadeIn 0.5s',
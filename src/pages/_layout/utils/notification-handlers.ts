@@ -24,6 +24,9 @@ export const handleNoticeMessage = (
       showNotice.error(msg)
     },
     'set_config::error': () => showNotice.error(msg),
+    'deep_link::ok': () =>
+      showNotice.success('shared.feedback.notifications.importSuccess'),
+    'deep_link::error': () => showNotice.error(msg),
     update_with_clash_proxy: () =>
       showNotice.success(
         'settings.feedback.notifications.updater.withClashProxySuccess',
@@ -36,6 +39,38 @@ export const handleNoticeMessage = (
       ),
     'reactivate_profiles::error': () => showNotice.error(msg),
     update_failed: () => showNotice.error(msg),
+    'core_supervisor::restarting': () => showNotice.error(msg),
+    'core_supervisor::crash_loop': () => showNotice.error(msg),
+    'core_supervisor::restart_failed': () => showNotice.error(msg),
+    'core_watchdog::recovering': () => showNotice.info(msg),
+    'core_watchdog::recover_failed': () => showNotice.error(msg),
+    'core_watchdog::unhealthy': () => showNotice.error(msg),
+    'external_controller::weak_secret': () =>
+      showNotice.error(
+        'settings.sections.externalController.messages.weakSecretNotice',
+      ),
+    'tun_preflight::failed': () =>
+      showNotice.error(
+        'settings.sections.proxyControl.messages.preflightFailed',
+        msg,
+      ),
+    'tun_preflight::warning': () =>
+      showNotice.info(
+        'settings.sections.proxyControl.messages.preflightWarning',
+        msg,
+      ),
+    'proxy_guard::conflict': () =>
+      showNotice.error('settings.modals.sysproxy.messages.guardConflict', msg),
+    'sysproxy::bypass_ignored': () =>
+      showNotice.error('settings.modals.sysproxy.messages.bypassIgnored', msg),
+    'config_rollback::restored': () => showNotice.error(msg),
+    'config_rollback::failed': () => showNotice.error(msg),
+    'port_conflict::reassigned': () => showNotice.info(msg),
+    'port_conflict::refused': () => showNotice.error(msg),
+    'service_upgrade::available': () => showNotice.info(msg),
+    'service_upgrade::started': () => showNotice.info(msg),
+    'service_upgrade::completed': () => showNotice.success(msg),
+    'service_upgrade::failed': () => showNotice.error(msg),
     'config_validate::boot_error': () =>
       showNotice.error('shared.feedback.validation.config.bootFailed', msg),
     'config_validate::core_change': () =>
@@ -9,6 +9,18 @@ export async function copyClashEnv() {
   return invoke<void>('copy_clash_env')
 }
 
+export async function getEnvExport(envType: string, unset = false) {
+  return invoke<string>('get_env_export', { envType, unset })
+}
+
+export async function writeEnvExport(
+  envType: string,
+  path?: string,
+  unset = false,
+) {
+  return invoke<string>('write_env_export', { envType, path, unset })
+}
+
 export async function getProfiles() {
   return invoke<IProfilesConfig>('get_profiles')
 }
@@ -40,6 +52,14 @@ export async function saveProfileFile(index: string, fileData: string) {
   return invoke<void>('save_profile_file', { index, fileData })
 }
 
+export async function getPendingDeepLinks() {
+  return invoke<IDeepLinkPreview[]>('get_pending_deep_links')
+}
+
+export async function respondDeepLink(id: string, accept: boolean) {
+  return invoke<void>('respond_deep_link', { id, accept })
+}
+
 export async function importProfile(url: string, option?: IProfileOption) {
   return invoke<void>('import_profile', {
     url,
@@ -47,6 +67,10 @@ export async function importProfile(url: string, option?: IProfileOption) {
   })
 }
 
+export async function importWasmItem(filePath: string, name?: string) {
+  return invoke<string>('import_wasm_item', { filePath, name })
+}
+
 export async function reorderProfile(activeId: string, overId: string) {
   return invoke<void>('reorder_profile', {
     activeId,
@@ -96,6 +120,14 @@ export async function getRuntimeProxyChainConfig(proxyChainExitNode: string) {
   })
 }
 
+export async function revertRuntimeConfig() {
+  return invoke<void>('revert_runtime_config')
+}
+
+export async function getLastConfigRollback() {
+  return invoke<IConfigRollback | null>('get_last_config_rollback')
+}
+
 export async function updateProxyChainConfigInRuntime(proxyChainConfig: any) {
   return invoke<void>('update_proxy_chain_config_in_runtime', {
     proxyChainConfig,
@@ -106,6 +138,14 @@ export async function patchClashConfig(payload: Partial<IConfigData>) {
   return invoke<void>('patch_clash_config', { payload })
 }
 
+export async function rotateClashSecret() {
+  return invoke<string>('rotate_clash_secret')
+}
+
+export async function checkSecretStrength(secret: string) {
+  return invoke<ISecretStrength>('check_secret_strength', { secret })
+}
+
 export async function patchClashMode(payload: string) {
   return invoke<void>('patch_clash_mode', { payload })
 }
@@ -243,6 +283,10 @@ export async function getClashLogs() {
   }, [])
 }
 
+export async function queryClashLogs(query: ICoreLogQuery = {}) {
+  return invoke<ICoreLogEntry[]>('query_clash_logs', { query })
+}
+
 export async function clearLogs() {
   return invoke<void>('clear_logs')
 }
@@ -251,10 +295,22 @@ export async function getVergeConfig() {
   return invoke<IVergeConfig>('get_verge_config')
 }
 
+export async function getBuiltinTransforms() {
+  return invoke<IBuiltinTransform[]>('get_builtin_transforms')
+}
+
 export async function patchVergeConfig(payload: IVergeConfig) {
   return invoke<void>('patch_verge_config', { payload })
 }
 
+export async function createAutomationToken(name: string) {
+  return invoke<IVergeApiToken>('create_automation_token', { name })
+}
+
+export async function revokeAutomationToken(name: string) {
+  return invoke<void>('revoke_automation_token', { name })
+}
+
 export async function getSystemProxy() {
   return invoke<{
     enable: boolean
@@ -263,6 +319,26 @@ export async function getSystemProxy() {
   }>('get_sys_proxy')
 }
 
+export async function getPacReport() {
+  return invoke<IPacReport>('get_pac_report')
+}
+
+export async function checkBypass(bypass: string) {
+  return invoke<IBypassReport>('check_bypass', { bypass })
+}
+
+export async function getBypassPresets() {
+  return invoke<IBypassPresetInfo[]>('get_bypass_presets')
+}
+
+export async function getProxyGuardHistory() {
+  return invoke<IProxyDeviation[]>('get_proxy_guard_history')
+}
+
+export async function clearProxyGuardHistory() {
+  return invoke<void>('clear_proxy_guard_history')
+}
+
 export async function getAutotemProxy() {
   try {
     debugLog('[API] 开始调用 get_auto_proxy')
@@ -294,6 +370,26 @@ export async function changeClashCore(clashCore: string) {
   return invoke<string | null>('change_clash_core', { clashCore })
 }
 
+export async function installManagedCore(
+  source: IManagedCoreSource,
+  version: string,
+  sha256?: string,
+) {
+  return invoke<IManagedCore>('install_managed_core', {
+    source,
+    version,
+    sha256,
+  })
+}
+
+export async function listManagedCores() {
+  return invoke<IManagedCore[]>('list_managed_cores')
+}
+
+export async function removeManagedCore(version: string) {
+  return invoke<void>('remove_managed_core', { version })
+}
+
 export async function startCore() {
   return invoke<void>('start_core')
 }
@@ -306,6 +402,14 @@ export async function restartCore() {
   return invoke<void>('restart_core')
 }
 
+export async function getCoreRestartStats() {
+  return invoke<ICoreRestartStats>('get_core_restart_stats')
+}
+
+export async function getCoreHealthHistory() {
+  return invoke<ICoreHealthRecord[]>('get_core_health_history')
+}
+
 export async function restartApp() {
   return invoke<void>('restart_app')
 }
@@ -370,6 +474,10 @@ export async function cmdTestDelay(url: string) {
   return invoke<number>('test_delay', { url })
 }
 
+export async function checkTunPreflight() {
+  return invoke<ITunPreflightReport | null>('check_tun_preflight')
+}
+
 export async function invoke_uwp_tool() {
   return invoke<void>('invoke_uwp_tool').catch((err) =>
     showNotice.error(err, 1500),
@@ -431,6 +539,26 @@ export async function getNetworkInterfacesInfo() {
   return invoke<INetworkInterface[]>('get_network_interfaces_info')
 }
 
+export async function getLanSharing() {
+  return invoke<ILanSharingInfo>('get_lan_sharing')
+}
+
+export async function patchLanSharing(patch: ILanSharingPatch) {
+  return invoke<void>('patch_lan_sharing', { patch })
+}
+
+export async function addLanUser(name: string) {
+  return invoke<ILanOnboarding>('add_lan_user', { name })
+}
+
+export async function removeLanUser(username: string) {
+  return invoke<void>('remove_lan_user', { username })
+}
+
+export async function getLanOnboarding(username: string) {
+  return invoke<ILanOnboarding>('get_lan_onboarding', { username })
+}
+
 export async function createWebdavBackup() {
   return invoke<void>('create_webdav_backup')
 }
@@ -525,6 +653,14 @@ export const repairService = async () => {
   return invoke<void>('repair_service')
 }
 
+export const upgradeService = async () => {
+  return invoke<void>('upgrade_service')
+}
+
+export const getServiceNegotiation = async () => {
+  return invoke<IServiceNegotiation>('get_service_negotiation')
+}
+
 // 系统服务是否可用
 export const isServiceAvailable = async () => {
   try {
@@ -109,6 +109,18 @@ export const translationKeys = [
   'layout.components.navigation.menu.lock',
   'layout.components.navigation.menu.collapseNavBar',
   'layout.components.navigation.menu.expandNavBar',
+  'layout.components.deepLink.title',
+  'layout.components.deepLink.description',
+  'layout.components.deepLink.actions.import-profile',
+  'layout.components.deepLink.actions.import-merge',
+  'layout.components.deepLink.actions.import-script',
+  'layout.components.deepLink.actions.switch-profile',
+  'layout.components.deepLink.actions.set-mode',
+  'layout.components.deepLink.fields.action',
+  'layout.components.deepLink.fields.host',
+  'layout.components.deepLink.fields.name',
+  'layout.components.deepLink.fields.size',
+  'layout.components.deepLink.trustHost',
   'logs.page.title',
   'logs.actions.showDescending',
   'logs.actions.showAscending',
@@ -333,6 +345,8 @@ export const translationKeys = [
   'settings.sections.proxyControl.actions.uninstallService',
   'settings.sections.proxyControl.fields.systemProxy',
   'settings.sections.proxyControl.fields.tunMode',
+  'settings.sections.proxyControl.messages.preflightFailed',
+  'settings.sections.proxyControl.messages.preflightWarning',
   'settings.sections.externalController.title',
   'settings.sections.externalController.fields.enable',
   'settings.sections.externalController.fields.address',
@@ -340,11 +354,17 @@ export const translationKeys = [
   'settings.sections.externalController.placeholders.address',
   'settings.sections.externalController.placeholders.secret',
   'settings.sections.externalController.tooltips.copy',
+  'settings.sections.externalController.tooltips.rotate',
   'settings.sections.externalController.messages.addressRequired',
   'settings.sections.externalController.messages.secretRequired',
   'settings.sections.externalController.messages.copyFailed',
   'settings.sections.externalController.messages.controllerCopied',
   'settings.sections.externalController.messages.secretCopied',
+  'settings.sections.externalController.messages.secretRotated',
+  'settings.sections.externalController.messages.emptySecret',
+  'settings.sections.externalController.messages.defaultSecret',
+  'settings.sections.externalController.messages.weakSecret',
+  'settings.sections.externalController.messages.weakSecretNotice',
   'settings.sections.externalCors.title',
   'settings.sections.externalCors.fields.allowPrivateNetwork',
   'settings.sections.externalCors.fields.allowedOrigins',
@@ -389,6 +409,7 @@ export const translationKeys = [
   'settings.sections.clash.form.tooltips.unifiedDelay',
   'settings.sections.clash.form.tooltips.logLevel',
   'settings.sections.clash.form.tooltips.openUwpTool',
+  'settings.sections.clash.form.tooltips.lanSharing',
   'settings.sections.clash.form.options.logLevel.debug',
   'settings.sections.clash.form.options.logLevel.info',
   'settings.sections.clash.form.options.logLevel.warning',
@@ -580,6 +601,8 @@ export const translationKeys = [
   'settings.modals.sysproxy.fields.usePacMode',
   'settings.modals.sysproxy.fields.proxyGuard',
   'settings.modals.sysproxy.fields.guardDuration',
+  'settings.modals.sysproxy.fields.guardDeviations',
+  'settings.modals.sysproxy.fields.bypassPresets',
   'settings.modals.sysproxy.fields.alwaysUseDefaultBypass',
   'settings.modals.sysproxy.fields.enableBypassCheck',
   'settings.modals.sysproxy.fields.proxyBypass',
@@ -587,9 +610,15 @@ export const translationKeys = [
   'settings.modals.sysproxy.fields.pacScriptContent',
   'settings.modals.sysproxy.tooltips.proxyGuard',
   'settings.modals.sysproxy.messages.durationTooShort',
+  'settings.modals.sysproxy.messages.invalidBypassEntry',
   'settings.modals.sysproxy.messages.invalidBypass',
   'settings.modals.sysproxy.messages.invalidProxyHost',
+  'settings.modals.sysproxy.messages.guardConflict',
+  'settings.modals.sysproxy.presets.lan',
+  'settings.modals.sysproxy.presets.intranet',
+  'settings.modals.sysproxy.presets.domestic-direct',
   'settings.modals.sysproxy.actions.editPac',
+  'settings.modals.sysproxy.actions.clearGuardHistory',
   'settings.modals.tun.title',
   'settings.modals.tun.fields.stack',
   'settings.modals.tun.fields.device',
@@ -672,6 +701,23 @@ export const translationKeys = [
   'settings.modals.networkInterface.title',
   'settings.modals.networkInterface.fields.ipAddress',
   'settings.modals.networkInterface.fields.macAddress',
+  'settings.modals.lanSharing.title',
+  'settings.modals.lanSharing.interface',
+  'settings.modals.lanSharing.auto',
+  'settings.modals.lanSharing.skipAuthPrefixes',
+  'settings.modals.lanSharing.lanAllowedIps',
+  'settings.modals.lanSharing.lanDisallowedIps',
+  'settings.modals.lanSharing.devices',
+  'settings.modals.lanSharing.deviceName',
+  'settings.modals.lanSharing.onboarding',
+  'settings.modals.lanSharing.fields.host',
+  'settings.modals.lanSharing.fields.username',
+  'settings.modals.lanSharing.fields.password',
+  'settings.modals.lanSharing.fields.httpProxy',
+  'settings.modals.lanSharing.fields.socksProxy',
+  'settings.modals.lanSharing.fields.pacUrl',
+  'settings.modals.lanSharing.messages.allowLanDisabled',
+  'settings.modals.lanSharing.actions.addDevice',
   'settings.feedback.notifications.clash.restartSuccess',
   'settings.feedback.notifications.clash.versionUpdated',
   'settings.feedback.notifications.clash.alreadyLatestVersion',
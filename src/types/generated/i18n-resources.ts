@@ -174,6 +174,24 @@ export interface TranslationResources {
     }
     layout: {
       components: {
+        deepLink: {
+          actions: {
+            'import-merge': string
+            'import-profile': string
+            'import-script': string
+            'set-mode': string
+            'switch-profile': string
+          }
+          description: string
+          fields: {
+            action: string
+            host: string
+            name: string
+            size: string
+          }
+          title: string
+          trustHost: string
+        }
         navigation: {
           menu: {
             collapseNavBar: string
@@ -918,6 +936,31 @@ export interface TranslationResources {
             enableGlobal: string
           }
         }
+        lanSharing: {
+          actions: {
+            addDevice: string
+          }
+          auto: string
+          deviceName: string
+          devices: string
+          fields: {
+            host: string
+            httpProxy: string
+            pacUrl: string
+            password: string
+            socksProxy: string
+            username: string
+          }
+          interface: string
+          lanAllowedIps: string
+          lanDisallowedIps: string
+          messages: {
+            allowLanDisabled: string
+          }
+          onboarding: string
+          skipAuthPrefixes: string
+          title: string
+        }
         liteMode: {
           actions: {
             enterNow: string
@@ -982,13 +1025,16 @@ export interface TranslationResources {
         }
         sysproxy: {
           actions: {
+            clearGuardHistory: string
             editPac: string
           }
           fields: {
             alwaysUseDefaultBypass: string
             bypass: string
+            bypassPresets: string
             enableBypassCheck: string
             enableStatus: string
+            guardDeviations: string
             guardDuration: string
             pacScriptContent: string
             pacUrl: string
@@ -1003,9 +1049,16 @@ export interface TranslationResources {
           }
           messages: {
             durationTooShort: string
+            guardConflict: string
             invalidBypass: string
+            invalidBypassEntry: string
             invalidProxyHost: string
           }
+          presets: {
+            'domestic-direct': string
+            intranet: string
+            lan: string
+          }
           title: string
           tooltips: {
             proxyGuard: string
@@ -1119,6 +1172,7 @@ export interface TranslationResources {
               }
             }
             tooltips: {
+              lanSharing: string
               logLevel: string
               networkInterface: string
               openUwpTool: string
@@ -1137,8 +1191,13 @@ export interface TranslationResources {
             addressRequired: string
             controllerCopied: string
             copyFailed: string
+            defaultSecret: string
+            emptySecret: string
             secretCopied: string
             secretRequired: string
+            secretRotated: string
+            weakSecret: string
+            weakSecretNotice: string
           }
           placeholders: {
             address: string
@@ -1147,6 +1206,7 @@ export interface TranslationResources {
           title: string
           tooltips: {
             copy: string
+            rotate: string
           }
         }
         externalCors: {
@@ -1177,6 +1237,10 @@ export interface TranslationResources {
             systemProxy: string
             tunMode: string
           }
+          messages: {
+            preflightFailed: string
+            preflightWarning: string
+          }
           tooltips: {
             systemProxy: string
             tunMode: string
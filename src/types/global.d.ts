@@ -261,7 +261,7 @@ interface IClashInfo {
 
 interface IProfileItem {
   uid: string
-  type?: 'local' | 'remote' | 'merge' | 'script'
+  type?: 'local' | 'remote' | 'merge' | 'script' | 'wasm'
   name?: string
   desc?: string
   file?: string
@@ -279,8 +279,15 @@ interface IProfileItem {
   }
   option?: IProfileOption
   home?: string
+  tags?: string[]
 }
 
+type IProfileScope =
+  | { type: 'all' }
+  | { type: 'uids'; uids: string[] }
+  | { type: 'tag'; tag: string }
+  | { type: 'pattern'; pattern: string }
+
 interface IProfileOption {
   user_agent?: string
   with_proxy?: boolean
@@ -294,6 +301,8 @@ interface IProfileOption {
   rules?: string
   proxies?: string
   groups?: string
+  wasm?: string
+  scope?: IProfileScope
 }
 
 interface IProfilesConfig {
@@ -885,6 +894,109 @@ interface IProxyConfig
     | 'sudoku'
 }
 
+interface ICoreRestartStats {
+  restart_count: number
+  crash_count: number
+  last_exit_reason?: string | null
+  last_crash_at?: number | null
+  crash_looping: boolean
+}
+
+type IManagedCoreSource =
+  | { type: 'url'; url: string }
+  | { type: 'file'; path: string }
+
+interface IManagedCore {
+  id: string
+  version: string
+  sha256: string
+  source: string
+  installed_at: number
+  reported_version?: string | null
+}
+
+interface IConfigRollback {
+  timestamp: number
+  reason: string
+  manual: boolean
+  restored: boolean
+  snapshot_at?: number | null
+}
+
+interface IServiceVersion {
+  protocol: number
+  build: string
+}
+
+type IServiceNegotiation =
+  | {
+      status: 'compatible' | 'upgrade-available'
+      client: IServiceVersion
+      service: IServiceVersion
+    }
+  | {
+      status: 'upgrade-required'
+      client: IServiceVersion
+      service?: IServiceVersion | null
+      reason: string
+    }
+  | { status: 'unreachable'; reason: string }
+
+type ICoreLogLevel = 'debug' | 'info' | 'warning' | 'error' | 'fatal'
+
+interface ICoreLogConnection {
+  network: string
+  source: string
+  process?: string | null
+  destination: string
+  rule?: string | null
+  rule_payload?: string | null
+  proxy: string
+}
+
+interface ICoreLogEntry {
+  seq: number
+  run: number
+  timestamp?: number | null
+  level: ICoreLogLevel
+  component?: string | null
+  message: string
+  connection?: ICoreLogConnection | null
+}
+
+interface ICoreLogQuery {
+  levels?: ICoreLogLevel[]
+  since?: number
+  until?: number
+  keyword?: string
+  regex?: boolean
+  component?: string
+  network?: string
+  source?: string
+  destination?: string
+  process?: string
+  rule?: string
+  proxy?: string
+  limit?: number
+}
+
+interface ICoreHealthRecord {
+  timestamp: number
+  ok: boolean
+  latency_ms?: number | null
+  version?: string | null
+  error?: string | null
+  action?: 'reload' | 'restart-core' | 'switch-mode' | null
+  running_mode: string
+}
+
+interface IBuiltinTransform {
+  uid: string
+  description: string
+}
+
+type IPortConflictStrategy = 'auto' | 'refuse'
+
 interface IVergeConfig {
   app_log_level?: 'trace' | 'debug' | 'info' | 'warn' | 'error' | string
   app_log_max_size?: number // KB
@@ -896,7 +1008,21 @@ interface IVergeConfig {
     | 'system_proxy'
     | 'tun_mode'
     | string
-  env_type?: 'bash' | 'cmd' | 'powershell' | 'fish' | string
+  env_type?:
+    | 'bash'
+    | 'zsh'
+    | 'fish'
+    | 'nushell'
+    | 'cmd'
+    | 'powershell'
+    | 'docker-daemon'
+    | 'docker-client'
+    | 'git'
+    | 'npm'
+    | 'pip'
+    | 'cargo'
+    | 'systemd'
+    | string
   startup_script?: string
   start_page?: string
   clash_core?: string
@@ -927,6 +1053,8 @@ interface IVergeConfig {
   enable_dns_settings?: boolean
   proxy_auto_config?: boolean
   pac_file_content?: string
+  pac_generate_from_rules?: boolean
+  pac_rule_limit?: number
   proxy_host?: string
   enable_random_port?: boolean
   verge_mixed_port?: number
@@ -943,6 +1071,7 @@ interface IVergeConfig {
   use_default_bypass?: boolean
   proxy_guard_duration?: number
   system_proxy_bypass?: string
+  system_proxy_bypass_presets?: Array<IBypassPresetInfo['id']>
   web_ui_list?: string[]
   hotkeys?: string[]
   theme_setting?: {
@@ -967,21 +1096,159 @@ interface IVergeConfig {
   enable_auto_delay_detection?: boolean
   auto_delay_detection_interval_minutes?: number
   enable_builtin_enhanced?: boolean
+  disabled_builtin_enhanced?: string[]
+  enable_enhance_cache?: boolean
   auto_log_clean?: 0 | 1 | 2 | 3 | 4
   enable_auto_backup_schedule?: boolean
   auto_backup_interval_hours?: number
   auto_backup_on_change?: boolean
+  enable_core_watchdog?: boolean
+  core_watchdog_interval?: number
+  core_watchdog_threshold?: number
+  core_watchdog_max_latency?: number
   proxy_layout_column?: number
   test_list?: IVergeTestItem[]
   webdav_url?: string
   webdav_username?: string
   webdav_password?: string
+  variables?: Record<string, string>
+  secret_variables?: Record<string, string>
+  auto_upgrade_service?: boolean
+  core_log_buffer_size?: number
+  port_conflict_strategy?: IPortConflictStrategy
+  enable_external_core?: boolean
+  external_core_url?: string
+  external_core_socket?: string
+  external_core_secret?: string
+  enable_automation_api?: boolean
+  automation_api_tokens?: IVergeApiToken[]
+  lan_sharing_users?: ILanUser[]
+  lan_sharing_interface?: string
+  deep_link_trusted_domains?: string[]
   home_cards?: Record<string, boolean>
   enable_hover_jump_navigator?: boolean
   hover_jump_navigator_delay?: number
   enable_external_controller?: boolean
 }
 
+interface IVergeApiToken {
+  name: string
+  token: string
+  created_at: number
+}
+
+type ISecretStrength = 'empty' | 'default' | 'weak' | 'strong'
+
+interface ITunPreflightCheck {
+  id: string
+  status: 'pass' | 'warn' | 'fail'
+  detail: string
+  hint?: string | null
+}
+
+interface ITunPreflightReport {
+  ready: boolean
+  checks: ITunPreflightCheck[]
+}
+
+interface ILanUser {
+  name: string
+  username: string
+  password: string
+  created_at: number
+}
+
+interface ILanSharingInfo {
+  allow_lan: boolean
+  interface?: string
+  host?: string
+  port: number
+  users: Omit<ILanUser, 'password'>[]
+  skip_auth_prefixes: string[]
+  lan_allowed_ips: string[]
+  lan_disallowed_ips: string[]
+}
+
+interface ILanSharingPatch {
+  allow_lan?: boolean
+  interface?: string
+  skip_auth_prefixes?: string[]
+  lan_allowed_ips?: string[]
+  lan_disallowed_ips?: string[]
+}
+
+interface ILanOnboarding {
+  name: string
+  host: string
+  port: number
+  username: string
+  password: string
+  http_proxy: string
+  socks_proxy: string
+  pac_url: string
+}
+
+interface IDeepLinkPreview {
+  id: string
+  action:
+    | 'import-profile'
+    | 'import-merge'
+    | 'import-script'
+    | 'switch-profile'
+    | 'set-mode'
+  host?: string
+  name?: string
+  size?: number
+}
+
+interface IPacReport {
+  mode: string
+  total: number
+  compiled: number
+  truncated: number
+  limit: number
+  unsupported: Record<string, number>
+  unreachable: number
+}
+
+interface IBypassError {
+  entry: string
+  start: number
+  end: number
+  reason: string
+}
+
+interface IBypassReport {
+  normalized: string
+  errors: IBypassError[]
+  duplicates: string[]
+}
+
+interface IBypassPresetInfo {
+  id: 'lan' | 'intranet' | 'domestic-direct'
+  entries: string[]
+}
+
+type IProxySnapshot =
+  | {
+      mode: 'sysproxy'
+      enable: boolean
+      host: string
+      port: number
+      bypass: string
+    }
+  | { mode: 'autoproxy'; enable: boolean; url: string }
+
+interface IProxyDeviation {
+  timestamp: number
+  expected: IProxySnapshot
+  found: IProxySnapshot
+  changed: string[]
+  action: 'reapplied' | 'throttled' | 'failed'
+  streak: number
+  error?: string
+}
+
 interface IWebDavFile {
   filename: string
   href: string